@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use gb7_core::{cartridge, gameboy::Gameboy};
+
+fn load(rom: &str) -> Gameboy {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("../gb7-core/resources");
+    path.push(rom);
+    Gameboy::new_dmg(cartridge::load_from_path(&path))
+}
+
+// Whole frames of emulation, reported in frames per second
+fn frames(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frames");
+    group.throughput(Throughput::Elements(1));
+
+    for (name, rom) in [("dmg-acid2", "dmg-acid2.gb"), ("cpu_instrs", "blargg/09-op r,r.gb")] {
+        let mut gb = load(rom);
+        group.bench_function(name, |b| b.iter(|| gb.execute_frame()));
+
+        let mut gb = load(rom);
+        gb.cycle_accurate = true;
+        group.bench_function(format!("{}-accurate", name), |b| b.iter(|| gb.execute_frame()));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, frames);
+criterion_main!(benches);
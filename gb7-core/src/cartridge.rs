@@ -1,16 +1,27 @@
-use std::{fs, path::Path};
+use std::{fs, io, path::Path};
 use std::fs::{File, OpenOptions};
 use std::path::PathBuf;
 
 use enum_dispatch::enum_dispatch;
 use memmap2::MmapMut;
 
+use crate::savestate::{self, Snapshot, StateReader, StateWriter};
+
 const RAM_SIZES: [usize; 6] = [0, 0, 8192, 32768, 131072, 65536];
 
 #[enum_dispatch(Cartridge)]
 pub trait CartMemory {
     fn read(&self, addr: u16) -> u8;
     fn write(&mut self, addr: u16, val: u8);
+    fn rom(&self) -> &[u8];
+
+    // Stop writing cartridge RAM through to its .sav file, keeping it in memory only
+    fn detach_save(&mut self) {}
+
+    // The ROM bank mapped at 0x4000-0x7FFF
+    fn rom_bank(&self) -> usize {
+        1
+    }
 }
 
 #[enum_dispatch]
@@ -56,6 +67,10 @@ impl CartMemory for NoMBC {
     fn write(&mut self, _: u16, _: u8) {
         // Writing to a cartridge without an MBC does nothing
     }
+
+    fn rom(&self) -> &[u8] {
+        &self.rom
+    }
 }
 
 pub struct MBC1 {
@@ -96,6 +111,14 @@ impl CartMemory for MBC1 {
         }
     }
 
+    fn rom_bank(&self) -> usize {
+        self.active_rom_bank
+    }
+
+    fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
     fn write(&mut self, addr: u16, value: u8) {
         if addr < 0x2000 {
             // Writing to addresses 0x0000 to 0x1fff sets the external RAM active state
@@ -175,6 +198,20 @@ impl CartMemory for MBC3 {
         }
     }
 
+    fn rom_bank(&self) -> usize {
+        self.active_rom_bank
+    }
+
+    fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    fn detach_save(&mut self) {
+        let mut ram = MmapMut::map_anon(self.ram.len()).expect("Could not allocate cartridge RAM");
+        ram.copy_from_slice(&self.ram);
+        self.ram = ram;
+    }
+
     fn write(&mut self, addr: u16, value: u8) {
         match addr {
             0x0000..=0x1FFF => self.ram_active = value == 0x0A,
@@ -194,3 +231,52 @@ impl CartMemory for MBC3 {
         }
     }
 }
+
+impl Snapshot for Cartridge {
+    fn save(&self, w: &mut StateWriter) {
+        match self {
+            Cartridge::NoMBC(_) => {}
+            Cartridge::MBC1(cart) => {
+                save_banks(w, cart.active_rom_bank, cart.active_ram_bank, cart.ram_active, cart.banking_mode);
+                w.vec(&cart.ram);
+            }
+            Cartridge::MBC3(cart) => {
+                save_banks(w, cart.active_rom_bank, cart.active_ram_bank, cart.ram_active, cart.banking_mode);
+                w.vec(&cart.ram);
+            }
+        }
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        match self {
+            Cartridge::NoMBC(_) => Ok(()),
+            Cartridge::MBC1(cart) => {
+                let (rom_banks, ram_banks) = (cart.rom.len() / 16384, cart.ram.len().div_ceil(16384));
+                (cart.active_rom_bank, cart.active_ram_bank, cart.ram_active, cart.banking_mode) = load_banks(r, rom_banks, ram_banks)?;
+                r.vec_into(&mut cart.ram)
+            }
+            Cartridge::MBC3(cart) => {
+                let (rom_banks, ram_banks) = (cart.rom.len() / 16384, cart.ram.len().div_ceil(16384));
+                (cart.active_rom_bank, cart.active_ram_bank, cart.ram_active, cart.banking_mode) = load_banks(r, rom_banks, ram_banks)?;
+                r.vec_into(&mut cart.ram)
+            }
+        }
+    }
+}
+
+fn save_banks(w: &mut StateWriter, rom_bank: usize, ram_bank: usize, ram_active: bool, banking_mode: bool) {
+    w.u16(rom_bank as u16);
+    w.u8(ram_bank as u8);
+    w.bool(ram_active);
+    w.bool(banking_mode);
+}
+
+// Bank 0 is always accepted, even without RAM, as that's what a cartridge starts with
+fn load_banks(r: &mut StateReader, rom_banks: usize, ram_banks: usize) -> io::Result<(usize, usize, bool, bool)> {
+    let rom_bank = r.u16()? as usize;
+    let ram_bank = r.u8()? as usize;
+    if rom_bank >= rom_banks || ram_bank >= ram_banks.max(1) {
+        return Err(savestate::invalid("save state selects a bank the cartridge doesn't have"));
+    }
+    Ok((rom_bank, ram_bank, r.bool()?, r.bool()?))
+}
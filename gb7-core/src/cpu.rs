@@ -1,15 +1,26 @@
+use std::io;
+
 use bitflags::bitflags;
 
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
 #[derive(Default)]
 pub struct Cpu {
     // Registers exist in their own struct
     pub registers: CpuRegisters,
 
-    // Stack pointer, program counter, interrupt enable, halted
+    // Stack pointer, program counter, interrupt enable, halted, stopped
     pub sp: u16,
     pub pc: u16,
     pub ime: bool,
     pub halted: bool,
+    pub stopped: bool,
+
+    // EI takes effect after the following instruction
+    pub ime_pending: bool,
+    // HALT was executed with IME off and an interrupt pending, so the next opcode fetch
+    // doesn't increment PC
+    pub halt_bug: bool,
 }
 
 impl Cpu {
@@ -70,6 +81,16 @@ impl Cpu {
         self.sp = 0xFFFE;
         self.pc = 0x0100;
     }
+
+    pub fn init_sgb(&mut self) {
+        // Set the register states to those after the SGB bootrom
+        self.registers.set_af(0x0100);
+        self.registers.set_bc(0x0014);
+        self.registers.set_de(0x0000);
+        self.registers.set_hl(0xC060);
+        self.sp = 0xFFFE;
+        self.pc = 0x0100;
+    }
 }
 
 #[derive(Default)]
@@ -150,3 +171,39 @@ bitflags! {
         const C = 0b0001_0000;
     }
 }
+
+impl Snapshot for Cpu {
+    fn save(&self, w: &mut StateWriter) {
+        let r = &self.registers;
+        w.bytes(&[r.a, r.flags.bits, r.b, r.c, r.d, r.e, r.h, r.l]);
+        w.u16(self.sp);
+        w.u16(self.pc);
+        for flag in [self.ime, self.halted, self.stopped, self.ime_pending, self.halt_bug] {
+            w.bool(flag);
+        }
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        let mut registers = [0; 8];
+        r.bytes(&mut registers)?;
+        let [a, f, b, c, d, e, h, l] = registers;
+        self.registers = CpuRegisters {
+            a,
+            flags: CpuFlags::from_bits_truncate(f),
+            b,
+            c,
+            d,
+            e,
+            h,
+            l,
+        };
+        self.sp = r.u16()?;
+        self.pc = r.u16()?;
+        self.ime = r.bool()?;
+        self.halted = r.bool()?;
+        self.stopped = r.bool()?;
+        self.ime_pending = r.bool()?;
+        self.halt_bug = r.bool()?;
+        Ok(())
+    }
+}
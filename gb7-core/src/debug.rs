@@ -0,0 +1,285 @@
+use crate::{
+    lcd::Palette,
+    memory::{IORegs, Oam, VideoMem, VideoRam},
+};
+
+const BACKGROUND: [u8; 4] = [32, 32, 32, 255];
+const OVERLAY: [u8; 4] = [255, 0, 0, 255];
+const TEXT: [u8; 4] = [230, 230, 230, 255];
+
+// An RGBA image of VRAM contents for inspection tools
+pub struct DebugImage {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl DebugImage {
+    fn new(width: usize, height: usize) -> Self {
+        let mut data = vec![0; width * height * 4];
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&BACKGROUND);
+        }
+        DebugImage { width, height, data }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        let i = (y * self.width + x) * 4;
+        [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]]
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: [u8; 4]) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 4;
+            self.data[i..i + 4].copy_from_slice(&color);
+        }
+    }
+
+    // Outline a rectangle, wrapping around the image edges like the tile maps do
+    fn draw_wrapping_rect(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        for dx in 0..width {
+            self.set_pixel((x + dx) % self.width, y % self.height, OVERLAY);
+            self.set_pixel((x + dx) % self.width, (y + height - 1) % self.height, OVERLAY);
+        }
+        for dy in 0..height {
+            self.set_pixel(x % self.width, (y + dy) % self.height, OVERLAY);
+            self.set_pixel((x + width - 1) % self.width, (y + dy) % self.height, OVERLAY);
+        }
+    }
+
+    fn draw_text(&mut self, x: usize, y: usize, text: &str) {
+        for (i, c) in text.chars().enumerate() {
+            let rows = glyph(c);
+            for (dy, row) in rows.iter().enumerate() {
+                for dx in 0..3 {
+                    if row & (0b100 >> dx) != 0 {
+                        self.set_pixel(x + i * 4 + dx, y + dy, TEXT);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// A decoded OAM entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OamEntry {
+    pub index: u8,
+    pub y: u8,
+    pub x: u8,
+    pub tile: u8,
+    pub flags: u8,
+}
+
+impl OamEntry {
+    pub fn background_priority(&self) -> bool {
+        self.flags & 0b1000_0000 != 0
+    }
+
+    pub fn yflip(&self) -> bool {
+        self.flags & 0b0100_0000 != 0
+    }
+
+    pub fn xflip(&self) -> bool {
+        self.flags & 0b0010_0000 != 0
+    }
+
+    // 0 for OBP0, 1 for OBP1
+    pub fn dmg_palette(&self) -> u8 {
+        (self.flags >> 4) & 0b1
+    }
+
+    pub fn cgb_bank(&self) -> u8 {
+        (self.flags >> 3) & 0b1
+    }
+
+    pub fn cgb_palette(&self) -> u8 {
+        self.flags & 0b111
+    }
+}
+
+pub fn oam_entries(oam: &Oam) -> Vec<OamEntry> {
+    oam.iter_entries()
+        .enumerate()
+        .map(|(index, (y, x, tile, flags))| OamEntry {
+            index: index as u8,
+            y,
+            x,
+            tile,
+            flags,
+        })
+        .collect()
+}
+
+// All 384 tiles of 0x8000-0x97FF, 16 tiles per row, with each VRAM bank side by side.
+// Raw colour indices are shown through `palette`, ignoring BGP/OBP.
+pub fn tile_sheet(vram: &VideoRam, palette: &Palette) -> DebugImage {
+    let banks = vram.bank_count() as usize;
+    let mut image = DebugImage::new(128 * banks, 192);
+
+    for bank in 0..banks {
+        for tile in 0..384 {
+            let (tile_x, tile_y) = (bank * 128 + (tile % 16) * 8, (tile / 16) * 8);
+            for row in 0..8 {
+                let indices = tile_row(vram, bank as u8, 0x8000 + tile as u16 * 16, row);
+                for (col, &index) in indices.iter().enumerate() {
+                    image.set_pixel(tile_x + col, tile_y + row as usize, palette.color(index));
+                }
+            }
+        }
+    }
+
+    image
+}
+
+// The full 256x256 tile map at `map_base` (0x9800 or 0x9C00), drawn with the current
+// tile addressing mode and BGP. If the background uses this map, the SCX/SCY viewport
+// is outlined.
+pub fn tile_map(vram: &VideoRam, io_regs: &IORegs, map_base: u16, palette: &Palette) -> DebugImage {
+    let mut image = draw_map(vram, io_regs, map_base, palette);
+
+    let lcdc = io_regs.read(0xFF40);
+    let bg_tilemap: u16 = match lcdc & 0b0000_1000 {
+        0 => 0x9800,
+        _ => 0x9C00,
+    };
+    if bg_tilemap == map_base {
+        let (scy, scx) = (io_regs.read(0xFF42), io_regs.read(0xFF43));
+        image.draw_wrapping_rect(scx as usize, scy as usize, 160, 144);
+    }
+
+    image
+}
+
+// The tile map used by the window, with the part visible on screen outlined
+pub fn window_map(vram: &VideoRam, io_regs: &IORegs, palette: &Palette) -> DebugImage {
+    let lcdc = io_regs.read(0xFF40);
+    let window_tilemap = match lcdc & 0b0100_0000 {
+        0 => 0x9800,
+        _ => 0x9C00,
+    };
+    let mut image = draw_map(vram, io_regs, window_tilemap, palette);
+
+    let (wy, wx) = (io_regs.read(0xFF4A) as usize, io_regs.read(0xFF4B) as usize);
+    if wy < 144 && wx < 167 {
+        let width = 160 - wx.saturating_sub(7);
+        image.draw_wrapping_rect(7usize.saturating_sub(wx), 0, width, 144 - wy);
+    }
+
+    image
+}
+
+// A table of all 40 sprites: each cell shows the sprite with its palette and flips,
+// followed by its X, Y, tile number and flags in hex.
+pub fn oam_table(vram: &VideoRam, oam: &Oam, io_regs: &IORegs, palette: &Palette) -> DebugImage {
+    const CELL_WIDTH: usize = 26;
+    const CELL_HEIGHT: usize = 26;
+    let mut image = DebugImage::new(CELL_WIDTH * 8, CELL_HEIGHT * 5);
+
+    let tall_sprite_mode = io_regs.read(0xFF40) & 0b0000_0100 != 0;
+    let sprite_height = if tall_sprite_mode { 16 } else { 8 };
+
+    for entry in oam_entries(oam) {
+        let cell_x = (entry.index as usize % 8) * CELL_WIDTH;
+        let cell_y = (entry.index as usize / 8) * CELL_HEIGHT;
+
+        let obp = match entry.dmg_palette() {
+            0 => io_regs.read(0xFF48),
+            _ => io_regs.read(0xFF49),
+        };
+        let tile = if tall_sprite_mode { entry.tile & 0xFE } else { entry.tile };
+        let bank = entry.cgb_bank().min(vram.bank_count() - 1);
+
+        for row in 0..sprite_height {
+            let tile_row_num = if entry.yflip() { sprite_height - 1 - row } else { row };
+            let indices = tile_row(vram, bank, 0x8000 + tile as u16 * 16, tile_row_num);
+            for col in 0..8 {
+                let index = indices[if entry.xflip() { 7 - col } else { col }];
+                // Colour 0 is transparent for sprites
+                if index != 0 {
+                    let shade = (obp >> (index * 2)) & 0x3;
+                    image.set_pixel(cell_x + 2 + col, cell_y + 2 + row as usize, palette.color(shade));
+                }
+            }
+        }
+
+        let text_x = cell_x + 12;
+        image.draw_text(text_x, cell_y + 1, &format!("X{:02X}", entry.x));
+        image.draw_text(text_x, cell_y + 7, &format!("Y{:02X}", entry.y));
+        image.draw_text(text_x, cell_y + 13, &format!("T{:02X}", entry.tile));
+        image.draw_text(text_x, cell_y + 19, &format!("F{:02X}", entry.flags));
+    }
+
+    image
+}
+
+fn draw_map(vram: &VideoRam, io_regs: &IORegs, map_base: u16, palette: &Palette) -> DebugImage {
+    let mut image = DebugImage::new(256, 256);
+
+    let lcdc = io_regs.read(0xFF40);
+    let tile_mode_8000 = (lcdc & 0b0001_0000) != 0;
+    let bg_palette = io_regs.read(0xFF47);
+
+    for map_y in 0..32u16 {
+        for map_x in 0..32u16 {
+            let tile_num = vram.read_bank(0, map_base + map_y * 32 + map_x);
+            let tile_addr = match tile_mode_8000 {
+                true => 0x8000 + (tile_num as u16) * 16,
+                false => 0x8800 + ((tile_num as i8 as i16 + 128) as u16) * 16,
+            };
+
+            for row in 0..8 {
+                let indices = tile_row(vram, 0, tile_addr, row);
+                for (col, &index) in indices.iter().enumerate() {
+                    let shade = (bg_palette >> (index * 2)) & 0x3;
+                    image.set_pixel(
+                        map_x as usize * 8 + col,
+                        map_y as usize * 8 + row as usize,
+                        palette.color(shade),
+                    );
+                }
+            }
+        }
+    }
+
+    image
+}
+
+// Colour indices 0-3 for one row of a tile, left to right
+fn tile_row(vram: &VideoRam, bank: u8, tile_addr: u16, row: u8) -> [u8; 8] {
+    let b1 = vram.read_bank(bank, tile_addr + row as u16 * 2);
+    let b2 = vram.read_bank(bank, tile_addr + row as u16 * 2 + 1);
+
+    let mut indices = [0; 8];
+    for (px, index) in indices.iter_mut().enumerate() {
+        let bit = 7 - px;
+        *index = ((b1 >> bit) & 1) | (((b2 >> bit) & 1) << 1);
+    }
+    indices
+}
+
+// 3x5 pixel glyphs for the characters used in labels
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        _ => [0; 5],
+    }
+}
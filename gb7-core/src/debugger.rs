@@ -0,0 +1,629 @@
+use std::{cell::RefCell, collections::BTreeMap, fmt, fmt::Write, rc::Rc};
+
+use crate::{
+    cartridge::CartMemory,
+    cpu::CpuFlags,
+    disasm::{self, Instruction, Syntax},
+    gameboy::Gameboy,
+    watch::{Access, DebugEvent, WatchCondition, Watches, Watchpoint},
+};
+
+// Give up on `continue` if nothing stops it within this many frames, a minute of
+// emulated time, so the prompt comes back
+pub const CONTINUE_FRAME_LIMIT: u64 = 3600;
+
+pub const HELP: &str = "\
+Addresses and values are hex, optionally with a $ or 0x prefix. ROM addresses can name a
+bank, e.g. 03:4A20. Counts are decimal.
+
+  s, step [n]           execute n instructions (default 1)
+  n, next               step over CALL and RST
+  c, continue           run until a breakpoint
+  f, frame <n>          run until frame n has been drawn
+  b, break <addr>       set a breakpoint
+  d, delete <id>        delete a breakpoint
+  bl, breakpoints       list breakpoints, watchpoints and catches
+  watch <rwx> <addr>[-<end>] [=<value>] [changed]
+                        stop on reads, writes and/or execution in a range, optionally
+                        only of a value or writes that change memory
+  unwatch <id>          delete a watchpoint
+  catch <event>         stop on an event: int, ly <value>, dma or bank
+  uncatch <event>       stop catching an event: int, ly, dma or bank
+  r, regs               show registers
+  set <reg> <value>     set a register: a f b c d e h l af bc de hl sp pc ime
+  x <addr> [len]        show memory (default 64 bytes)
+  w <addr> <value>...   write bytes to memory outside ROM
+  dis [addr] [n]        disassemble n instructions (default 10) from addr or PC
+  help                  show this help
+  q, quit               exit
+";
+
+// An address, optionally in a specific ROM bank
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BankAddr {
+    pub bank: Option<usize>,
+    pub addr: u16,
+}
+
+impl BankAddr {
+    // `4A20`, `$4A20`, `0x4A20` or `03:4A20`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (bank, addr) = match text.split_once(':') {
+            Some((bank, addr)) => {
+                let bank = parse_hex(bank)? as usize;
+                (Some(bank), parse_hex(addr)?)
+            }
+            None => (None, parse_hex(text)?),
+        };
+        let addr = u16::try_from(addr).map_err(|_| format!("address {} is out of range", text))?;
+        match (bank, addr) {
+            (Some(0), 0x0000..=0x3FFF) | (Some(_), 0x4000..=0x7FFF) | (None, _) => Ok(BankAddr { bank, addr }),
+            _ => Err(format!("{} isn't a ROM address in that bank", text)),
+        }
+    }
+
+    // Whether the Gameboy currently sees this address, in the right bank
+    pub fn is_mapped(&self, gameboy: &Gameboy) -> bool {
+        self.bank.is_none() || self.bank == gameboy.rom_bank(self.addr)
+    }
+
+    // Read without side effects, from ROM directly if the bank isn't mapped
+    pub fn read(&self, gameboy: &Gameboy) -> Option<u8> {
+        match self.bank {
+            Some(bank) if !self.is_mapped(gameboy) => {
+                let offset = bank * 0x4000 + (self.addr as usize & 0x3FFF);
+                gameboy.cartridge.rom().get(offset).copied()
+            }
+            _ => Some(gameboy.peek(self.addr)),
+        }
+    }
+
+    // The address `offset` bytes on, unless that's past the end of the bank, or of memory
+    fn offset(&self, offset: u16) -> Option<Self> {
+        let end = match (self.bank, self.addr) {
+            (Some(_), 0x0000..=0x3FFF) => 0x3FFF,
+            (Some(_), _) => 0x7FFF,
+            (None, _) => 0xFFFF,
+        };
+        let addr = self.addr.checked_add(offset).filter(|&addr| addr <= end)?;
+        Some(BankAddr { bank: self.bank, addr })
+    }
+}
+
+impl fmt::Display for BankAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "{:02X}:{:04X}", bank, self.addr),
+            None => write!(f, "{:04X}", self.addr),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterName {
+    A,
+    F,
+    B,
+    C,
+    D,
+    E,
+    H,
+    L,
+    AF,
+    BC,
+    DE,
+    HL,
+    SP,
+    PC,
+    IME,
+}
+
+impl RegisterName {
+    fn parse(text: &str) -> Result<Self, String> {
+        Ok(match text.to_ascii_lowercase().as_str() {
+            "a" => RegisterName::A,
+            "f" => RegisterName::F,
+            "b" => RegisterName::B,
+            "c" => RegisterName::C,
+            "d" => RegisterName::D,
+            "e" => RegisterName::E,
+            "h" => RegisterName::H,
+            "l" => RegisterName::L,
+            "af" => RegisterName::AF,
+            "bc" => RegisterName::BC,
+            "de" => RegisterName::DE,
+            "hl" => RegisterName::HL,
+            "sp" => RegisterName::SP,
+            "pc" => RegisterName::PC,
+            "ime" => RegisterName::IME,
+            _ => return Err(format!("unknown register {}", text)),
+        })
+    }
+
+    fn is_16_bit(&self) -> bool {
+        matches!(
+            self,
+            RegisterName::AF | RegisterName::BC | RegisterName::DE | RegisterName::HL | RegisterName::SP | RegisterName::PC
+        )
+    }
+}
+
+// Events that can be caught, see `Watches`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Catch {
+    Interrupt,
+    // Only the value matters when catching
+    Line(u8),
+    Dma,
+    BankSwitch,
+}
+
+impl Catch {
+    fn parse(args: &[&str]) -> Result<Self, String> {
+        Ok(match args {
+            ["int"] => Catch::Interrupt,
+            ["ly", value] => Catch::Line(u8::try_from(parse_hex(value)?).map_err(|_| format!("{} isn't a line", value))?),
+            ["ly"] => Catch::Line(0),
+            ["dma"] => Catch::Dma,
+            ["bank"] => Catch::BankSwitch,
+            _ => return Err("expected an event: int, ly <value>, dma or bank".to_string()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Step(usize),
+    Next,
+    Continue,
+    RunToFrame(u64),
+    Break(BankAddr),
+    Delete(usize),
+    Breakpoints,
+    Registers,
+    Set(RegisterName, u16),
+    Examine(BankAddr, usize),
+    Write(BankAddr, Vec<u8>),
+    Disassemble(Option<BankAddr>, usize),
+    Watch(Watchpoint),
+    Unwatch(usize),
+    Catch(Catch),
+    Uncatch(Catch),
+    Help,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or("no command given")?;
+        let args: Vec<&str> = words.collect();
+        let arg = |i: usize| args.get(i).copied().ok_or_else(|| format!("{} needs more arguments", name));
+
+        let command = match name {
+            "s" | "step" => Command::Step(args.first().map_or(Ok(1), |n| parse_count(n))?),
+            "n" | "next" => Command::Next,
+            "c" | "continue" => Command::Continue,
+            "f" | "frame" => Command::RunToFrame(parse_count(arg(0)?)? as u64),
+            "b" | "break" => Command::Break(BankAddr::parse(arg(0)?)?),
+            "d" | "delete" => Command::Delete(parse_count(arg(0)?)?),
+            "bl" | "breakpoints" => Command::Breakpoints,
+            "r" | "regs" => Command::Registers,
+            "set" => {
+                let register = RegisterName::parse(arg(0)?)?;
+                let value = parse_hex(arg(1)?)?;
+                let max = if register.is_16_bit() { 0xFFFF } else { 0xFF };
+                if value > max {
+                    return Err(format!("{:X} doesn't fit in {:?}", value, register));
+                }
+                Command::Set(register, value as u16)
+            }
+            "x" => Command::Examine(BankAddr::parse(arg(0)?)?, args.get(1).map_or(Ok(64), |n| parse_count(n))?),
+            "w" => {
+                let bytes = args
+                    .iter()
+                    .skip(1)
+                    .map(|val| parse_hex(val).and_then(|val| u8::try_from(val).map_err(|_| format!("{:X} isn't a byte", val))))
+                    .collect::<Result<Vec<u8>, String>>()?;
+                if bytes.is_empty() {
+                    return Err("w needs an address and values".to_string());
+                }
+                Command::Write(BankAddr::parse(arg(0)?)?, bytes)
+            }
+            "dis" => {
+                let (addr, count) = match args.as_slice() {
+                    [] => (None, 10),
+                    [addr] => (Some(BankAddr::parse(addr)?), 10),
+                    [addr, count] => (Some(BankAddr::parse(addr)?), parse_count(count)?),
+                    _ => return Err("dis takes at most an address and a count".to_string()),
+                };
+                Command::Disassemble(addr, count)
+            }
+            "watch" => Command::Watch(parse_watchpoint(&args)?),
+            "unwatch" => Command::Unwatch(parse_count(arg(0)?)?),
+            "catch" if args == ["ly"] => return Err("catch ly needs a value".to_string()),
+            "catch" => Command::Catch(Catch::parse(&args)?),
+            "uncatch" => Command::Uncatch(Catch::parse(&args)?),
+            "help" | "h" | "?" => Command::Help,
+            "q" | "quit" => Command::Quit,
+            _ => return Err(format!("unknown command {}, try help", name)),
+        };
+        Ok(command)
+    }
+}
+
+// `rw C000-C0FF =12 changed`, `x 03:4A20`
+fn parse_watchpoint(args: &[&str]) -> Result<Watchpoint, String> {
+    let [kinds, range, conditions @ ..] = args else {
+        return Err("watch needs accesses and an address".to_string());
+    };
+    let accesses = kinds
+        .chars()
+        .map(|kind| match kind {
+            'r' => Ok(Access::Read),
+            'w' => Ok(Access::Write),
+            'x' => Ok(Access::Execute),
+            _ => Err(format!("unknown access {}, expected r, w or x", kind)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (BankAddr::parse(start)?, BankAddr::parse(end)?.addr),
+        None => (BankAddr::parse(range)?, BankAddr::parse(range)?.addr),
+    };
+    if end < start.addr {
+        return Err(format!("{} ends before it starts", range));
+    }
+    let mut watchpoint = Watchpoint::new(start.addr..=end, &accesses);
+    if let Some(bank) = start.bank {
+        watchpoint = watchpoint.with_condition(WatchCondition::Bank(bank));
+    }
+    for condition in conditions {
+        let condition = match condition.strip_prefix('=') {
+            Some(value) => WatchCondition::Equals(u8::try_from(parse_hex(value)?).map_err(|_| format!("{} isn't a byte", value))?),
+            None if *condition == "changed" => WatchCondition::Changed,
+            None => return Err(format!("unknown condition {}, expected =<value> or changed", condition)),
+        };
+        watchpoint = watchpoint.with_condition(condition);
+    }
+    Ok(watchpoint)
+}
+
+fn parse_hex(text: &str) -> Result<u32, String> {
+    let digits = text.trim_start_matches('$').trim_start_matches("0x");
+    u32::from_str_radix(digits, 16).map_err(|_| format!("{} isn't a hex number", text))
+}
+
+fn parse_count(text: &str) -> Result<usize, String> {
+    text.parse().map_err(|_| format!("{} isn't a count", text))
+}
+
+// Why execution stopped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    Done,
+    Breakpoint(usize),
+    // Watchpoints hit and events caught during the last instruction
+    Events(Vec<DebugEvent>),
+    FrameLimit,
+}
+
+// Runs a Gameboy under commands from a REPL or script
+#[derive(Default)]
+pub struct Debugger {
+    pub breakpoints: BTreeMap<usize, BankAddr>,
+    next_breakpoint: usize,
+    pub syntax: Syntax,
+    // Frames drawn since the debugger started
+    pub frame: u64,
+    // Filled in by the callback of the `Watches` the debugger installs
+    hits: Rc<RefCell<Vec<DebugEvent>>>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Debugger::default()
+    }
+
+    // Run a command, returning what to show. `Quit` is left to the caller.
+    pub fn execute(&mut self, gameboy: &mut Gameboy, command: Command) -> Result<String, String> {
+        let output = match command {
+            Command::Step(count) => {
+                let mut reason = StopReason::Done;
+                for _ in 0..count {
+                    self.step(gameboy);
+                    if let Some(stop) = self.check_stop(gameboy) {
+                        reason = stop;
+                        break;
+                    }
+                }
+                self.stopped(gameboy, reason)
+            }
+            Command::Next => {
+                let reason = self.step_over(gameboy);
+                self.stopped(gameboy, reason)
+            }
+            Command::Continue => {
+                let limit = self.frame + CONTINUE_FRAME_LIMIT;
+                let reason = self.run_until(gameboy, |debugger, _| debugger.frame >= limit);
+                let reason = match reason {
+                    StopReason::Done => StopReason::FrameLimit,
+                    reason => reason,
+                };
+                self.stopped(gameboy, reason)
+            }
+            Command::RunToFrame(frame) => {
+                if frame <= self.frame {
+                    return Err(format!("already at frame {}", self.frame));
+                }
+                let reason = self.run_until(gameboy, |debugger, _| debugger.frame >= frame);
+                self.stopped(gameboy, reason)
+            }
+            Command::Break(addr) => {
+                self.next_breakpoint += 1;
+                self.breakpoints.insert(self.next_breakpoint, addr);
+                format!("Breakpoint {} at {}", self.next_breakpoint, addr)
+            }
+            Command::Delete(id) => match self.breakpoints.remove(&id) {
+                Some(addr) => format!("Deleted breakpoint {} at {}", id, addr),
+                None => return Err(format!("no breakpoint {}", id)),
+            },
+            Command::Breakpoints => {
+                let mut lines: Vec<String> =
+                    self.breakpoints.iter().map(|(id, addr)| format!("{:>3}  break {}", id, addr)).collect();
+                if let Some(watches) = &gameboy.watches {
+                    lines.extend(watches.watchpoints().map(|(id, watchpoint)| format!("{:>3}  watch {}", id, describe_watchpoint(watchpoint))));
+                    let catches = [
+                        (watches.break_on_interrupt, "int".to_string()),
+                        (watches.break_on_line.is_some(), format!("ly {:02X}", watches.break_on_line.unwrap_or(0))),
+                        (watches.break_on_dma, "dma".to_string()),
+                        (watches.break_on_bank_switch, "bank".to_string()),
+                    ];
+                    lines.extend(catches.into_iter().filter(|(on, _)| *on).map(|(_, event)| format!("     catch {}", event)));
+                }
+                match lines.is_empty() {
+                    true => "No breakpoints".to_string(),
+                    false => lines.join("\n"),
+                }
+            }
+            Command::Watch(watchpoint) => {
+                let description = describe_watchpoint(&watchpoint);
+                let id = self.watches(gameboy).add(watchpoint);
+                format!("Watchpoint {} on {}", id, description)
+            }
+            Command::Unwatch(id) => match self.watches(gameboy).remove(id) {
+                Some(watchpoint) => format!("Deleted watchpoint {} on {}", id, describe_watchpoint(&watchpoint)),
+                None => return Err(format!("no watchpoint {}", id)),
+            },
+            Command::Catch(catch) => {
+                self.set_catch(gameboy, catch, true);
+                format!("Catching {:?}", catch)
+            }
+            Command::Uncatch(catch) => {
+                self.set_catch(gameboy, catch, false);
+                format!("No longer catching {:?}", catch)
+            }
+            Command::Registers => registers(gameboy),
+            Command::Set(register, value) => {
+                set_register(gameboy, register, value);
+                registers(gameboy)
+            }
+            Command::Examine(addr, len) => examine(gameboy, addr, len)?,
+            Command::Write(addr, bytes) => {
+                // Writes to ROM go to the cartridge's bank registers
+                if addr.addr < 0x8000 {
+                    return Err(format!("{} is in ROM, which can't be written", addr));
+                }
+                if u16::try_from(bytes.len() - 1).ok().and_then(|n| addr.offset(n)).is_none() {
+                    return Err(format!("writing {} bytes at {} runs past the end of memory", bytes.len(), addr));
+                }
+                for (i, &val) in bytes.iter().enumerate() {
+                    gameboy.poke(addr.addr + i as u16, val);
+                }
+                examine(gameboy, addr, bytes.len())?
+            }
+            Command::Disassemble(addr, count) => {
+                let start = addr.unwrap_or_else(|| self.location(gameboy));
+                self.disassemble(gameboy, start, count)
+            }
+            Command::Help => HELP.trim_end().to_string(),
+            Command::Quit => String::new(),
+        };
+        Ok(output)
+    }
+
+    // The PC, with its bank if it's in ROM
+    pub fn location(&self, gameboy: &Gameboy) -> BankAddr {
+        let pc = gameboy.cpu.pc;
+        BankAddr {
+            bank: gameboy.rom_bank(pc),
+            addr: pc,
+        }
+    }
+
+    pub fn breakpoint_at_pc(&self, gameboy: &Gameboy) -> Option<usize> {
+        let pc = gameboy.cpu.pc;
+        self.breakpoints
+            .iter()
+            .find(|(_, addr)| addr.addr == pc && addr.is_mapped(gameboy))
+            .map(|(&id, _)| id)
+    }
+
+    // Execute one instruction, or interrupt dispatch, or M-cycle of HALT
+    pub fn step(&mut self, gameboy: &mut Gameboy) {
+        gameboy.execute();
+        if gameboy.lcd.take_frame_ready() {
+            self.frame += 1;
+        }
+    }
+
+    // Step, running any subroutine CALL or RST jumps to until it returns
+    pub fn step_over(&mut self, gameboy: &mut Gameboy) -> StopReason {
+        let instruction = self.instruction_at(gameboy, self.location(gameboy));
+        let is_call = instruction.info.mnemonic.starts_with("CALL") || instruction.info.mnemonic.starts_with("RST");
+        if !is_call {
+            self.step(gameboy);
+            return self.check_stop(gameboy).unwrap_or(StopReason::Done);
+        }
+
+        // Recursion can come back to the same address deeper in the stack, so also wait
+        // for the stack to unwind
+        let (return_addr, sp) = (instruction.next_addr(), gameboy.cpu.sp);
+        let limit = self.frame + CONTINUE_FRAME_LIMIT;
+        let reason = self.run_until(gameboy, |debugger, gameboy| {
+            (gameboy.cpu.pc == return_addr && gameboy.cpu.sp >= sp) || debugger.frame >= limit
+        });
+        match reason {
+            StopReason::Done if self.frame >= limit => StopReason::FrameLimit,
+            reason => reason,
+        }
+    }
+
+    // Step at least once, then until `done` or a breakpoint
+    pub fn run_until(&mut self, gameboy: &mut Gameboy, done: impl Fn(&Self, &Gameboy) -> bool) -> StopReason {
+        loop {
+            self.step(gameboy);
+            if let Some(reason) = self.check_stop(gameboy) {
+                return reason;
+            }
+            if done(self, gameboy) {
+                return StopReason::Done;
+            }
+        }
+    }
+
+    // The Gameboy's watches, installing ones that report to the debugger if needed
+    pub fn watches<'a>(&self, gameboy: &'a mut Gameboy) -> &'a mut Watches {
+        let hits = self.hits.clone();
+        gameboy
+            .watches
+            .get_or_insert_with(|| Watches::new(move |event| hits.borrow_mut().push(*event)))
+    }
+
+    fn set_catch(&self, gameboy: &mut Gameboy, catch: Catch, on: bool) {
+        let watches = self.watches(gameboy);
+        match catch {
+            Catch::Interrupt => watches.break_on_interrupt = on,
+            Catch::Line(ly) => watches.break_on_line = on.then_some(ly),
+            Catch::Dma => watches.break_on_dma = on,
+            Catch::BankSwitch => watches.break_on_bank_switch = on,
+        }
+    }
+
+    // Whether the last step hit something that should stop execution
+    fn check_stop(&self, gameboy: &Gameboy) -> Option<StopReason> {
+        let hits = std::mem::take(&mut *self.hits.borrow_mut());
+        if !hits.is_empty() {
+            return Some(StopReason::Events(hits));
+        }
+        self.breakpoint_at_pc(gameboy).map(StopReason::Breakpoint)
+    }
+
+    fn instruction_at(&self, gameboy: &Gameboy, addr: BankAddr) -> Instruction {
+        let bytes: Vec<u8> = (0..3).filter_map(|i| addr.offset(i)?.read(gameboy)).collect();
+        disasm::decode(&bytes, addr.addr, self.syntax)
+    }
+
+    fn disassemble(&self, gameboy: &Gameboy, start: BankAddr, count: usize) -> String {
+        let pc = self.location(gameboy);
+        let mut addr = start;
+        let mut lines = Vec::new();
+        for _ in 0..count {
+            let instruction = self.instruction_at(gameboy, addr);
+            let marker = if addr.addr == pc.addr && addr.is_mapped(gameboy) { '>' } else { ' ' };
+            let bytes: Vec<String> = instruction.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            lines.push(format!("{} {}  {:<8}  {}", marker, addr, bytes.join(" "), instruction.text));
+            match addr.offset(instruction.bytes.len() as u16) {
+                Some(next) => addr = next,
+                None => break,
+            }
+        }
+        lines.join("\n")
+    }
+
+    // Describe where execution stopped and the instruction about to run
+    fn stopped(&self, gameboy: &Gameboy, reason: StopReason) -> String {
+        let mut output = String::new();
+        match reason {
+            StopReason::Done => {}
+            StopReason::Breakpoint(id) => writeln!(output, "Breakpoint {} hit", id).unwrap(),
+            StopReason::Events(events) => events.iter().for_each(|event| writeln!(output, "{}", event).unwrap()),
+            StopReason::FrameLimit => writeln!(output, "Stopped after {} frames", CONTINUE_FRAME_LIMIT).unwrap(),
+        }
+        write!(output, "frame {}  {}", self.frame, self.disassemble(gameboy, self.location(gameboy), 1)).unwrap();
+        output
+    }
+}
+
+fn describe_watchpoint(watchpoint: &Watchpoint) -> String {
+    let accesses: String = [(watchpoint.read, 'r'), (watchpoint.write, 'w'), (watchpoint.execute, 'x')]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, c)| c)
+        .collect();
+    let mut description = format!("{} {:04X}-{:04X}", accesses, watchpoint.range.start(), watchpoint.range.end());
+    for condition in &watchpoint.conditions {
+        match condition {
+            WatchCondition::Equals(value) => write!(description, " ={:02X}", value).unwrap(),
+            WatchCondition::Changed => description.push_str(" changed"),
+            WatchCondition::Bank(bank) => write!(description, " bank {:02X}", bank).unwrap(),
+        }
+    }
+    description
+}
+
+fn registers(gameboy: &Gameboy) -> String {
+    let cpu = &gameboy.cpu;
+    let r = &cpu.registers;
+    let flag = |flag, c| if r.flags.contains(flag) { c } else { '-' };
+    format!(
+        "AF:{:04X} BC:{:04X} DE:{:04X} HL:{:04X} SP:{:04X} PC:{:04X} F:{}{}{}{} IME:{} HALT:{} ROM:{:02X} LY:{:02X}",
+        r.af(), r.bc(), r.de(), r.hl(), cpu.sp, cpu.pc,
+        flag(CpuFlags::Z, 'Z'), flag(CpuFlags::N, 'N'), flag(CpuFlags::H, 'H'), flag(CpuFlags::C, 'C'),
+        cpu.ime as u8, cpu.halted as u8, gameboy.cartridge.rom_bank(), gameboy.peek(0xFF44)
+    )
+}
+
+fn set_register(gameboy: &mut Gameboy, register: RegisterName, value: u16) {
+    let cpu = &mut gameboy.cpu;
+    let r = &mut cpu.registers;
+    match register {
+        RegisterName::A => r.a = value as u8,
+        RegisterName::F => r.flags = CpuFlags::from_bits_truncate(value as u8),
+        RegisterName::B => r.b = value as u8,
+        RegisterName::C => r.c = value as u8,
+        RegisterName::D => r.d = value as u8,
+        RegisterName::E => r.e = value as u8,
+        RegisterName::H => r.h = value as u8,
+        RegisterName::L => r.l = value as u8,
+        RegisterName::AF => r.set_af(value),
+        RegisterName::BC => r.set_bc(value),
+        RegisterName::DE => r.set_de(value),
+        RegisterName::HL => r.set_hl(value),
+        RegisterName::SP => cpu.sp = value,
+        RegisterName::PC => cpu.pc = value,
+        RegisterName::IME => cpu.ime = value != 0,
+    }
+}
+
+// Hex dump, 16 bytes per line
+fn examine(gameboy: &Gameboy, addr: BankAddr, len: usize) -> Result<String, String> {
+    let at = |i: usize| match u16::try_from(i).ok().and_then(|i| addr.offset(i)) {
+        Some(addr) => Ok(addr),
+        None if addr.bank.is_some() => Err(format!("{} bytes from {} runs past the end of the bank", len, addr)),
+        None => Err(format!("{} bytes from {} runs past the end of memory", len, addr)),
+    };
+    let mut lines = Vec::new();
+    for line_start in (0..len).step_by(16) {
+        let mut line = format!("{} ", at(line_start)?);
+        for i in line_start..len.min(line_start + 16) {
+            let addr = at(i)?;
+            let val = addr
+                .read(gameboy)
+                .ok_or_else(|| format!("{} is past the end of the ROM", addr))?;
+            write!(line, " {:02X}", val).unwrap();
+        }
+        lines.push(line);
+    }
+    Ok(lines.join("\n"))
+}
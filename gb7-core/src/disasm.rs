@@ -0,0 +1,142 @@
+use crate::{
+    gameboy::Gameboy,
+    instructions::{self, InstructionInfo},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Syntax {
+    // `LD A,(HL+)`, `SUB B`, `LD (C),A`
+    #[default]
+    Conventional,
+    // What RGBDS assembles: `LD A,[HL+]`, `SUB A,B`, `LDH [C],A`
+    Rgbds,
+}
+
+// One decoded instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    pub text: String,
+    pub info: &'static InstructionInfo,
+    // Where a jump, call or RST goes if it's taken, with relative jumps resolved
+    pub target: Option<u16>,
+}
+
+impl Instruction {
+    // Address of the instruction after this one
+    pub fn next_addr(&self) -> u16 {
+        self.addr.wrapping_add(self.bytes.len() as u16)
+    }
+}
+
+// Decode the instruction at `addr` on the bus, without side effects
+pub fn disassemble(gameboy: &Gameboy, addr: u16, syntax: Syntax) -> Instruction {
+    let bytes = [0, 1, 2].map(|i| gameboy.peek(addr.wrapping_add(i)));
+    decode(&bytes, addr, syntax)
+}
+
+// Decode `count` consecutive instructions starting at `addr` on the bus
+pub fn disassemble_count(gameboy: &Gameboy, addr: u16, count: usize, syntax: Syntax) -> Vec<Instruction> {
+    let mut addr = addr;
+    (0..count)
+        .map(|_| {
+            let instruction = disassemble(gameboy, addr, syntax);
+            addr = instruction.next_addr();
+            instruction
+        })
+        .collect()
+}
+
+// Decode all of `bytes`, which are loaded at `addr`
+pub fn disassemble_slice(bytes: &[u8], addr: u16, syntax: Syntax) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let instruction = decode(&bytes[offset..], addr.wrapping_add(offset as u16), syntax);
+        offset += instruction.bytes.len();
+        instructions.push(instruction);
+    }
+    instructions
+}
+
+// Decode the instruction at the start of `bytes`, which is at `addr`. An instruction cut
+// off by the end of `bytes` comes out as a single data byte.
+pub fn decode(bytes: &[u8], addr: u16, syntax: Syntax) -> Instruction {
+    let op = bytes.first().copied().unwrap_or(0);
+    let info = instructions::info(op, bytes.get(1).copied().unwrap_or(0));
+    let length = info.length as usize;
+
+    if info.mnemonic == "INVALID" || bytes.len() < length {
+        return Instruction {
+            addr,
+            bytes: vec![op],
+            text: format!("DB ${:02X}", op),
+            info,
+            target: None,
+        };
+    }
+
+    let bytes = bytes[..length].to_vec();
+    let n8 = bytes.get(1).copied().unwrap_or(0);
+    let n16 = u16::from_le_bytes([n8, bytes.get(2).copied().unwrap_or(0)]);
+    let relative = addr.wrapping_add(2).wrapping_add(n8 as i8 as u16);
+
+    let mnemonic = match syntax {
+        Syntax::Conventional => info.mnemonic.to_string(),
+        Syntax::Rgbds => rgbds_mnemonic(info.mnemonic),
+    };
+    let text = match mnemonic.split_once(' ') {
+        Some((name, operands)) => {
+            let operands = operands
+                .replace("SP+e8", &signed_offset("SP", n8 as i8))
+                .replace("a16", &format!("${:04X}", n16))
+                .replace("n16", &format!("${:04X}", n16))
+                .replace("a8", &format!("${:04X}", 0xFF00 | n8 as u16))
+                .replace("n8", &format!("${:02X}", n8));
+            let operands = match name {
+                "JR" => operands.replace("e8", &format!("${:04X}", relative)),
+                _ => operands.replace("e8", &format!("{}", n8 as i8)),
+            };
+            format!("{} {}", name, operands)
+        }
+        None => mnemonic,
+    };
+
+    let target = match info.mnemonic.split(' ').next() {
+        Some("JR") => Some(relative),
+        Some("JP") | Some("CALL") if info.length == 3 => Some(n16),
+        Some("RST") => Some((op & 0x38) as u16),
+        _ => None,
+    };
+
+    Instruction {
+        addr,
+        bytes,
+        text,
+        info,
+        target,
+    }
+}
+
+fn signed_offset(base: &str, offset: i8) -> String {
+    match offset < 0 {
+        true => format!("{}-{}", base, -(offset as i16)),
+        false => format!("{}+{}", base, offset),
+    }
+}
+
+fn rgbds_mnemonic(mnemonic: &str) -> String {
+    let mnemonic = match mnemonic {
+        "LD (C),A" => "LDH (C),A",
+        "LD A,(C)" => "LDH A,(C)",
+        "JP (HL)" => "JP HL",
+        _ => mnemonic,
+    };
+    // The ALU instructions always name A as their destination
+    let mnemonic = match mnemonic.split_once(' ') {
+        Some((name @ ("SUB" | "AND" | "XOR" | "OR" | "CP"), operand)) => format!("{} A,{}", name, operand),
+        _ => mnemonic.to_string(),
+    };
+    mnemonic.replace('(', "[").replace(')', "]")
+}
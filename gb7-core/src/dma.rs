@@ -0,0 +1,91 @@
+use std::io;
+
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
+const TRANSFER_LENGTH: u16 = 160;
+
+// OAM DMA, started by writing the source's upper byte to 0xFF46. After a one M-cycle
+// start-up delay, one byte is copied to OAM each M-cycle for 160 M-cycles, during which
+// the CPU only has access to HRAM and the IO registers. Starting a new transfer while
+// one is running lets the old one carry on until the new one takes over.
+#[derive(Default)]
+pub struct OamDma {
+    // Source of a transfer that starts on the next M-cycle
+    starting: Option<u16>,
+    // Source and number of bytes copied of the current transfer
+    active: Option<(u16, u16)>,
+    // A byte was copied on the last M-cycle, so the CPU can't use the bus
+    blocking: bool,
+    // The last byte copied, which is what the CPU sees on a blocked bus
+    bus_byte: u8,
+}
+
+impl OamDma {
+    pub fn start(&mut self, val: u8) {
+        // Sources past work RAM read from its echo, including 0xFE00-0xFFFF
+        let source = match val {
+            0xE0..=0xFF => (val as u16 - 0x20) << 8,
+            _ => (val as u16) << 8,
+        };
+        self.starting = Some(source);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some() || self.starting.is_some()
+    }
+
+    pub fn is_blocking(&self) -> bool {
+        self.blocking
+    }
+
+    pub fn bus_byte(&self) -> u8 {
+        self.bus_byte
+    }
+
+    // Advance by one M-cycle, returning the source and OAM addresses of a byte to copy
+    pub fn tick(&mut self) -> Option<(u16, u16)> {
+        let copy = self.active.map(|(source, copied)| {
+            self.active = (copied + 1 < TRANSFER_LENGTH).then_some((source, copied + 1));
+            (source + copied, 0xFE00 + copied)
+        });
+        self.blocking = copy.is_some();
+
+        // A transfer becomes active at the end of its start-up M-cycle
+        if let Some(source) = self.starting.take() {
+            self.active = Some((source, 0));
+        }
+
+        copy
+    }
+
+    // Called with each byte copied
+    pub fn set_bus_byte(&mut self, val: u8) {
+        self.bus_byte = val;
+    }
+}
+
+impl Snapshot for OamDma {
+    fn save(&self, w: &mut StateWriter) {
+        w.bool(self.starting.is_some());
+        w.u16(self.starting.unwrap_or(0));
+        let (source, copied) = self.active.unwrap_or((0, 0));
+        w.bool(self.active.is_some());
+        w.u16(source);
+        w.u16(copied);
+        w.bool(self.blocking);
+        w.u8(self.bus_byte);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        let starting = r.bool()?;
+        let source = r.u16()?;
+        self.starting = starting.then_some(source);
+        let active = r.bool()?;
+        let source = r.u16()?;
+        let copied = r.u16()?.min(TRANSFER_LENGTH - 1);
+        self.active = active.then_some((source, copied));
+        self.blocking = r.bool()?;
+        self.bus_byte = r.u8()?;
+        Ok(())
+    }
+}
@@ -1,11 +1,20 @@
+use std::io;
+
 use crate::{
     cartridge::{CartMemory, Cartridge},
     cpu::{Cpu, CpuFlags},
+    dma::OamDma,
+    instructions::{CB_INSTRUCTIONS, INSTRUCTIONS},
     lcd::Lcd,
     memory::{GBVideoRam, GBWorkRam, HighRam, IORegs, Oam, VideoMem, VideoRam, WorkMem, WorkRam},
     opcodes::{Opcode, CB_OPCODES, OPCODES},
     ppu::Ppu,
+    savestate::{self, Snapshot, StateReader, StateWriter},
+    serial::Serial,
+    sgb::{self, Sgb},
     timers::Timers, joypad::Joypad,
+    trace::Tracer,
+    watch::{Access, DebugEvent, Watches},
 };
 
 pub struct Gameboy {
@@ -14,40 +23,213 @@ pub struct Gameboy {
     pub lcd: Lcd,
     pub joypad: Joypad,
     pub timers: Timers,
+    pub serial: Serial,
     pub cartridge: Cartridge,
     pub wram: WorkRam,
     pub vram: VideoRam,
     pub oam: Oam,
+    pub dma: OamDma,
     pub io_regs: IORegs,
     pub high_ram: HighRam,
+    pub sgb: Option<Sgb>,
+    // Tick the rest of the system before each memory access the CPU makes, rather than
+    // all at once after the instruction, so reads and writes land on the right M-cycle
+    pub cycle_accurate: bool,
+    // M-cycles of the current instruction already ticked in cycle-accurate mode
+    ticked_cycles: u8,
+    // M-cycles executed since power on
+    cycles: u64,
+    // Called before each instruction is executed
+    pub tracer: Option<Box<dyn Tracer>>,
+    // Make LY always read 0x90, as logs for gameboy-doctor expect
+    pub stub_ly: bool,
+    // Watchpoints and event breakpoints
+    pub watches: Option<Watches>,
+    // CRC-32 of the ROM, which save states are tied to
+    rom_checksum: u32,
 }
 
-const CYCLES_PER_FRAME: u32 = 70224;
+pub(crate) const CYCLES_PER_FRAME: u32 = 70224;
+
+// Bits of each IO register (0xFF00-0xFF7F) that are unused or unmapped on the DMG and
+// always read back as 1
+const IO_READ_MASKS: [u8; 0x80] = [
+    // JOYP, SB, SC, -, DIV, TIMA, TMA, TAC, -, -, -, -, -, -, -, IF
+    0xC0, 0x00, 0x7E, 0xFF, 0x00, 0x00, 0x00, 0xF8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE0,
+    // NR10-NR14, -, NR21-NR24, NR30-NR34, -
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, 0xFF, 0xBF, 0x7F, 0xFF, 0x9F, 0xFF, 0xBF, 0xFF,
+    // NR41-NR44, NR50-NR52, -
+    0xFF, 0x00, 0x00, 0xBF, 0x00, 0x00, 0x70, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // Wave RAM
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // LCDC, STAT, SCY, SCX, LY, LYC, DMA, BGP, OBP0, OBP1, WY, WX, -
+    0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF,
+    // CGB-only registers and unused space
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
 
 impl Gameboy {
     pub fn new_dmg(cartridge: Cartridge) -> Self {
+        let rom_checksum = savestate::crc32(cartridge.rom());
         let mut gb = Gameboy {
             cpu: Cpu::default(),
             ppu: Ppu::default(),
             lcd: Lcd::default(),
             joypad: Joypad::default(),
             timers: Timers::default(),
+            serial: Serial::default(),
             cartridge,
             wram: GBWorkRam::default().into(),
             vram: GBVideoRam::default().into(),
             oam: Oam::default(),
+            dma: OamDma::default(),
             io_regs: IORegs::default(),
             high_ram: HighRam::default(),
+            sgb: None,
+            cycle_accurate: false,
+            ticked_cycles: 0,
+            cycles: 0,
+            tracer: None,
+            stub_ly: false,
+            watches: None,
+            rom_checksum,
         };
         gb.init();
         gb
     }
 
+    pub fn new_sgb(cartridge: Cartridge) -> Self {
+        // The SGB only honors packets from games that declare SGB support in their header
+        let sgb = Sgb::new(sgb::supports_sgb(&cartridge));
+        let mut gb = Gameboy::new_dmg(cartridge);
+        gb.sgb = Some(sgb);
+        gb.cpu.init_sgb();
+        gb
+    }
+
     pub fn init(&mut self) {
         self.cpu.init();
     }
 
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn rom_checksum(&self) -> u32 {
+        self.rom_checksum
+    }
+
+    // Serialize the whole emulated system. Settings such as the palette, tracer and
+    // link cable aren't included.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::default();
+        w.bytes(savestate::MAGIC);
+        w.u16(savestate::VERSION);
+        w.u32(self.rom_checksum);
+
+        self.cpu.save(&mut w);
+        self.ppu.save(&mut w);
+        self.lcd.save(&mut w);
+        self.joypad.save(&mut w);
+        self.timers.save(&mut w);
+        self.serial.save(&mut w);
+        self.cartridge.save(&mut w);
+        self.wram.save(&mut w);
+        self.vram.save(&mut w);
+        self.oam.save(&mut w);
+        self.dma.save(&mut w);
+        self.io_regs.save(&mut w);
+        self.high_ram.save(&mut w);
+        w.bool(self.sgb.is_some());
+        if let Some(sgb) = &self.sgb {
+            sgb.save(&mut w);
+        }
+        w.u64(self.cycles);
+        w.data
+    }
+
+    // Restore a state from `save_state`. It must have been made with the same ROM and
+    // model. On error the Gameboy is left as it was.
+    pub fn load_state(&mut self, data: &[u8]) -> io::Result<()> {
+        if savestate::read_header(data)? != self.rom_checksum {
+            return Err(savestate::invalid("save state is for a different ROM"));
+        }
+
+        let backup = self.save_state();
+        let result = self.load_components(&mut StateReader::new(&data[savestate::HEADER_LEN..]));
+        if result.is_err() {
+            self.load_components(&mut StateReader::new(&backup[savestate::HEADER_LEN..]))
+                .expect("restoring a state that was just saved");
+        }
+        result
+    }
+
+    fn load_components(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.cpu.load(r)?;
+        self.ppu.load(r)?;
+        self.lcd.load(r)?;
+        self.joypad.load(r)?;
+        self.timers.load(r)?;
+        self.serial.load(r)?;
+        self.cartridge.load(r)?;
+        self.wram.load(r)?;
+        self.vram.load(r)?;
+        self.oam.load(r)?;
+        self.dma.load(r)?;
+        self.io_regs.load(r)?;
+        self.high_ram.load(r)?;
+        match (r.bool()?, &mut self.sgb) {
+            (true, Some(sgb)) => sgb.load(r)?,
+            (false, None) => {}
+            _ => return Err(savestate::invalid("save state is for a different model")),
+        }
+        self.cycles = r.u64()?;
+        self.ticked_cycles = 0;
+        if !r.is_empty() {
+            return Err(savestate::invalid("save state has trailing data"));
+        }
+        Ok(())
+    }
+
+    // The ROM bank `addr` is in, if it is in ROM
+    pub fn rom_bank(&self, addr: u16) -> Option<usize> {
+        match addr {
+            0x0000..=0x3FFF => Some(0),
+            0x4000..=0x7FFF => Some(self.cartridge.rom_bank()),
+            _ => None,
+        }
+    }
+
     pub fn read(&self, addr: u16) -> u8 {
+        let val = self.peek(addr);
+        self.watch_access(Access::Read, addr, val, false);
+        val
+    }
+
+    // Reports a write that changes what memory holds, which for the cartridge's bank
+    // registers means the value read back at the address, as changed
+    pub fn write(&mut self, addr: u16, val: u8) {
+        if self.watches.is_none() {
+            return self.poke(addr, val);
+        }
+
+        let (old_val, old_bank) = (self.peek(addr), self.cartridge.rom_bank());
+        self.poke(addr, val);
+        self.watch_access(Access::Write, addr, val, self.peek(addr) != old_val);
+
+        let bank = self.cartridge.rom_bank();
+        if bank != old_bank {
+            self.watch_event(DebugEvent::BankSwitch { from: old_bank, to: bank });
+        }
+        if addr == 0xFF46 {
+            self.watch_event(DebugEvent::DmaStart((val as u16) << 8));
+        }
+    }
+
+    // Read without triggering watchpoints, e.g. for the debugger
+    pub fn peek(&self, addr: u16) -> u8 {
         match addr {
             0x0000..=0x7FFF => self.cartridge.read(addr), // Cartridge ROM
             0x8000..=0x9FFF => self.vram.read(addr),      // Video RAM
@@ -56,12 +238,13 @@ impl Gameboy {
             0xE000..=0xFDFF => self.wram.read(addr - 0x2000), // Echo RAM
             0xFE00..=0xFE9F => self.oam.read(addr),       // OAM
             0xFEA0..=0xFEFF => 0xFF,                      // Forbidden Memory
-            0xFF00..=0xFF7F => self.io_regs.read(addr),   // IO Registers
+            0xFF00..=0xFF7F => self.read_io(addr),        // IO Registers
             0xFF80.. => self.high_ram.read(addr),  // High RAM, Interrupt Enable
         }
     }
 
-    pub fn write(&mut self, addr: u16, val: u8) {
+    // Write without triggering watchpoints or events
+    pub fn poke(&mut self, addr: u16, val: u8) {
         match addr {
             0x0000..=0x7FFF => self.cartridge.write(addr, val), // Cartridge ROM
             0x8000..=0x9FFF => self.vram.write(addr, val),      // Video RAM
@@ -70,21 +253,46 @@ impl Gameboy {
             0xE000..=0xFDFF => self.wram.write(addr - 0x2000, val), // Echo RAM
             0xFE00..=0xFE9F => self.oam.write(addr, val),       // OAM
             0xFEA0..=0xFEFF => (),                              // Forbidden Memory
-            0xFF00..=0xFF7F => {
-                // IO Regs
-                self.io_regs.write(addr, val);
+            0xFF00..=0xFF7F => self.write_io(addr, val),        // IO Registers
+            0xFF80.. => self.high_ram.write(addr, val), // High RAM, Interrupt Enable Register
+        }
+    }
+
+    fn read_io(&self, addr: u16) -> u8 {
+        if addr == 0xFF44 && self.stub_ly {
+            return 0x90;
+        }
+        self.io_regs.read(addr) | IO_READ_MASKS[addr as usize - 0xFF00]
+    }
 
-                // OAM DMA
+    fn write_io(&mut self, addr: u16, val: u8) {
+        match addr {
+            0xFF04..=0xFF07 => self.timers.write(&mut self.io_regs, addr, val), // Timers
+            0xFF41 => {
+                // The mode and LYC=LY bits of STAT are read-only
+                let stat = self.io_regs.read(0xFF41);
+                self.io_regs.write(0xFF41, (val & 0b1111_1000) | (stat & 0b0000_0111));
+            }
+            0xFF00 => {
+                // Only the select bits of JOYP are writable, the button lines are driven
+                // by the joypad
+                let joyp = self.io_regs.read(0xFF00);
+                self.io_regs.write(0xFF00, (val & 0b0011_0000) | (joyp & 0b0000_1111));
+                self.joypad.tick(&mut self.io_regs);
+
+                // SGB command packets are sent through the joypad select lines
+                if let Some(sgb) = &mut self.sgb {
+                    sgb.write_joyp(val);
+                }
+            }
+            0xFF44 => (), // LY is read-only
+            _ if IO_READ_MASKS[addr as usize - 0xFF00] == 0xFF => (), // Unmapped
+            _ => {
+                self.io_regs.write(addr, val);
                 if addr == 0xFF46 {
-                    let mut data: [u8; 160] = [0; 160];
-                    let value_base = (val as u16) << 8;
-                    for i in 0x00..=0x9F {
-                        data[i as usize] = self.read(value_base | i);
-                    }
-                    self.oam.dma(&data);
+                    self.dma.start(val);
                 }
             }
-            0xFF80.. => self.high_ram.write(addr, val), // High RAM, Interrupt Enable Register
         }
     }
 
@@ -97,14 +305,55 @@ impl Gameboy {
         self.write(addr, (val & 0xFF) as u8);
     }
 
+    // Memory accesses made by the CPU. In cycle-accurate mode each one first advances the
+    // rest of the system by the M-cycle it takes.
+    // While OAM DMA is copying, everything below the IO registers is cut off: reads see
+    // the byte being copied (OAM itself reads 0xFF) and writes are lost.
+    fn cpu_read(&mut self, addr: u16) -> u8 {
+        self.internal_cycle();
+        match addr {
+            0xFE00..=0xFEFF if self.dma.is_blocking() => 0xFF,
+            ..=0xFDFF if self.dma.is_blocking() => self.dma.bus_byte(),
+            _ => self.read(addr),
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, val: u8) {
+        self.internal_cycle();
+        if addr >= 0xFF00 || !self.dma.is_blocking() {
+            self.write(addr, val);
+        }
+    }
+
+    fn watch_access(&self, access: Access, addr: u16, val: u8, changed: bool) {
+        if let Some(watches) = &self.watches {
+            watches.access(access, addr, val, changed, self.rom_bank(addr));
+        }
+    }
+
+    fn watch_event(&self, event: DebugEvent) {
+        if let Some(watches) = &self.watches {
+            watches.event(event);
+        }
+    }
+
+    // Advance the rest of the system by one M-cycle in cycle-accurate mode. The CPU calls
+    // this for each M-cycle it spends, whether accessing memory or not.
+    fn internal_cycle(&mut self) {
+        if self.cycle_accurate {
+            self.tick_components(1);
+            self.ticked_cycles += 1;
+        }
+    }
+
     fn stack_push(&mut self, val: u8) {
-        self.cpu.sp -= 1;
-        self.write(self.cpu.sp, val);
+        self.cpu.sp = self.cpu.sp.wrapping_sub(1);
+        self.cpu_write(self.cpu.sp, val);
     }
 
     fn stack_pop(&mut self) -> u8 {
-        let res = self.read(self.cpu.sp);
-        self.cpu.sp += 1;
+        let res = self.cpu_read(self.cpu.sp);
+        self.cpu.sp = self.cpu.sp.wrapping_add(1);
         res
     }
 
@@ -118,8 +367,12 @@ impl Gameboy {
     }
 
     fn fetch(&mut self) -> u8 {
-        let fetched = self.read(self.cpu.pc); // Fetch a value at the current PC
-        self.cpu.pc += 1; // Increment PC
+        let fetched = self.cpu_read(self.cpu.pc); // Fetch a value at the current PC
+        // Increment PC, unless the HALT bug makes this byte get read twice
+        match self.cpu.halt_bug {
+            true => self.cpu.halt_bug = false,
+            false => self.cpu.pc += 1,
+        }
         fetched // Return fetched value
     }
 
@@ -130,7 +383,8 @@ impl Gameboy {
         (hi << 8) | lo
     }
 
-    fn execute_opcode(&mut self, opcode: &Opcode) -> u8 {
+    // Execute a decoded instruction, returning whether a conditional branch was taken
+    fn execute_opcode(&mut self, opcode: &Opcode) -> bool {
         match opcode {
             Opcode::ADC(register) => {
                 let rhs = self.cpu.read_register(register);
@@ -140,17 +394,17 @@ impl Gameboy {
                     true,
                     &mut self.cpu.registers.flags,
                 );
-                1
+                false
             }
             Opcode::ADCHL => {
-                let rhs = self.read(self.cpu.registers.hl());
+                let rhs = self.cpu_read(self.cpu.registers.hl());
                 self.cpu.registers.a = Gameboy::do_add(
                     self.cpu.registers.a,
                     rhs,
                     true,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::ADCI => {
                 let rhs = self.fetch();
@@ -160,7 +414,7 @@ impl Gameboy {
                     true,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::ADD(register) => {
                 let rhs = self.cpu.read_register(register);
@@ -170,17 +424,17 @@ impl Gameboy {
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                1
+                false
             }
             Opcode::ADDHL => {
-                let rhs = self.read(self.cpu.registers.hl());
+                let rhs = self.cpu_read(self.cpu.registers.hl());
                 self.cpu.registers.a = Gameboy::do_add(
                     self.cpu.registers.a,
                     rhs,
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::ADDI => {
                 let rhs = self.fetch();
@@ -190,7 +444,7 @@ impl Gameboy {
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::ADDHLR(wide_register) => {
                 let res = Gameboy::do_add_16(
@@ -199,7 +453,7 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.registers.set_hl(res);
-                2
+                false
             }
             Opcode::ADDSP => {
                 let res = Gameboy::do_signed_add(
@@ -208,70 +462,66 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.sp = res;
-                4
+                false
             }
             Opcode::AND(register) => {
                 let rhs = self.cpu.read_register(register);
                 self.cpu.registers.a =
                     Gameboy::do_and(self.cpu.registers.a, rhs, &mut self.cpu.registers.flags);
-                1
+                false
             }
             Opcode::ANDHL => {
-                let rhs = self.read(self.cpu.registers.hl());
+                let rhs = self.cpu_read(self.cpu.registers.hl());
                 self.cpu.registers.a =
                     Gameboy::do_and(self.cpu.registers.a, rhs, &mut self.cpu.registers.flags);
-                2
+                false
             }
             Opcode::ANDI => {
                 let rhs = self.fetch();
                 self.cpu.registers.a =
                     Gameboy::do_and(self.cpu.registers.a, rhs, &mut self.cpu.registers.flags);
-                2
+                false
             }
             Opcode::BIT(bit, register) => {
                 let value = self.cpu.read_register(register);
                 Gameboy::do_bit(*bit, value, &mut self.cpu.registers.flags);
-                2
+                false
             }
             Opcode::BITHL(bit) => {
-                let value = self.read(self.cpu.registers.hl());
+                let value = self.cpu_read(self.cpu.registers.hl());
                 Gameboy::do_bit(*bit, value, &mut self.cpu.registers.flags);
-                2
+                false
             }
             Opcode::CALL => {
                 let target = self.fetch_word();
+                self.internal_cycle();
                 self.stack_push_word(self.cpu.pc);
                 self.cpu.pc = target;
-                6
+                false
             }
             Opcode::CALLCC(condition) => {
                 let target = self.fetch_word();
                 if self.cpu.registers.flags.contains(*condition) {
+                    self.internal_cycle();
                     self.stack_push_word(self.cpu.pc);
                     self.cpu.pc = target;
-                    6
+                    true
                 } else {
-                    3
+                    false
                 }
             }
             Opcode::CALLNCC(condition) => {
                 let target = self.fetch_word();
                 if !self.cpu.registers.flags.contains(*condition) {
+                    self.internal_cycle();
                     self.stack_push_word(self.cpu.pc);
                     self.cpu.pc = target;
-                    6
+                    true
                 } else {
-                    3
+                    false
                 }
             }
-            Opcode::CB => {
-                // Double-length opcodes: grab the next code and use the CB code map to execute
-                let op = self.fetch();
-                let opcode = CB_OPCODES
-                    .get(&op)
-                    .unwrap_or_else(|| panic!("Invalid opcode encountered: {}", op));
-                self.execute_opcode(opcode)
-            }
+            Opcode::CB => unreachable!("CB-prefixed opcodes are decoded by execute"),
             Opcode::CCF => {
                 self.cpu.registers.flags.remove(CpuFlags::N);
                 self.cpu.registers.flags.remove(CpuFlags::H);
@@ -279,7 +529,7 @@ impl Gameboy {
                     .registers
                     .flags
                     .set(CpuFlags::C, !self.cpu.registers.flags.contains(CpuFlags::C));
-                1
+                false
             }
             Opcode::CP(register) => {
                 Gameboy::do_sub(
@@ -288,16 +538,16 @@ impl Gameboy {
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                1
+                false
             }
             Opcode::CPHL => {
                 Gameboy::do_sub(
                     self.cpu.registers.a,
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::CPI => {
                 Gameboy::do_sub(
@@ -306,18 +556,18 @@ impl Gameboy {
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::CPL => {
                 self.cpu.registers.a = !self.cpu.registers.a;
                 self.cpu.registers.flags.insert(CpuFlags::N);
                 self.cpu.registers.flags.insert(CpuFlags::H);
-                1
+                false
             }
             Opcode::DAA => {
                 self.cpu.registers.a =
                     Gameboy::do_daa(self.cpu.registers.a, &mut self.cpu.registers.flags);
-                1
+                false
             }
             Opcode::DEC(register) => {
                 let res = Gameboy::do_dec(
@@ -325,32 +575,38 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                1
+                false
             }
             Opcode::DECHL => {
                 let res = Gameboy::do_dec(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                3
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::DECW(wide_register) => {
                 let res = Gameboy::do_dec_16(self.cpu.read_wide_register(wide_register));
                 self.cpu.write_wide_register(wide_register, res);
-                2
+                false
             }
             Opcode::DI => {
                 self.cpu.ime = false;
-                1
+                self.cpu.ime_pending = false;
+                false
             }
             Opcode::EI => {
-                self.cpu.ime = true;
-                1
+                self.cpu.ime_pending = true;
+                false
             }
             Opcode::HALT => {
-                self.cpu.halted = true;
-                1
+                // With IME off and an interrupt already pending, HALT doesn't halt and
+                // the following byte is read twice
+                match (self.cpu.ime, self.check_interrupts()) {
+                    (false, Some(_)) => self.cpu.halt_bug = true,
+                    _ => self.cpu.halted = true,
+                }
+                false
             },
             Opcode::INC(register) => {
                 let res = Gameboy::do_inc(
@@ -358,150 +614,151 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                1
+                false
             }
             Opcode::INCHL => {
                 let res = Gameboy::do_inc(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                3
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::INCW(wide_register) => {
                 let res = Gameboy::do_inc_16(self.cpu.read_wide_register(wide_register));
                 self.cpu.write_wide_register(wide_register, res);
-                2
+                false
             }
             Opcode::JP => {
                 let target = self.fetch_word();
                 self.cpu.pc = target;
-                4
+                false
             }
             Opcode::JPCC(condition) => {
                 let target = self.fetch_word();
                 if self.cpu.registers.flags.contains(*condition) {
                     self.cpu.pc = target;
-                    4
+                    true
                 } else {
-                    3
+                    false
                 }
             }
             Opcode::JPNCC(condition) => {
                 let target = self.fetch_word();
                 if !self.cpu.registers.flags.contains(*condition) {
                     self.cpu.pc = target;
-                    4
+                    true
                 } else {
-                    3
+                    false
                 }
             }
             Opcode::JPHL => {
                 self.cpu.pc = self.cpu.registers.hl();
-                1
+                false
             }
             Opcode::JR => {
                 let jump = self.fetch() as i8;
                 self.cpu.pc = ((self.cpu.pc as u32 as i32) + (jump as i32)) as u16;
-                3
+                false
             }
             Opcode::JRCC(condition) => {
                 let jump = self.fetch() as i8;
                 if self.cpu.registers.flags.contains(*condition) {
                     self.cpu.pc = ((self.cpu.pc as u32 as i32) + (jump as i32)) as u16;
-                    3
+                    true
                 } else {
-                    2
+                    false
                 }
             }
             Opcode::JRNCC(condition) => {
                 let jump = self.fetch() as i8;
                 if !self.cpu.registers.flags.contains(*condition) {
                     self.cpu.pc = ((self.cpu.pc as u32 as i32) + (jump as i32)) as u16;
-                    3
+                    true
                 } else {
-                    2
+                    false
                 }
             }
             Opcode::LDRR(dest, source) => {
                 self.cpu
                     .write_register(dest, self.cpu.read_register(source));
-                1
+                false
             }
             Opcode::LDRI(dest) => {
                 let res = self.fetch();
                 self.cpu.write_register(dest, res);
-                2
+                false
             }
             Opcode::LDWRI(dest) => {
                 let res = self.fetch_word();
                 self.cpu.write_wide_register(dest, res);
-                3
+                false
             }
             Opcode::LDHLR(source) => {
-                self.write(self.cpu.registers.hl(), self.cpu.read_register(source));
-                2
+                self.cpu_write(self.cpu.registers.hl(), self.cpu.read_register(source));
+                false
             }
             Opcode::LDHLI => {
                 let res = self.fetch();
-                self.write(self.cpu.registers.hl(), res);
-                3
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::LDRHL(dest) => {
-                self.cpu
-                    .write_register(dest, self.read(self.cpu.registers.hl()));
-                2
+                let val = self.cpu_read(self.cpu.registers.hl());
+                self.cpu.write_register(dest, val);
+                false
             }
             Opcode::LDWRA(dest) => {
-                self.write(self.cpu.read_wide_register(dest), self.cpu.registers.a);
-                2
+                self.cpu_write(self.cpu.read_wide_register(dest), self.cpu.registers.a);
+                false
             }
             Opcode::LDIWA => {
                 let addr = self.fetch_word();
-                self.write(addr, self.cpu.registers.a);
-                4
+                self.cpu_write(addr, self.cpu.registers.a);
+                false
             }
             Opcode::LDAWR(source) => {
-                self.cpu.registers.a = self.read(self.cpu.read_wide_register(source));
-                2
+                self.cpu.registers.a = self.cpu_read(self.cpu.read_wide_register(source));
+                false
             }
             Opcode::LDAIW => {
                 let addr = self.fetch_word();
-                self.cpu.registers.a = self.read(addr);
-                4
+                self.cpu.registers.a = self.cpu_read(addr);
+                false
             }
             Opcode::LDHLIA => {
-                self.write(self.cpu.registers.hl(), self.cpu.registers.a);
+                self.cpu_write(self.cpu.registers.hl(), self.cpu.registers.a);
                 self.cpu
                     .registers
                     .set_hl(self.cpu.registers.hl().wrapping_add(1));
-                2
+                false
             }
             Opcode::LDHLDA => {
-                self.write(self.cpu.registers.hl(), self.cpu.registers.a);
+                self.cpu_write(self.cpu.registers.hl(), self.cpu.registers.a);
                 self.cpu
                     .registers
                     .set_hl(self.cpu.registers.hl().wrapping_sub(1));
-                2
+                false
             }
             Opcode::LDAHLD => {
-                self.cpu.registers.a = self.read(self.cpu.registers.hl());
+                self.cpu.registers.a = self.cpu_read(self.cpu.registers.hl());
                 self.cpu
                     .registers
                     .set_hl(self.cpu.registers.hl().wrapping_sub(1));
-                2
+                false
             }
             Opcode::LDAHLI => {
-                self.cpu.registers.a = self.read(self.cpu.registers.hl());
+                self.cpu.registers.a = self.cpu_read(self.cpu.registers.hl());
                 self.cpu
                     .registers
                     .set_hl(self.cpu.registers.hl().wrapping_add(1));
-                2
+                false
             }
             Opcode::LDISP => {
                 let addr = self.fetch_word();
-                self.write_word(addr, self.cpu.sp);
-                5
+                self.cpu_write(addr, (self.cpu.sp & 0xFF) as u8);
+                self.cpu_write(addr.wrapping_add(1), (self.cpu.sp >> 8) as u8);
+                false
             }
             Opcode::LDHLSP => {
                 let value = Gameboy::do_signed_add(
@@ -510,104 +767,107 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.registers.set_hl(value);
-                3
+                false
             }
             Opcode::LDSPHL => {
                 self.cpu.sp = self.cpu.registers.hl();
-                2
+                false
             }
             Opcode::LDIOA => {
                 let addr = self.fetch() as u16 + 0xFF00;
-                self.write(addr, self.cpu.registers.a);
-                3
+                self.cpu_write(addr, self.cpu.registers.a);
+                false
             }
             Opcode::LDIOCA => {
                 let addr = self.cpu.registers.c as u16 + 0xFF00;
-                self.write(addr, self.cpu.registers.a);
-                2
+                self.cpu_write(addr, self.cpu.registers.a);
+                false
             }
             Opcode::LDAIO => {
                 let addr = self.fetch() as u16 + 0xFF00;
-                self.cpu.registers.a = self.read(addr);
-                3
+                self.cpu.registers.a = self.cpu_read(addr);
+                false
             }
             Opcode::LDAIOC => {
                 let addr = self.cpu.registers.c as u16 + 0xFF00;
-                self.cpu.registers.a = self.read(addr);
-                2
+                self.cpu.registers.a = self.cpu_read(addr);
+                false
             }
-            Opcode::NOP => 1,
+            Opcode::NOP => false,
             Opcode::OR(register) => {
                 self.cpu.registers.a = Gameboy::do_or(
                     self.cpu.registers.a,
                     self.cpu.read_register(register),
                     &mut self.cpu.registers.flags,
                 );
-                1
+                false
             }
             Opcode::ORHL => {
                 self.cpu.registers.a = Gameboy::do_or(
                     self.cpu.registers.a,
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::ORI => {
                 let rhs = self.fetch();
                 self.cpu.registers.a =
                     Gameboy::do_or(self.cpu.registers.a, rhs, &mut self.cpu.registers.flags);
-                2
+                false
             }
             Opcode::POPWR(wide_register) => {
                 let val = self.stack_pop_word();
                 self.cpu.write_wide_register(wide_register, val);
-                3
+                false
             }
             Opcode::PUSHWR(wide_register) => {
+                self.internal_cycle();
                 self.stack_push_word(self.cpu.read_wide_register(wide_register));
-                4
+                false
             }
             Opcode::RES(bit, register) => {
                 self.cpu.write_register(
                     register,
                     Gameboy::do_res(*bit, self.cpu.read_register(register)),
                 );
-                2
+                false
             }
             Opcode::RESHL(bit) => {
-                let res = Gameboy::do_res(*bit, self.read(self.cpu.registers.hl()));
-                self.write(self.cpu.registers.hl(), res);
-                4
+                let res = Gameboy::do_res(*bit, self.cpu_read(self.cpu.registers.hl()));
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::RET => {
                 let target = self.stack_pop_word();
                 self.cpu.pc = target;
-                4
+                false
             }
             Opcode::RETCC(condition) => {
+                self.internal_cycle();
                 if self.cpu.registers.flags.contains(*condition) {
                     let target = self.stack_pop_word();
                     self.cpu.pc = target;
-                    5
+                    true
                 } else {
-                    2
+                    false
                 }
             }
             Opcode::RETNCC(condition) => {
+                self.internal_cycle();
                 if !self.cpu.registers.flags.contains(*condition) {
                     let target = self.stack_pop_word();
                     self.cpu.pc = target;
-                    5
+                    true
                 } else {
-                    2
+                    false
                 }
             }
             Opcode::RETI => {
                 self.cpu.ime = true;
                 let target = self.stack_pop_word();
                 self.cpu.pc = target;
-                4
+                false
             }
             Opcode::RL(register) => {
                 let res = Gameboy::do_rl(
@@ -615,21 +875,21 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::RLHL => {
                 let res = Gameboy::do_rl(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::RLA => {
                 self.cpu.registers.a =
                     Gameboy::do_rl(self.cpu.registers.a, &mut self.cpu.registers.flags);
                 self.cpu.registers.flags.remove(CpuFlags::Z);
-                1
+                false
             }
             Opcode::RLC(register) => {
                 let res = Gameboy::do_rlc(
@@ -637,21 +897,21 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::RLCHL => {
                 let res = Gameboy::do_rlc(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::RLCA => {
                 self.cpu.registers.a =
                     Gameboy::do_rlc(self.cpu.registers.a, &mut self.cpu.registers.flags);
                 self.cpu.registers.flags.remove(CpuFlags::Z);
-                1
+                false
             }
             Opcode::RR(register) => {
                 let res = Gameboy::do_rr(
@@ -659,21 +919,21 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::RRHL => {
                 let res = Gameboy::do_rr(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::RRA => {
                 self.cpu.registers.a =
                     Gameboy::do_rr(self.cpu.registers.a, &mut self.cpu.registers.flags);
                 self.cpu.registers.flags.remove(CpuFlags::Z);
-                1
+                false
             }
             Opcode::RRC(register) => {
                 let res = Gameboy::do_rrc(
@@ -681,25 +941,25 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::RRCHL => {
                 let res = Gameboy::do_rrc(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::RRCA => {
                 self.cpu.registers.a =
                     Gameboy::do_rrc(self.cpu.registers.a, &mut self.cpu.registers.flags);
                 self.cpu.registers.flags.remove(CpuFlags::Z);
-                1
+                false
             }
             Opcode::RST(vector) => {
                 self.rst(*vector);
-                4
+                false
             }
             Opcode::SBC(register) => {
                 let rhs = self.cpu.read_register(register);
@@ -709,17 +969,17 @@ impl Gameboy {
                     true,
                     &mut self.cpu.registers.flags,
                 );
-                1
+                false
             }
             Opcode::SBCHL => {
-                let rhs = self.read(self.cpu.registers.hl());
+                let rhs = self.cpu_read(self.cpu.registers.hl());
                 self.cpu.registers.a = Gameboy::do_sub(
                     self.cpu.registers.a,
                     rhs,
                     true,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::SBCI => {
                 let rhs = self.fetch();
@@ -729,24 +989,24 @@ impl Gameboy {
                     true,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::SCF => {
                 self.cpu.registers.flags.insert(CpuFlags::C);
                 self.cpu.registers.flags.remove(CpuFlags::N | CpuFlags::H);
-                1
+                false
             }
             Opcode::SET(bit, register) => {
                 self.cpu.write_register(
                     register,
                     Gameboy::do_set(*bit, self.cpu.read_register(register)),
                 );
-                2
+                false
             }
             Opcode::SETHL(bit) => {
-                let res = Gameboy::do_set(*bit, self.read(self.cpu.registers.hl()));
-                self.write(self.cpu.registers.hl(), res);
-                4
+                let res = Gameboy::do_set(*bit, self.cpu_read(self.cpu.registers.hl()));
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::SLA(register) => {
                 let res = Gameboy::do_sla(
@@ -754,15 +1014,15 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::SLAHL => {
                 let res = Gameboy::do_sla(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::SRA(register) => {
                 let res = Gameboy::do_sra(
@@ -770,15 +1030,15 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::SRAHL => {
                 let res = Gameboy::do_sra(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::SRL(register) => {
                 let res = Gameboy::do_srl(
@@ -786,17 +1046,24 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::SRLHL => {
                 let res = Gameboy::do_srl(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
+            }
+            Opcode::STOP => {
+                // STOP is followed by an ignored byte. The system clock halts, resetting
+                // DIV, until a selected joypad line goes low.
+                self.fetch();
+                self.timers.write(&mut self.io_regs, 0xFF04, 0);
+                self.cpu.stopped = true;
+                false
             }
-            Opcode::STOP => todo!(),
             Opcode::SUB(register) => {
                 let rhs = self.cpu.read_register(register);
                 self.cpu.registers.a = Gameboy::do_sub(
@@ -805,17 +1072,17 @@ impl Gameboy {
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                1
+                false
             }
             Opcode::SUBHL => {
-                let rhs = self.read(self.cpu.registers.hl());
+                let rhs = self.cpu_read(self.cpu.registers.hl());
                 self.cpu.registers.a = Gameboy::do_sub(
                     self.cpu.registers.a,
                     rhs,
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::SUBI => {
                 let rhs = self.fetch();
@@ -825,7 +1092,7 @@ impl Gameboy {
                     false,
                     &mut self.cpu.registers.flags,
                 );
-                2
+                false
             }
             Opcode::SWAP(register) => {
                 let res = Gameboy::do_swap(
@@ -833,52 +1100,63 @@ impl Gameboy {
                     &mut self.cpu.registers.flags,
                 );
                 self.cpu.write_register(register, res);
-                2
+                false
             }
             Opcode::SWAPHL => {
                 let res = Gameboy::do_swap(
-                    self.read(self.cpu.registers.hl()),
+                    self.cpu_read(self.cpu.registers.hl()),
                     &mut self.cpu.registers.flags,
                 );
-                self.write(self.cpu.registers.hl(), res);
-                4
+                self.cpu_write(self.cpu.registers.hl(), res);
+                false
             }
             Opcode::XOR(register) => {
                 let rhs = self.cpu.read_register(register);
                 self.cpu.registers.a =
                     Gameboy::do_xor(self.cpu.registers.a, rhs, &mut self.cpu.registers.flags);
-                1
+                false
             }
             Opcode::XORHL => {
-                let rhs = self.read(self.cpu.registers.hl());
+                let rhs = self.cpu_read(self.cpu.registers.hl());
                 self.cpu.registers.a =
                     Gameboy::do_xor(self.cpu.registers.a, rhs, &mut self.cpu.registers.flags);
-                2
+                false
             }
             Opcode::XORI => {
                 let rhs = self.fetch();
                 self.cpu.registers.a =
                     Gameboy::do_xor(self.cpu.registers.a, rhs, &mut self.cpu.registers.flags);
-                2
+                false
             }
         }
     }
 
     pub fn execute(&mut self) -> u8 {
+        if self.cpu.stopped {
+            // Only the joypad runs while stopped, and pressing a selected button resumes
+            self.joypad.tick(&mut self.io_regs);
+            if self.io_regs.read(0xFF00) & 0x0F != 0x0F {
+                self.cpu.stopped = false;
+            }
+            self.cycles += 1;
+            return 1;
+        }
+
         // Before executing anything, we need to check for CPU interrupts:
         let interrupt = self.check_interrupts();
-
-        if self.cpu.halted && interrupt.is_some() {
-            self.cpu.halted = false;
-        }
+        // A pending EI only takes effect once the next instruction is done
+        let enable_ime = self.cpu.ime_pending;
 
         let m_cycles = match (self.cpu.ime, interrupt) {
-            (true, Some(interrupt_num)) => {
-                // IME must be enabled to service an interrupt,
-                // however an interrupt will wake up a HALT regardless.
-                if self.cpu.ime {
-                    self.service_interrupt(interrupt_num);
-                }
+            (_, Some(_)) if self.cpu.halted => {
+                // Any pending interrupt wakes up a HALT regardless of IME, which takes
+                // one M-cycle before the CPU carries on or dispatches the interrupt
+                self.cpu.halted = false;
+                1
+            }
+            (true, Some(_)) => {
+                // IME must be enabled to service an interrupt
+                self.service_interrupt();
                 5
             }
             (_, _) => {
@@ -886,12 +1164,32 @@ impl Gameboy {
                 match self.cpu.halted {
                     false => {
                         // No interrupt not halted, fetch an opcode and map it to an actual Opcode
-                        let op = self.fetch();
-                        let opcode = OPCODES
-                            .get(&op)
-                            .unwrap_or_else(|| panic!("Invalid opcode encountered: {}", op));
-                        // Execute the opcodes, tracking the cycles used
-                        self.execute_opcode(opcode)
+                        if let Some(mut tracer) = self.tracer.take() {
+                            tracer.trace(self);
+                            self.tracer = Some(tracer);
+                        }
+                        if self.watches.is_some() {
+                            self.watch_access(Access::Execute, self.cpu.pc, self.peek(self.cpu.pc), false);
+                        }
+
+                        let (opcode, info) = match self.fetch() {
+                            // Double-length opcodes: grab the next code and use the CB code map
+                            0xCB => {
+                                let op = self.fetch() as usize;
+                                (&CB_OPCODES[op], &CB_INSTRUCTIONS[op])
+                            }
+                            op => {
+                                let opcode = OPCODES[op as usize]
+                                    .as_ref()
+                                    .unwrap_or_else(|| panic!("Invalid opcode encountered: {}", op));
+                                (opcode, &INSTRUCTIONS[op as usize])
+                            }
+                        };
+                        // Execute the opcode, taking its cycles from the instruction table
+                        match self.execute_opcode(opcode) {
+                            true => info.taken_cycles,
+                            false => info.cycles,
+                        }
                     },
                     true => {
                         1
@@ -900,7 +1198,31 @@ impl Gameboy {
             }
         };
 
-        // Tick other components the same number of cycles
+        if enable_ime && self.cpu.ime_pending {
+            self.cpu.ime = true;
+            self.cpu.ime_pending = false;
+        }
+
+        // Tick other components for whatever cycles haven't been ticked already
+        let ticked_cycles = std::mem::take(&mut self.ticked_cycles);
+        debug_assert!(ticked_cycles <= m_cycles, "memory accesses took longer than the instruction");
+        self.tick_components(m_cycles - ticked_cycles);
+
+        self.cycles += m_cycles as u64;
+        m_cycles
+    }
+
+    fn tick_components(&mut self, m_cycles: u8) {
+        if self.dma.is_active() {
+            for _ in 0..m_cycles {
+                if let Some((source, dest)) = self.dma.tick() {
+                    let val = self.peek(source);
+                    self.oam.write(dest, val);
+                    self.dma.set_bus_byte(val);
+                }
+            }
+        }
+        let ly = self.io_regs.read(0xFF44);
         self.ppu.tick(
             m_cycles,
             &self.vram,
@@ -908,24 +1230,34 @@ impl Gameboy {
             &mut self.io_regs,
             &mut self.lcd,
         );
+        if self.watches.is_some() && self.io_regs.read(0xFF44) != ly {
+            self.watch_event(DebugEvent::Line(self.io_regs.read(0xFF44)));
+        }
         self.timers.tick(&mut self.io_regs, m_cycles);
+        self.serial.tick(&mut self.io_regs, m_cycles);
         self.joypad.tick(&mut self.io_regs);
-
-        m_cycles
+        if let Some(sgb) = &mut self.sgb {
+            sgb.apply_joyp(&mut self.io_regs);
+            sgb.tick(&self.vram, &self.io_regs, &self.lcd);
+        }
     }
 
     pub fn execute_frame(&mut self) {
-        // Execute a single frame worth of opcodes
-        // TODO: Do I need to worry about the few extra frames for sync?
+        // Execute opcodes until the PPU finishes a frame at VBlank, so the LCD always
+        // holds a complete image afterwards. The cycle limit is a guard in case no frame
+        // is ever produced.
         let mut cycle_count = 0;
-        while cycle_count < CYCLES_PER_FRAME {
+        while cycle_count < CYCLES_PER_FRAME * 2 {
             cycle_count += (self.execute() as u32) * 4;
+            if self.lcd.take_frame_ready() {
+                break;
+            }
         }
     }
 
     fn check_interrupts(&self) -> Option<u8> {
-        let if_reg = self.read(0xFF0F);
-        let interrupts = self.read(0xFFFF) & if_reg;
+        let if_reg = self.peek(0xFF0F);
+        let interrupts = self.peek(0xFFFF) & if_reg & 0x1F;
 
         match interrupts {
             0 => None,
@@ -933,15 +1265,39 @@ impl Gameboy {
         }
     }
 
-    fn service_interrupt(&mut self, interrupt_num: u8) {
-        // Disable IME and IF bit for this interrupt
+    fn service_interrupt(&mut self) {
         self.cpu.ime = false;
-        self.write(0xFF0F, self.read(0xFF0F) & (!(1 << interrupt_num)));
+        self.cpu.ime_pending = false;
+
+        // The interrupt to service is only decided after the upper byte of PC has been
+        // pushed. If that push overwrote IE and nothing is left pending, dispatch is
+        // cancelled and execution continues at 0x0000.
+        self.internal_cycle();
+        self.internal_cycle();
+        // After `EI; HALT` with an interrupt already pending, the HALT bug is cut short by
+        // dispatch and the interrupt returns to the HALT itself
+        let pc = match std::mem::take(&mut self.cpu.halt_bug) {
+            true => self.cpu.pc.wrapping_sub(1),
+            false => self.cpu.pc,
+        };
+        self.stack_push((pc >> 8) as u8);
+        let interrupt = self.check_interrupts();
+        self.stack_push((pc & 0xFF) as u8);
 
-        self.rst(0x40 + (0x08 * interrupt_num) as u16);
+        self.internal_cycle();
+        self.cpu.pc = match interrupt {
+            Some(interrupt_num) => {
+                // Clear the IF bit for this interrupt
+                self.poke(0xFF0F, self.peek(0xFF0F) & (!(1 << interrupt_num)));
+                self.watch_event(DebugEvent::Interrupt(interrupt_num));
+                0x40 + (0x08 * interrupt_num) as u16
+            }
+            None => 0x0000,
+        };
     }
 
     fn rst(&mut self, vector: u16) {
+        self.internal_cycle();
         self.stack_push_word(self.cpu.pc);
         self.cpu.pc = vector;
     }
@@ -1,4 +1,9 @@
-use crate::memory::IORegs;
+use std::io;
+
+use crate::{
+    memory::IORegs,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum JoypadButton {
@@ -14,11 +19,14 @@ pub enum JoypadButton {
 
 pub struct Joypad {
     state: u8,
+    // The button lines last driven into JOYP. The SGB can override JOYP afterwards, so
+    // edges are found from these rather than the register.
+    lines: u8,
 }
 
 impl Default for Joypad {
     fn default() -> Self {
-        Joypad { state: 0xFF }
+        Joypad { state: 0xFF, lines: 0x0F }
     }
 }
 
@@ -32,7 +40,16 @@ impl Joypad {
         self.state |= button as u8;
     }
 
-    pub fn tick(&self, io_regs: &mut IORegs) {
+    // The buttons held down, as `JoypadButton` bits
+    pub fn pressed(&self) -> u8 {
+        !self.state
+    }
+
+    pub fn set_pressed(&mut self, buttons: u8) {
+        self.state = !buttons;
+    }
+
+    pub fn tick(&mut self, io_regs: &mut IORegs) {
         // Get current joyp state
         let mut joyp = io_regs.read(0xFF00);
         let action = joyp & 0b0010_0000 == 0;
@@ -51,7 +68,26 @@ impl Joypad {
             joyp &= (self.state >> 4) | 0xF0;
         }
 
+        // A selected button line going from high to low requests the joypad interrupt
+        if self.lines & !joyp & 0x0F != 0 {
+            io_regs.write(0xFF0F, io_regs.read(0xFF0F) | 0b1_0000);
+        }
+        self.lines = joyp & 0x0F;
+
         // Write new value to io register
         io_regs.write(0xFF00, joyp);
     }
-}
\ No newline at end of file
+}
+
+impl Snapshot for Joypad {
+    fn save(&self, w: &mut StateWriter) {
+        w.u8(self.state);
+        w.u8(self.lines);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.state = r.u8()?;
+        self.lines = r.u8()?;
+        Ok(())
+    }
+}
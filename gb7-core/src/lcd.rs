@@ -1,16 +1,204 @@
+use std::io;
+
+use crate::{
+    postprocess::PostProcess,
+    ppu::Layer,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+pub const LCD_WIDTH: usize = 160;
+pub const LCD_HEIGHT: usize = 144;
+
+// Four RGBA colours, indexed by shade from lightest (0) to darkest (3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [[u8; 4]; 4],
+}
+
+impl Palette {
+    pub const GRAYSCALE: Palette = Palette::new([
+        [255, 255, 255],
+        [175, 175, 175],
+        [100, 100, 100],
+        [0, 0, 0],
+    ]);
+
+    // Pea-green screen of the original DMG
+    pub const CLASSIC: Palette = Palette::new([
+        [0x9B, 0xBC, 0x0F],
+        [0x8B, 0xAC, 0x0F],
+        [0x30, 0x62, 0x30],
+        [0x0F, 0x38, 0x0F],
+    ]);
+
+    // Gameboy Pocket's grayish-green screen
+    pub const POCKET: Palette = Palette::new([
+        [0xC4, 0xCF, 0xA1],
+        [0x8B, 0x95, 0x6D],
+        [0x4D, 0x53, 0x3C],
+        [0x1F, 0x1F, 0x1F],
+    ]);
+
+    // Gameboy Light's backlit teal screen
+    pub const LIGHT: Palette = Palette::new([
+        [0x00, 0xB5, 0x81],
+        [0x00, 0x9A, 0x71],
+        [0x00, 0x69, 0x4A],
+        [0x00, 0x4F, 0x3B],
+    ]);
+
+    pub const BUILTIN: [Palette; 4] = [
+        Palette::GRAYSCALE,
+        Palette::CLASSIC,
+        Palette::POCKET,
+        Palette::LIGHT,
+    ];
+
+    // Build an opaque palette from four RGB colours, lightest first
+    pub const fn new(rgb: [[u8; 3]; 4]) -> Self {
+        let mut colors = [[0, 0, 0, 255]; 4];
+        let mut i = 0;
+        while i < 4 {
+            colors[i] = [rgb[i][0], rgb[i][1], rgb[i][2], 255];
+            i += 1;
+        }
+        Palette { colors }
+    }
+
+    pub fn color(&self, shade: u8) -> [u8; 4] {
+        self.colors[(shade & 0x3) as usize]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::GRAYSCALE
+    }
+}
+
 pub struct Lcd {
-    pub pixels: [u8; 23200],
+    // Shade indices 0-3 as produced by the PPU
+    pub pixels: [u8; LCD_WIDTH * LCD_HEIGHT],
+    // The same image converted to RGBA through the active palette, drawn line by line
+    frame: [u8; LCD_WIDTH * LCD_HEIGHT * 4],
+    // The last completed frame after post-processing
+    output: [u8; LCD_WIDTH * LCD_HEIGHT * 4],
+    palette: Palette,
+    post_process: PostProcess,
+    frame_ready: bool,
 }
 
 impl Lcd {
     pub fn set_line(&mut self, ly: u8, line: [u8; 160]) {
         let line_num = ly as usize;
-        self.pixels[line_num * 160..(line_num + 1) * 160].copy_from_slice(&line);
+        self.pixels[line_num * LCD_WIDTH..(line_num + 1) * LCD_WIDTH].copy_from_slice(&line);
+
+        let rgba_line = &mut self.frame[line_num * LCD_WIDTH * 4..(line_num + 1) * LCD_WIDTH * 4];
+        for (pixel, shade) in rgba_line.chunks_exact_mut(4).zip(line) {
+            pixel.copy_from_slice(&self.palette.color(shade));
+        }
+    }
+
+    // Like `set_line`, but mixing each pixel with a colour for the layer that produced it
+    pub fn set_line_tinted(&mut self, ly: u8, line: [u8; 160], layers: &[Layer; 160]) {
+        self.set_line(ly, line);
+
+        let line_num = ly as usize;
+        let rgba_line = &mut self.frame[line_num * LCD_WIDTH * 4..(line_num + 1) * LCD_WIDTH * 4];
+        for (pixel, layer) in rgba_line.chunks_exact_mut(4).zip(layers) {
+            let tint = match layer {
+                Layer::Background => [0, 0, 255],
+                Layer::Window => [0, 255, 0],
+                Layer::Sprite => [255, 0, 0],
+            };
+            for (c, t) in pixel.iter_mut().zip(tint) {
+                *c = ((*c as u16 + t as u16) / 2) as u8;
+            }
+        }
+    }
+
+    // The last completed image as tightly packed RGBA rows, 160x144
+    pub fn frame(&self) -> &[u8] {
+        &self.output
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+
+        // Recolour the current image so a paused emulator picks up the change
+        for (pixel, &shade) in self.frame.chunks_exact_mut(4).zip(self.pixels.iter()) {
+            pixel.copy_from_slice(&palette.color(shade));
+        }
+        self.refresh_output();
+    }
+
+    pub fn post_process(&self) -> &PostProcess {
+        &self.post_process
+    }
+
+    pub fn set_post_process(&mut self, post_process: PostProcess) {
+        self.post_process = post_process;
+        self.refresh_output();
+    }
+
+    // Called by the PPU on entering VBlank, once every line of the frame has been drawn
+    pub fn finish_frame(&mut self) {
+        if self.post_process.is_enabled() {
+            self.post_process.process(&self.frame, &mut self.output);
+        } else {
+            self.output.copy_from_slice(&self.frame);
+        }
+        self.frame_ready = true;
+    }
+
+    fn refresh_output(&mut self) {
+        if self.post_process.is_enabled() {
+            self.post_process.render(&self.frame, &mut self.output);
+        } else {
+            self.output.copy_from_slice(&self.frame);
+        }
+    }
+
+    // Returns true once per completed frame, clearing the flag
+    pub fn take_frame_ready(&mut self) -> bool {
+        std::mem::take(&mut self.frame_ready)
     }
 }
 
 impl Default for Lcd {
     fn default() -> Self {
-        Lcd { pixels: [0; 23200] }
+        let palette = Palette::default();
+        let mut frame = [0; LCD_WIDTH * LCD_HEIGHT * 4];
+        for pixel in frame.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&palette.color(0));
+        }
+
+        Lcd {
+            pixels: [0; LCD_WIDTH * LCD_HEIGHT],
+            frame,
+            output: frame,
+            palette,
+            post_process: PostProcess::default(),
+            frame_ready: false,
+        }
+    }
+}
+
+// Only the shades are saved, the palette and post-processing are display settings
+impl Snapshot for Lcd {
+    fn save(&self, w: &mut StateWriter) {
+        w.bytes(&self.pixels);
+        w.bool(self.frame_ready);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        r.bytes(&mut self.pixels)?;
+        self.frame_ready = r.bool()?;
+        self.set_palette(self.palette);
+        Ok(())
     }
 }
@@ -1,9 +1,25 @@
 pub mod cartridge;
 pub mod cpu;
+pub mod debug;
+pub mod debugger;
+pub mod disasm;
+pub mod dma;
 pub mod gameboy;
+pub mod instructions;
 pub mod lcd;
+pub mod link;
 pub mod memory;
+pub mod movie;
+pub mod netlink;
 mod opcodes;
+pub mod postprocess;
 pub mod ppu;
+pub mod printer;
+pub mod rewind;
+pub mod savestate;
+pub mod serial;
+pub mod sgb;
 pub mod timers;
+pub mod trace;
+pub mod watch;
 pub mod joypad;
@@ -0,0 +1,103 @@
+use crate::{
+    gameboy::{Gameboy, CYCLES_PER_FRAME},
+    memory::IORegs,
+    serial::SerialDevice,
+};
+
+// One end of a link cable. Bytes clocked out by this Gameboy are held until
+// the cable delivers them, and the reply is whatever the other Gameboy had waiting in SB.
+#[derive(Default)]
+pub struct LinkPort {
+    pub(crate) peer_byte: u8,
+    pub(crate) sent: Option<u8>,
+}
+
+impl SerialDevice for LinkPort {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        self.sent = Some(byte);
+        self.peer_byte
+    }
+}
+
+// Two Gameboys connected by a link cable, stepped in lockstep so neither gets more than
+// one instruction ahead of the other. Whichever side starts a transfer on its internal
+// clock is the master; the other side only completes transfers it has started on the
+// external clock, otherwise the master reads 0xFF.
+pub struct LinkCable {
+    pub gameboys: [Gameboy; 2],
+    // T-cycles executed by each Gameboy
+    cycles: [u64; 2],
+}
+
+impl LinkCable {
+    pub fn new(first: Gameboy, second: Gameboy) -> Self {
+        let mut gameboys = [first, second];
+        for gameboy in gameboys.iter_mut() {
+            gameboy.serial.connect(Box::new(LinkPort::default()));
+        }
+
+        LinkCable {
+            gameboys,
+            cycles: [0, 0],
+        }
+    }
+
+    pub fn into_gameboys(self) -> [Gameboy; 2] {
+        let mut gameboys = self.gameboys;
+        for gameboy in gameboys.iter_mut() {
+            gameboy.serial.disconnect();
+        }
+        gameboys
+    }
+
+    // Execute one instruction on whichever Gameboy is behind, returning its index and
+    // the M-cycles taken
+    pub fn step(&mut self) -> (usize, u8) {
+        let index = if self.cycles[0] <= self.cycles[1] { 0 } else { 1 };
+        let [first, second] = &mut self.gameboys;
+        let (gameboy, other) = match index {
+            0 => (first, second),
+            _ => (second, first),
+        };
+
+        // The other side can't change SB or SC while this side executes
+        let peer_byte = LinkCable::waiting_byte(&other.io_regs);
+        port(gameboy).peer_byte = peer_byte;
+
+        let m_cycles = gameboy.execute();
+        self.cycles[index] += m_cycles as u64 * 4;
+
+        if let Some(byte) = port(gameboy).sent.take() {
+            other.serial.receive(&mut other.io_regs, byte);
+        }
+
+        (index, m_cycles)
+    }
+
+    // Execute until the first Gameboy finishes a frame, keeping the second in step
+    pub fn execute_frame(&mut self) {
+        let start = self.cycles[0];
+        while self.cycles[0] - start < CYCLES_PER_FRAME as u64 * 2 {
+            self.step();
+            if self.gameboys[0].lcd.take_frame_ready() {
+                break;
+            }
+        }
+        self.gameboys[1].lcd.take_frame_ready();
+    }
+
+    // The byte a Gameboy will send when the other side clocks a transfer
+    fn waiting_byte(io_regs: &IORegs) -> u8 {
+        match io_regs.read(0xFF02) & 0b1000_0001 {
+            0b1000_0000 => io_regs.read(0xFF01),
+            _ => 0xFF,
+        }
+    }
+}
+
+pub(crate) fn port(gameboy: &mut Gameboy) -> &mut LinkPort {
+    gameboy
+        .serial
+        .device_mut::<LinkPort>()
+        .expect("link cable port was disconnected")
+}
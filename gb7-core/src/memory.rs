@@ -1,5 +1,9 @@
+use std::io;
+
 use enum_dispatch::enum_dispatch;
 
+use crate::savestate::{Snapshot, StateReader, StateWriter};
+
 #[enum_dispatch(WorkRam)]
 pub trait WorkMem {
     fn read(&self, addr: u16) -> u8;
@@ -76,6 +80,9 @@ pub trait VideoMem {
     fn read(&self, addr: u16) -> u8;
     fn write(&mut self, addr: u16, val: u8);
     fn set_bank(&mut self, bank: u8);
+    // Read from a specific bank regardless of the active one
+    fn read_bank(&self, bank: u8, addr: u16) -> u8;
+    fn bank_count(&self) -> u8;
 }
 
 #[enum_dispatch]
@@ -106,6 +113,14 @@ impl VideoMem for GBVideoRam {
     fn set_bank(&mut self, _: u8) {
         // Setting bank on DMG ram does nothing
     }
+
+    fn read_bank(&self, _: u8, addr: u16) -> u8 {
+        self.read(addr)
+    }
+
+    fn bank_count(&self) -> u8 {
+        1
+    }
 }
 
 pub struct CGBVideoRam {
@@ -131,6 +146,14 @@ impl VideoMem for CGBVideoRam {
     fn set_bank(&mut self, bank: u8) {
         self.active_bank = if bank & 0b1 == 1 { 1 } else { 0 }
     }
+
+    fn read_bank(&self, bank: u8, addr: u16) -> u8 {
+        self.vram[(addr as usize - 0x8000) + 8192 * (bank as usize & 0b1)]
+    }
+
+    fn bank_count(&self) -> u8 {
+        2
+    }
 }
 
 pub struct Oam {
@@ -152,10 +175,6 @@ impl Oam {
         self.data[addr as usize - 0xFE00] = val
     }
 
-    pub fn dma(&mut self, data: &[u8]) {
-        self.data.copy_from_slice(data);
-    }
-
     pub fn iter_entries(&self) -> impl Iterator<Item = (u8, u8, u8, u8)> + '_ {
         self.data.chunks_exact(4).map(|c| (c[0], c[1], c[2], c[3]))
     }
@@ -200,3 +219,79 @@ impl HighRam {
         self.data[addr as usize - 0xFF80] = val;
     }
 }
+
+impl Snapshot for WorkRam {
+    fn save(&self, w: &mut StateWriter) {
+        match self {
+            WorkRam::GBWorkRam(ram) => w.vec(&ram.wram),
+            WorkRam::CGBWorkRam(ram) => {
+                w.vec(&ram.wram);
+                w.u8(ram.active_bank as u8);
+            }
+        }
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        match self {
+            WorkRam::GBWorkRam(ram) => r.vec_into(&mut ram.wram),
+            WorkRam::CGBWorkRam(ram) => {
+                r.vec_into(&mut ram.wram)?;
+                ram.set_bank(r.u8()?);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Snapshot for VideoRam {
+    fn save(&self, w: &mut StateWriter) {
+        match self {
+            VideoRam::GBVideoRam(ram) => w.vec(&ram.vram),
+            VideoRam::CGBVideoRam(ram) => {
+                w.vec(&ram.vram);
+                w.u8(ram.active_bank as u8);
+            }
+        }
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        match self {
+            VideoRam::GBVideoRam(ram) => r.vec_into(&mut ram.vram),
+            VideoRam::CGBVideoRam(ram) => {
+                r.vec_into(&mut ram.vram)?;
+                ram.set_bank(r.u8()?);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Snapshot for Oam {
+    fn save(&self, w: &mut StateWriter) {
+        w.bytes(&self.data);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        r.bytes(&mut self.data)
+    }
+}
+
+impl Snapshot for IORegs {
+    fn save(&self, w: &mut StateWriter) {
+        w.bytes(&self.data);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        r.bytes(&mut self.data)
+    }
+}
+
+impl Snapshot for HighRam {
+    fn save(&self, w: &mut StateWriter) {
+        w.bytes(&self.data);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        r.bytes(&mut self.data)
+    }
+}
@@ -0,0 +1,176 @@
+use std::{fs, io, path::Path};
+
+use crate::{
+    cartridge::CartMemory,
+    gameboy::Gameboy,
+    savestate::{self, StateReader, StateWriter},
+};
+
+pub const MAGIC: &[u8; 4] = b"GB7M";
+pub const VERSION: u16 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovieStart {
+    PowerOn,
+    SaveState,
+}
+
+// A recording of the buttons held on each frame, from a known starting point. The
+// starting state is always embedded, even from power on, so that cartridge RAM loaded
+// from a .sav file is part of the recording. The emulator reads no other outside input:
+// there's no RTC, and anything plugged into the link port isn't recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_checksum: u32,
+    pub start: MovieStart,
+    // Timing mode the movie was recorded in, which changes how games run
+    pub cycle_accurate: bool,
+    pub initial_state: Vec<u8>,
+    // Pressed buttons for each frame, as `JoypadButton` bits
+    pub inputs: Vec<u8>,
+}
+
+impl Movie {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = StateWriter::default();
+        w.bytes(MAGIC);
+        w.u16(VERSION);
+        w.u32(self.rom_checksum);
+        w.u8(self.start as u8);
+        w.bool(self.cycle_accurate);
+        w.vec(&self.initial_state);
+        w.vec(&self.inputs);
+        w.data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        let mut r = StateReader::new(data);
+        let mut magic = [0; 4];
+        r.bytes(&mut magic)
+            .map_err(|_| savestate::invalid("not a gb7 movie"))?;
+        if &magic != MAGIC {
+            return Err(savestate::invalid("not a gb7 movie"));
+        }
+        let version = r.u16()?;
+        if version != VERSION {
+            return Err(savestate::invalid(&format!(
+                "movie is version {}, expected {}",
+                version, VERSION
+            )));
+        }
+        let movie = Movie {
+            rom_checksum: r.u32()?,
+            start: match r.u8()? {
+                0 => MovieStart::PowerOn,
+                1 => MovieStart::SaveState,
+                _ => return Err(savestate::invalid("invalid movie start")),
+            },
+            cycle_accurate: r.bool()?,
+            initial_state: r.vec()?,
+            inputs: r.vec()?,
+        };
+        if !r.is_empty() {
+            return Err(savestate::invalid("movie has trailing data"));
+        }
+        Ok(movie)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Movie::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn len(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inputs.is_empty()
+    }
+}
+
+// Records a movie from the Gameboy's current state. Frames must be run through
+// `execute_frame` for their input to be recorded.
+pub struct MovieRecorder {
+    movie: Movie,
+}
+
+impl MovieRecorder {
+    pub fn new(gameboy: &Gameboy, start: MovieStart) -> Self {
+        MovieRecorder {
+            movie: Movie {
+                rom_checksum: gameboy.rom_checksum(),
+                start,
+                cycle_accurate: gameboy.cycle_accurate,
+                initial_state: gameboy.save_state(),
+                inputs: Vec::new(),
+            },
+        }
+    }
+
+    pub fn execute_frame(&mut self, gameboy: &mut Gameboy) {
+        self.movie.inputs.push(gameboy.joypad.pressed());
+        gameboy.execute_frame();
+    }
+
+    // Frames recorded so far
+    pub fn frame(&self) -> usize {
+        self.movie.len()
+    }
+
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+
+    pub fn finish(self) -> Movie {
+        self.movie
+    }
+}
+
+// Plays a movie back, replacing the Gameboy's input
+pub struct MoviePlayer {
+    movie: Movie,
+    frame: usize,
+}
+
+impl MoviePlayer {
+    // Put the Gameboy in the movie's starting state. Its cartridge RAM is detached from
+    // the .sav file first, so playback doesn't overwrite the player's save.
+    pub fn new(movie: Movie, gameboy: &mut Gameboy) -> io::Result<Self> {
+        if movie.rom_checksum != gameboy.rom_checksum() {
+            return Err(savestate::invalid("movie is for a different ROM"));
+        }
+        gameboy.cartridge.detach_save();
+        gameboy.load_state(&movie.initial_state)?;
+        gameboy.cycle_accurate = movie.cycle_accurate;
+        Ok(MoviePlayer { movie, frame: 0 })
+    }
+
+    // Run the next frame with its recorded input. Returns false without running
+    // anything once the movie has ended.
+    pub fn execute_frame(&mut self, gameboy: &mut Gameboy) -> bool {
+        let Some(&buttons) = self.movie.inputs.get(self.frame) else {
+            return false;
+        };
+        gameboy.joypad.set_pressed(buttons);
+        gameboy.execute_frame();
+        self.frame += 1;
+        true
+    }
+
+    // Frames played so far
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame == self.movie.len()
+    }
+
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+}
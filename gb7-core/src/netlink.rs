@@ -0,0 +1,264 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+};
+
+use crate::{
+    gameboy::{Gameboy, CYCLES_PER_FRAME},
+    link::{self, LinkPort},
+};
+
+const HANDSHAKE: &[u8; 4] = b"GB7L";
+const PROTOCOL_VERSION: u8 = 1;
+
+// How far in T-cycles either side may run ahead of the last time it heard from its peer.
+// This must stay well below the 4096 T-cycles of a transfer so that a side always learns
+// about a transfer before reaching the time it completes.
+const SYNC_WINDOW: u64 = 2048;
+const PROGRESS_INTERVAL: u64 = SYNC_WINDOW / 4;
+// Upper bound on the T-cycles taken by one `Gameboy::execute`
+const MAX_STEP_CYCLES: u64 = 32;
+
+const MSG_PROGRESS: u8 = 0;
+const MSG_TRANSFER: u8 = 1;
+const MSG_REPLY: u8 = 2;
+
+enum Message {
+    // The sender has executed up to this cycle
+    Progress(u64),
+    // The sender started a transfer on its internal clock which completes at `at`
+    Transfer { at: u64, byte: u8 },
+    // The receiving end's SB for the last transfer, or 0xFF if it wasn't waiting
+    Reply(u8),
+}
+
+// A link cable to a Gameboy in another process over TCP. Both sides count T-cycles from
+// the moment they connect and exchange timestamped messages, so transfers complete on
+// the same cycle on both ends regardless of network latency: a side stalls whenever it
+// gets too far ahead of its peer, or is about to finish a transfer without a reply.
+// If the connection drops, the cable behaves as if it was unplugged.
+pub struct TcpLink {
+    stream: TcpStream,
+    connected: bool,
+    cycles: u64,
+    peer_cycles: u64,
+    sent_cycles: u64,
+    // Completion time of our transfer on the internal clock, with the peer's reply once known
+    outgoing: Option<(u64, Option<u8>)>,
+    // Transfers clocked by the peer, waiting for their completion time
+    incoming: VecDeque<(u64, u8)>,
+}
+
+impl TcpLink {
+    // Wait for the other side to connect to `addr`
+    pub fn host(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        TcpLink::accept(&TcpListener::bind(addr)?)
+    }
+
+    pub fn accept(listener: &TcpListener) -> io::Result<Self> {
+        let (stream, _) = listener.accept()?;
+        TcpLink::new(stream)
+    }
+
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        TcpLink::new(TcpStream::connect(addr)?)
+    }
+
+    fn new(mut stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+
+        stream.write_all(HANDSHAKE)?;
+        stream.write_all(&[PROTOCOL_VERSION])?;
+        let mut handshake = [0; 5];
+        stream.read_exact(&mut handshake)?;
+        if &handshake[..4] != HANDSHAKE || handshake[4] != PROTOCOL_VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "peer is not a compatible gb7 link"));
+        }
+
+        Ok(TcpLink {
+            stream,
+            connected: true,
+            cycles: 0,
+            peer_cycles: 0,
+            sent_cycles: 0,
+            outgoing: None,
+            incoming: VecDeque::new(),
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    // Plug this cable into a Gameboy's serial port
+    pub fn attach(&self, gameboy: &mut Gameboy) {
+        gameboy.serial.connect(Box::new(LinkPort::default()));
+    }
+
+    // Execute one instruction, first stalling until it is safe to do so. Returns the
+    // M-cycles taken.
+    pub fn step(&mut self, gameboy: &mut Gameboy) -> io::Result<u8> {
+        // Complete transfers clocked by the peer that are due
+        while let Some(&(at, byte)) = self.incoming.front() {
+            if at > self.cycles {
+                break;
+            }
+            self.incoming.pop_front();
+            let reply = gameboy.serial.receive(&mut gameboy.io_regs, byte);
+            self.send(Message::Reply(reply))?;
+        }
+
+        loop {
+            let awaiting_reply = matches!(self.outgoing, Some((at, None)) if self.cycles + MAX_STEP_CYCLES >= at);
+            if !awaiting_reply && self.cycles < self.peer_cycles.saturating_add(SYNC_WINDOW) {
+                break;
+            }
+            // Let the peer know how far we got before blocking on it
+            self.send_progress()?;
+            self.receive()?;
+        }
+
+        if let Some((_, Some(reply))) = self.outgoing {
+            link::port(gameboy).peer_byte = reply;
+        }
+
+        let m_cycles = gameboy.execute();
+        self.cycles += m_cycles as u64 * 4;
+        link::port(gameboy).sent = None;
+
+        // Forget a transfer once it has completed or been cancelled, then announce any
+        // new one as soon as it starts
+        if matches!(self.outgoing, Some((at, _)) if self.cycles >= at) {
+            self.outgoing = None;
+        }
+        if self.outgoing.is_none() {
+            if let Some(remaining) = gameboy.serial.transfer_remaining() {
+                let at = self.cycles + remaining as u64 * 4;
+                self.outgoing = Some((at, (!self.connected).then_some(0xFF)));
+                self.send(Message::Transfer {
+                    at,
+                    byte: gameboy.peek(0xFF01),
+                })?;
+            }
+        }
+
+        if self.cycles >= self.sent_cycles + PROGRESS_INTERVAL {
+            self.send_progress()?;
+        }
+
+        Ok(m_cycles)
+    }
+
+    // Execute until the PPU finishes a frame, like `Gameboy::execute_frame`
+    pub fn execute_frame(&mut self, gameboy: &mut Gameboy) -> io::Result<()> {
+        let start = self.cycles;
+        while self.cycles - start < CYCLES_PER_FRAME as u64 * 2 {
+            self.step(gameboy)?;
+            if gameboy.lcd.take_frame_ready() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn send_progress(&mut self) -> io::Result<()> {
+        self.sent_cycles = self.cycles;
+        self.send(Message::Progress(self.cycles))
+    }
+
+    fn send(&mut self, message: Message) -> io::Result<()> {
+        if !self.connected {
+            return Ok(());
+        }
+
+        let mut buf = Vec::with_capacity(10);
+        match message {
+            Message::Progress(cycles) => {
+                buf.push(MSG_PROGRESS);
+                buf.extend_from_slice(&cycles.to_le_bytes());
+            }
+            Message::Transfer { at, byte } => {
+                buf.push(MSG_TRANSFER);
+                buf.extend_from_slice(&at.to_le_bytes());
+                buf.push(byte);
+            }
+            Message::Reply(byte) => {
+                buf.push(MSG_REPLY);
+                buf.push(byte);
+            }
+        }
+        let result = self.stream.write_all(&buf);
+        self.check_disconnect(result)
+    }
+
+    // Block until one message arrives and apply it
+    fn receive(&mut self) -> io::Result<()> {
+        if !self.connected {
+            return Ok(());
+        }
+        let result = self.receive_message();
+        self.check_disconnect(result)
+    }
+
+    fn receive_message(&mut self) -> io::Result<()> {
+        match self.read_u8()? {
+            MSG_PROGRESS => self.peer_cycles = self.read_u64()?,
+            MSG_TRANSFER => {
+                let at = self.read_u64()?;
+                let byte = self.read_u8()?;
+                self.incoming.push_back((at, byte));
+            }
+            MSG_REPLY => {
+                let byte = self.read_u8()?;
+                if let Some((_, reply)) = &mut self.outgoing {
+                    *reply = Some(byte);
+                }
+            }
+            tag => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown link message {}", tag),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    // Treat the peer going away as the cable being unplugged: everything reads 0xFF and
+    // there is nobody left to wait for
+    fn check_disconnect(&mut self, result: io::Result<()>) -> io::Result<()> {
+        match result {
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::UnexpectedEof
+                        | io::ErrorKind::ConnectionReset
+                        | io::ErrorKind::ConnectionAborted
+                        | io::ErrorKind::BrokenPipe
+                ) =>
+            {
+                self.connected = false;
+                self.peer_cycles = u64::MAX;
+                self.incoming.clear();
+                if let Some((_, reply @ None)) = &mut self.outgoing {
+                    *reply = Some(0xFF);
+                }
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn read_u8(&mut self) -> io::Result<u8> {
+        let mut buf = [0; 1];
+        self.stream.read_exact(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        self.stream.read_exact(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+}
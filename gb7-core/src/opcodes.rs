@@ -1,9 +1,5 @@
-use std::collections::HashMap;
-
 use crate::cpu::{CpuFlags, Register, WideRegister};
 
-use lazy_static::lazy_static;
-
 pub enum Opcode {
     ADC(Register), // Add register value and carry to A
     ADCHL,         // Add deferenced [HL] and carry to A
@@ -150,522 +146,522 @@ pub enum Opcode {
     XORI,          // XOR A with immediate u8
 }
 
-lazy_static! {
-    pub static ref OPCODES: HashMap<u8, Opcode> = HashMap::from([
-        (0x00, Opcode::NOP),
-        (0x01, Opcode::LDWRI(WideRegister::BC)),
-        (0x02, Opcode::LDWRA(WideRegister::BC)),
-        (0x03, Opcode::INCW(WideRegister::BC)),
-        (0x04, Opcode::INC(Register::B)),
-        (0x05, Opcode::DEC(Register::B)),
-        (0x06, Opcode::LDRI(Register::B)),
-        (0x07, Opcode::RLCA),
-        (0x08, Opcode::LDISP),
-        (0x09, Opcode::ADDHLR(WideRegister::BC)),
-        (0x0A, Opcode::LDAWR(WideRegister::BC)),
-        (0x0B, Opcode::DECW(WideRegister::BC)),
-        (0x0C, Opcode::INC(Register::C)),
-        (0x0D, Opcode::DEC(Register::C)),
-        (0x0E, Opcode::LDRI(Register::C)),
-        (0x0F, Opcode::RRCA),
-        (0x10, Opcode::STOP),
-        (0x11, Opcode::LDWRI(WideRegister::DE)),
-        (0x12, Opcode::LDWRA(WideRegister::DE)),
-        (0x13, Opcode::INCW(WideRegister::DE)),
-        (0x14, Opcode::INC(Register::D)),
-        (0x15, Opcode::DEC(Register::D)),
-        (0x16, Opcode::LDRI(Register::D)),
-        (0x17, Opcode::RLA),
-        (0x18, Opcode::JR),
-        (0x19, Opcode::ADDHLR(WideRegister::DE)),
-        (0x1A, Opcode::LDAWR(WideRegister::DE)),
-        (0x1B, Opcode::DECW(WideRegister::DE)),
-        (0x1C, Opcode::INC(Register::E)),
-        (0x1D, Opcode::DEC(Register::E)),
-        (0x1E, Opcode::LDRI(Register::E)),
-        (0x1F, Opcode::RRA),
-        (0x20, Opcode::JRNCC(CpuFlags::Z)),
-        (0x21, Opcode::LDWRI(WideRegister::HL)),
-        (0x22, Opcode::LDHLIA),
-        (0x23, Opcode::INCW(WideRegister::HL)),
-        (0x24, Opcode::INC(Register::H)),
-        (0x25, Opcode::DEC(Register::H)),
-        (0x26, Opcode::LDRI(Register::H)),
-        (0x27, Opcode::DAA),
-        (0x28, Opcode::JRCC(CpuFlags::Z)),
-        (0x29, Opcode::ADDHLR(WideRegister::HL)),
-        (0x2A, Opcode::LDAHLI),
-        (0x2B, Opcode::DECW(WideRegister::HL)),
-        (0x2C, Opcode::INC(Register::L)),
-        (0x2D, Opcode::DEC(Register::L)),
-        (0x2E, Opcode::LDRI(Register::L)),
-        (0x2F, Opcode::CPL),
-        (0x30, Opcode::JRNCC(CpuFlags::C)),
-        (0x31, Opcode::LDWRI(WideRegister::SP)),
-        (0x32, Opcode::LDHLDA),
-        (0x33, Opcode::INCW(WideRegister::SP)),
-        (0x34, Opcode::INCHL),
-        (0x35, Opcode::DECHL),
-        (0x36, Opcode::LDHLI),
-        (0x37, Opcode::SCF),
-        (0x38, Opcode::JRCC(CpuFlags::C)),
-        (0x39, Opcode::ADDHLR(WideRegister::SP)),
-        (0x3A, Opcode::LDAHLD),
-        (0x3B, Opcode::DECW(WideRegister::SP)),
-        (0x3C, Opcode::INC(Register::A)),
-        (0x3D, Opcode::DEC(Register::A)),
-        (0x3E, Opcode::LDRI(Register::A)),
-        (0x3F, Opcode::CCF),
-        (0x40, Opcode::LDRR(Register::B, Register::B)),
-        (0x41, Opcode::LDRR(Register::B, Register::C)),
-        (0x42, Opcode::LDRR(Register::B, Register::D)),
-        (0x43, Opcode::LDRR(Register::B, Register::E)),
-        (0x44, Opcode::LDRR(Register::B, Register::H)),
-        (0x45, Opcode::LDRR(Register::B, Register::L)),
-        (0x46, Opcode::LDRHL(Register::B)),
-        (0x47, Opcode::LDRR(Register::B, Register::A)),
-        (0x48, Opcode::LDRR(Register::C, Register::B)),
-        (0x49, Opcode::LDRR(Register::C, Register::C)),
-        (0x4A, Opcode::LDRR(Register::C, Register::D)),
-        (0x4B, Opcode::LDRR(Register::C, Register::E)),
-        (0x4C, Opcode::LDRR(Register::C, Register::H)),
-        (0x4D, Opcode::LDRR(Register::C, Register::L)),
-        (0x4E, Opcode::LDRHL(Register::C)),
-        (0x4F, Opcode::LDRR(Register::C, Register::A)),
-        (0x50, Opcode::LDRR(Register::D, Register::B)),
-        (0x51, Opcode::LDRR(Register::D, Register::C)),
-        (0x52, Opcode::LDRR(Register::D, Register::D)),
-        (0x53, Opcode::LDRR(Register::D, Register::E)),
-        (0x54, Opcode::LDRR(Register::D, Register::H)),
-        (0x55, Opcode::LDRR(Register::D, Register::L)),
-        (0x56, Opcode::LDRHL(Register::D)),
-        (0x57, Opcode::LDRR(Register::D, Register::A)),
-        (0x58, Opcode::LDRR(Register::E, Register::B)),
-        (0x59, Opcode::LDRR(Register::E, Register::C)),
-        (0x5A, Opcode::LDRR(Register::E, Register::D)),
-        (0x5B, Opcode::LDRR(Register::E, Register::E)),
-        (0x5C, Opcode::LDRR(Register::E, Register::H)),
-        (0x5D, Opcode::LDRR(Register::E, Register::L)),
-        (0x5E, Opcode::LDRHL(Register::E)),
-        (0x5F, Opcode::LDRR(Register::E, Register::A)),
-        (0x60, Opcode::LDRR(Register::H, Register::B)),
-        (0x61, Opcode::LDRR(Register::H, Register::C)),
-        (0x62, Opcode::LDRR(Register::H, Register::D)),
-        (0x63, Opcode::LDRR(Register::H, Register::E)),
-        (0x64, Opcode::LDRR(Register::H, Register::H)),
-        (0x65, Opcode::LDRR(Register::H, Register::L)),
-        (0x66, Opcode::LDRHL(Register::H)),
-        (0x67, Opcode::LDRR(Register::H, Register::A)),
-        (0x68, Opcode::LDRR(Register::L, Register::B)),
-        (0x69, Opcode::LDRR(Register::L, Register::C)),
-        (0x6A, Opcode::LDRR(Register::L, Register::D)),
-        (0x6B, Opcode::LDRR(Register::L, Register::E)),
-        (0x6C, Opcode::LDRR(Register::L, Register::H)),
-        (0x6D, Opcode::LDRR(Register::L, Register::L)),
-        (0x6E, Opcode::LDRHL(Register::L)),
-        (0x6F, Opcode::LDRR(Register::L, Register::A)),
-        (0x70, Opcode::LDHLR(Register::B)),
-        (0x71, Opcode::LDHLR(Register::C)),
-        (0x72, Opcode::LDHLR(Register::D)),
-        (0x73, Opcode::LDHLR(Register::E)),
-        (0x74, Opcode::LDHLR(Register::H)),
-        (0x75, Opcode::LDHLR(Register::L)),
-        (0x76, Opcode::HALT),
-        (0x77, Opcode::LDHLR(Register::A)),
-        (0x78, Opcode::LDRR(Register::A, Register::B)),
-        (0x79, Opcode::LDRR(Register::A, Register::C)),
-        (0x7A, Opcode::LDRR(Register::A, Register::D)),
-        (0x7B, Opcode::LDRR(Register::A, Register::E)),
-        (0x7C, Opcode::LDRR(Register::A, Register::H)),
-        (0x7D, Opcode::LDRR(Register::A, Register::L)),
-        (0x7E, Opcode::LDRHL(Register::A)),
-        (0x7F, Opcode::LDRR(Register::A, Register::A)),
-        (0x80, Opcode::ADD(Register::B)),
-        (0x81, Opcode::ADD(Register::C)),
-        (0x82, Opcode::ADD(Register::D)),
-        (0x83, Opcode::ADD(Register::E)),
-        (0x84, Opcode::ADD(Register::H)),
-        (0x85, Opcode::ADD(Register::L)),
-        (0x86, Opcode::ADDHL),
-        (0x87, Opcode::ADD(Register::A)),
-        (0x88, Opcode::ADC(Register::B)),
-        (0x89, Opcode::ADC(Register::C)),
-        (0x8A, Opcode::ADC(Register::D)),
-        (0x8B, Opcode::ADC(Register::E)),
-        (0x8C, Opcode::ADC(Register::H)),
-        (0x8D, Opcode::ADC(Register::L)),
-        (0x8E, Opcode::ADCHL),
-        (0x8F, Opcode::ADC(Register::A)),
-        (0x90, Opcode::SUB(Register::B)),
-        (0x91, Opcode::SUB(Register::C)),
-        (0x92, Opcode::SUB(Register::D)),
-        (0x93, Opcode::SUB(Register::E)),
-        (0x94, Opcode::SUB(Register::H)),
-        (0x95, Opcode::SUB(Register::L)),
-        (0x96, Opcode::SUBHL),
-        (0x97, Opcode::SUB(Register::A)),
-        (0x98, Opcode::SBC(Register::B)),
-        (0x99, Opcode::SBC(Register::C)),
-        (0x9A, Opcode::SBC(Register::D)),
-        (0x9B, Opcode::SBC(Register::E)),
-        (0x9C, Opcode::SBC(Register::H)),
-        (0x9D, Opcode::SBC(Register::L)),
-        (0x9E, Opcode::SBCHL),
-        (0x9F, Opcode::SBC(Register::A)),
-        (0xA0, Opcode::AND(Register::B)),
-        (0xA1, Opcode::AND(Register::C)),
-        (0xA2, Opcode::AND(Register::D)),
-        (0xA3, Opcode::AND(Register::E)),
-        (0xA4, Opcode::AND(Register::H)),
-        (0xA5, Opcode::AND(Register::L)),
-        (0xA6, Opcode::ANDHL),
-        (0xA7, Opcode::AND(Register::A)),
-        (0xA8, Opcode::XOR(Register::B)),
-        (0xA9, Opcode::XOR(Register::C)),
-        (0xAA, Opcode::XOR(Register::D)),
-        (0xAB, Opcode::XOR(Register::E)),
-        (0xAC, Opcode::XOR(Register::H)),
-        (0xAD, Opcode::XOR(Register::L)),
-        (0xAE, Opcode::XORHL),
-        (0xAF, Opcode::XOR(Register::A)),
-        (0xB0, Opcode::OR(Register::B)),
-        (0xB1, Opcode::OR(Register::C)),
-        (0xB2, Opcode::OR(Register::D)),
-        (0xB3, Opcode::OR(Register::E)),
-        (0xB4, Opcode::OR(Register::H)),
-        (0xB5, Opcode::OR(Register::L)),
-        (0xB6, Opcode::ORHL),
-        (0xB7, Opcode::OR(Register::A)),
-        (0xB8, Opcode::CP(Register::B)),
-        (0xB9, Opcode::CP(Register::C)),
-        (0xBA, Opcode::CP(Register::D)),
-        (0xBB, Opcode::CP(Register::E)),
-        (0xBC, Opcode::CP(Register::H)),
-        (0xBD, Opcode::CP(Register::L)),
-        (0xBE, Opcode::CPHL),
-        (0xBF, Opcode::CP(Register::A)),
-        (0xC0, Opcode::RETNCC(CpuFlags::Z)),
-        (0xC1, Opcode::POPWR(WideRegister::BC)),
-        (0xC2, Opcode::JPNCC(CpuFlags::Z)),
-        (0xC3, Opcode::JP),
-        (0xC4, Opcode::CALLNCC(CpuFlags::Z)),
-        (0xC5, Opcode::PUSHWR(WideRegister::BC)),
-        (0xC6, Opcode::ADDI),
-        (0xC7, Opcode::RST(0x00)),
-        (0xC8, Opcode::RETCC(CpuFlags::Z)),
-        (0xC9, Opcode::RET),
-        (0xCA, Opcode::JPCC(CpuFlags::Z)),
-        (0xCB, Opcode::CB),
-        (0xCC, Opcode::CALLCC(CpuFlags::Z)),
-        (0xCD, Opcode::CALL),
-        (0xCE, Opcode::ADCI),
-        (0xCF, Opcode::RST(0x08)),
-        (0xD0, Opcode::RETNCC(CpuFlags::C)),
-        (0xD1, Opcode::POPWR(WideRegister::DE)),
-        (0xD2, Opcode::JPNCC(CpuFlags::C)),
-        // 0xD3
-        (0xD4, Opcode::CALLNCC(CpuFlags::C)),
-        (0xD5, Opcode::PUSHWR(WideRegister::DE)),
-        (0xD6, Opcode::SUBI),
-        (0xD7, Opcode::RST(0x10)),
-        (0xD8, Opcode::RETCC(CpuFlags::C)),
-        (0xD9, Opcode::RETI),
-        (0xDA, Opcode::JPCC(CpuFlags::C)),
-        // 0xDB
-        (0xDC, Opcode::CALLCC(CpuFlags::C)),
-        // 0xDD
-        (0xDE, Opcode::SBCI),
-        (0xDF, Opcode::RST(0x18)),
-        (0xE0, Opcode::LDIOA),
-        (0xE1, Opcode::POPWR(WideRegister::HL)),
-        (0xE2, Opcode::LDIOCA),
-        // 0xE3
-        // 0xE4
-        (0xE5, Opcode::PUSHWR(WideRegister::HL)),
-        (0xE6, Opcode::ANDI),
-        (0xE7, Opcode::RST(0x20)),
-        (0xE8, Opcode::ADDSP),
-        (0xE9, Opcode::JPHL),
-        (0xEA, Opcode::LDIWA),
-        // 0xEB
-        // 0xEC
-        // 0xED
-        (0xEE, Opcode::XORI),
-        (0xEF, Opcode::RST(0x28)),
-        (0xF0, Opcode::LDAIO),
-        (0xF1, Opcode::POPWR(WideRegister::AF)),
-        (0xF2, Opcode::LDAIOC),
-        (0xF3, Opcode::DI),
-        // 0xF4
-        (0xF5, Opcode::PUSHWR(WideRegister::AF)),
-        (0xF6, Opcode::ORI),
-        (0xF7, Opcode::RST(0x30)),
-        (0xF8, Opcode::LDHLSP),
-        (0xF9, Opcode::LDSPHL),
-        (0xFA, Opcode::LDAIW),
-        (0xFB, Opcode::EI),
-        // 0xFC
-        // 0xFD
-        (0xFE, Opcode::CPI),
-        (0xFF, Opcode::RST(0x38)),
-    ]);
-
-    pub static ref CB_OPCODES: HashMap<u8, Opcode> = HashMap::from([
-        (0x00, Opcode::RLC(Register::B)),
-        (0x01, Opcode::RLC(Register::C)),
-        (0x02, Opcode::RLC(Register::D)),
-        (0x03, Opcode::RLC(Register::E)),
-        (0x04, Opcode::RLC(Register::H)),
-        (0x05, Opcode::RLC(Register::L)),
-        (0x06, Opcode::RLCHL),
-        (0x07, Opcode::RLC(Register::A)),
-        (0x08, Opcode::RRC(Register::B)),
-        (0x09, Opcode::RRC(Register::C)),
-        (0x0A, Opcode::RRC(Register::D)),
-        (0x0B, Opcode::RRC(Register::E)),
-        (0x0C, Opcode::RRC(Register::H)),
-        (0x0D, Opcode::RRC(Register::L)),
-        (0x0E, Opcode::RRCHL),
-        (0x0F, Opcode::RRC(Register::A)),
-        (0x10, Opcode::RL(Register::B)),
-        (0x11, Opcode::RL(Register::C)),
-        (0x12, Opcode::RL(Register::D)),
-        (0x13, Opcode::RL(Register::E)),
-        (0x14, Opcode::RL(Register::H)),
-        (0x15, Opcode::RL(Register::L)),
-        (0x16, Opcode::RLHL),
-        (0x17, Opcode::RL(Register::A)),
-        (0x18, Opcode::RR(Register::B)),
-        (0x19, Opcode::RR(Register::C)),
-        (0x1A, Opcode::RR(Register::D)),
-        (0x1B, Opcode::RR(Register::E)),
-        (0x1C, Opcode::RR(Register::H)),
-        (0x1D, Opcode::RR(Register::L)),
-        (0x1E, Opcode::RRHL),
-        (0x1F, Opcode::RR(Register::A)),
-        (0x20, Opcode::SLA(Register::B)),
-        (0x21, Opcode::SLA(Register::C)),
-        (0x22, Opcode::SLA(Register::D)),
-        (0x23, Opcode::SLA(Register::E)),
-        (0x24, Opcode::SLA(Register::H)),
-        (0x25, Opcode::SLA(Register::L)),
-        (0x26, Opcode::SLAHL),
-        (0x27, Opcode::SLA(Register::A)),
-        (0x28, Opcode::SRA(Register::B)),
-        (0x29, Opcode::SRA(Register::C)),
-        (0x2A, Opcode::SRA(Register::D)),
-        (0x2B, Opcode::SRA(Register::E)),
-        (0x2C, Opcode::SRA(Register::H)),
-        (0x2D, Opcode::SRA(Register::L)),
-        (0x2E, Opcode::SRAHL),
-        (0x2F, Opcode::SRA(Register::A)),
-        (0x30, Opcode::SWAP(Register::B)),
-        (0x31, Opcode::SWAP(Register::C)),
-        (0x32, Opcode::SWAP(Register::D)),
-        (0x33, Opcode::SWAP(Register::E)),
-        (0x34, Opcode::SWAP(Register::H)),
-        (0x35, Opcode::SWAP(Register::L)),
-        (0x36, Opcode::SWAPHL),
-        (0x37, Opcode::SWAP(Register::A)),
-        (0x38, Opcode::SRL(Register::B)),
-        (0x39, Opcode::SRL(Register::C)),
-        (0x3A, Opcode::SRL(Register::D)),
-        (0x3B, Opcode::SRL(Register::E)),
-        (0x3C, Opcode::SRL(Register::H)),
-        (0x3D, Opcode::SRL(Register::L)),
-        (0x3E, Opcode::SRLHL),
-        (0x3F, Opcode::SRL(Register::A)),
-        (0x40, Opcode::BIT(0, Register::B)),
-        (0x41, Opcode::BIT(0, Register::C)),
-        (0x42, Opcode::BIT(0, Register::D)),
-        (0x43, Opcode::BIT(0, Register::E)),
-        (0x44, Opcode::BIT(0, Register::H)),
-        (0x45, Opcode::BIT(0, Register::L)),
-        (0x46, Opcode::BITHL(0)),
-        (0x47, Opcode::BIT(0, Register::A)),
-        (0x48, Opcode::BIT(1, Register::B)),
-        (0x49, Opcode::BIT(1, Register::C)),
-        (0x4A, Opcode::BIT(1, Register::D)),
-        (0x4B, Opcode::BIT(1, Register::E)),
-        (0x4C, Opcode::BIT(1, Register::H)),
-        (0x4D, Opcode::BIT(1, Register::L)),
-        (0x4E, Opcode::BITHL(1)),
-        (0x4F, Opcode::BIT(1, Register::A)),
-        (0x50, Opcode::BIT(2, Register::B)),
-        (0x51, Opcode::BIT(2, Register::C)),
-        (0x52, Opcode::BIT(2, Register::D)),
-        (0x53, Opcode::BIT(2, Register::E)),
-        (0x54, Opcode::BIT(2, Register::H)),
-        (0x55, Opcode::BIT(2, Register::L)),
-        (0x56, Opcode::BITHL(2)),
-        (0x57, Opcode::BIT(2, Register::A)),
-        (0x58, Opcode::BIT(3, Register::B)),
-        (0x59, Opcode::BIT(3, Register::C)),
-        (0x5A, Opcode::BIT(3, Register::D)),
-        (0x5B, Opcode::BIT(3, Register::E)),
-        (0x5C, Opcode::BIT(3, Register::H)),
-        (0x5D, Opcode::BIT(3, Register::L)),
-        (0x5E, Opcode::BITHL(3)),
-        (0x5F, Opcode::BIT(3, Register::A)),
-        (0x60, Opcode::BIT(4, Register::B)),
-        (0x61, Opcode::BIT(4, Register::C)),
-        (0x62, Opcode::BIT(4, Register::D)),
-        (0x63, Opcode::BIT(4, Register::E)),
-        (0x64, Opcode::BIT(4, Register::H)),
-        (0x65, Opcode::BIT(4, Register::L)),
-        (0x66, Opcode::BITHL(4)),
-        (0x67, Opcode::BIT(4, Register::A)),
-        (0x68, Opcode::BIT(5, Register::B)),
-        (0x69, Opcode::BIT(5, Register::C)),
-        (0x6A, Opcode::BIT(5, Register::D)),
-        (0x6B, Opcode::BIT(5, Register::E)),
-        (0x6C, Opcode::BIT(5, Register::H)),
-        (0x6D, Opcode::BIT(5, Register::L)),
-        (0x6E, Opcode::BITHL(5)),
-        (0x6F, Opcode::BIT(5, Register::A)),
-        (0x70, Opcode::BIT(6, Register::B)),
-        (0x71, Opcode::BIT(6, Register::C)),
-        (0x72, Opcode::BIT(6, Register::D)),
-        (0x73, Opcode::BIT(6, Register::E)),
-        (0x74, Opcode::BIT(6, Register::H)),
-        (0x75, Opcode::BIT(6, Register::L)),
-        (0x76, Opcode::BITHL(6)),
-        (0x77, Opcode::BIT(6, Register::A)),
-        (0x78, Opcode::BIT(7, Register::B)),
-        (0x79, Opcode::BIT(7, Register::C)),
-        (0x7A, Opcode::BIT(7, Register::D)),
-        (0x7B, Opcode::BIT(7, Register::E)),
-        (0x7C, Opcode::BIT(7, Register::H)),
-        (0x7D, Opcode::BIT(7, Register::L)),
-        (0x7E, Opcode::BITHL(7)),
-        (0x7F, Opcode::BIT(7, Register::A)),
-        (0x80, Opcode::RES(0, Register::B)),
-        (0x81, Opcode::RES(0, Register::C)),
-        (0x82, Opcode::RES(0, Register::D)),
-        (0x83, Opcode::RES(0, Register::E)),
-        (0x84, Opcode::RES(0, Register::H)),
-        (0x85, Opcode::RES(0, Register::L)),
-        (0x86, Opcode::RESHL(0)),
-        (0x87, Opcode::RES(0, Register::A)),
-        (0x88, Opcode::RES(1, Register::B)),
-        (0x89, Opcode::RES(1, Register::C)),
-        (0x8A, Opcode::RES(1, Register::D)),
-        (0x8B, Opcode::RES(1, Register::E)),
-        (0x8C, Opcode::RES(1, Register::H)),
-        (0x8D, Opcode::RES(1, Register::L)),
-        (0x8E, Opcode::RESHL(1)),
-        (0x8F, Opcode::RES(1, Register::A)),
-        (0x90, Opcode::RES(2, Register::B)),
-        (0x91, Opcode::RES(2, Register::C)),
-        (0x92, Opcode::RES(2, Register::D)),
-        (0x93, Opcode::RES(2, Register::E)),
-        (0x94, Opcode::RES(2, Register::H)),
-        (0x95, Opcode::RES(2, Register::L)),
-        (0x96, Opcode::RESHL(2)),
-        (0x97, Opcode::RES(2, Register::A)),
-        (0x98, Opcode::RES(3, Register::B)),
-        (0x99, Opcode::RES(3, Register::C)),
-        (0x9A, Opcode::RES(3, Register::D)),
-        (0x9B, Opcode::RES(3, Register::E)),
-        (0x9C, Opcode::RES(3, Register::H)),
-        (0x9D, Opcode::RES(3, Register::L)),
-        (0x9E, Opcode::RESHL(3)),
-        (0x9F, Opcode::RES(3, Register::A)),
-        (0xA0, Opcode::RES(4, Register::B)),
-        (0xA1, Opcode::RES(4, Register::C)),
-        (0xA2, Opcode::RES(4, Register::D)),
-        (0xA3, Opcode::RES(4, Register::E)),
-        (0xA4, Opcode::RES(4, Register::H)),
-        (0xA5, Opcode::RES(4, Register::L)),
-        (0xA6, Opcode::RESHL(4)),
-        (0xA7, Opcode::RES(4, Register::A)),
-        (0xA8, Opcode::RES(5, Register::B)),
-        (0xA9, Opcode::RES(5, Register::C)),
-        (0xAA, Opcode::RES(5, Register::D)),
-        (0xAB, Opcode::RES(5, Register::E)),
-        (0xAC, Opcode::RES(5, Register::H)),
-        (0xAD, Opcode::RES(5, Register::L)),
-        (0xAE, Opcode::RESHL(5)),
-        (0xAF, Opcode::RES(5, Register::A)),
-        (0xB0, Opcode::RES(6, Register::B)),
-        (0xB1, Opcode::RES(6, Register::C)),
-        (0xB2, Opcode::RES(6, Register::D)),
-        (0xB3, Opcode::RES(6, Register::E)),
-        (0xB4, Opcode::RES(6, Register::H)),
-        (0xB5, Opcode::RES(6, Register::L)),
-        (0xB6, Opcode::RESHL(6)),
-        (0xB7, Opcode::RES(6, Register::A)),
-        (0xB8, Opcode::RES(7, Register::B)),
-        (0xB9, Opcode::RES(7, Register::C)),
-        (0xBA, Opcode::RES(7, Register::D)),
-        (0xBB, Opcode::RES(7, Register::E)),
-        (0xBC, Opcode::RES(7, Register::H)),
-        (0xBD, Opcode::RES(7, Register::L)),
-        (0xBE, Opcode::RESHL(7)),
-        (0xBF, Opcode::RES(7, Register::A)),
-        (0xC0, Opcode::SET(0, Register::B)),
-        (0xC1, Opcode::SET(0, Register::C)),
-        (0xC2, Opcode::SET(0, Register::D)),
-        (0xC3, Opcode::SET(0, Register::E)),
-        (0xC4, Opcode::SET(0, Register::H)),
-        (0xC5, Opcode::SET(0, Register::L)),
-        (0xC6, Opcode::SETHL(0)),
-        (0xC7, Opcode::SET(0, Register::A)),
-        (0xC8, Opcode::SET(1, Register::B)),
-        (0xC9, Opcode::SET(1, Register::C)),
-        (0xCA, Opcode::SET(1, Register::D)),
-        (0xCB, Opcode::SET(1, Register::E)),
-        (0xCC, Opcode::SET(1, Register::H)),
-        (0xCD, Opcode::SET(1, Register::L)),
-        (0xCE, Opcode::SETHL(1)),
-        (0xCF, Opcode::SET(1, Register::A)),
-        (0xD0, Opcode::SET(2, Register::B)),
-        (0xD1, Opcode::SET(2, Register::C)),
-        (0xD2, Opcode::SET(2, Register::D)),
-        (0xD3, Opcode::SET(2, Register::E)),
-        (0xD4, Opcode::SET(2, Register::H)),
-        (0xD5, Opcode::SET(2, Register::L)),
-        (0xD6, Opcode::SETHL(2)),
-        (0xD7, Opcode::SET(2, Register::A)),
-        (0xD8, Opcode::SET(3, Register::B)),
-        (0xD9, Opcode::SET(3, Register::C)),
-        (0xDA, Opcode::SET(3, Register::D)),
-        (0xDB, Opcode::SET(3, Register::E)),
-        (0xDC, Opcode::SET(3, Register::H)),
-        (0xDD, Opcode::SET(3, Register::L)),
-        (0xDE, Opcode::SETHL(3)),
-        (0xDF, Opcode::SET(3, Register::A)),
-        (0xE0, Opcode::SET(4, Register::B)),
-        (0xE1, Opcode::SET(4, Register::C)),
-        (0xE2, Opcode::SET(4, Register::D)),
-        (0xE3, Opcode::SET(4, Register::E)),
-        (0xE4, Opcode::SET(4, Register::H)),
-        (0xE5, Opcode::SET(4, Register::L)),
-        (0xE6, Opcode::SETHL(4)),
-        (0xE7, Opcode::SET(4, Register::A)),
-        (0xE8, Opcode::SET(5, Register::B)),
-        (0xE9, Opcode::SET(5, Register::C)),
-        (0xEA, Opcode::SET(5, Register::D)),
-        (0xEB, Opcode::SET(5, Register::E)),
-        (0xEC, Opcode::SET(5, Register::H)),
-        (0xED, Opcode::SET(5, Register::L)),
-        (0xEE, Opcode::SETHL(5)),
-        (0xEF, Opcode::SET(5, Register::A)),
-        (0xF0, Opcode::SET(6, Register::B)),
-        (0xF1, Opcode::SET(6, Register::C)),
-        (0xF2, Opcode::SET(6, Register::D)),
-        (0xF3, Opcode::SET(6, Register::E)),
-        (0xF4, Opcode::SET(6, Register::H)),
-        (0xF5, Opcode::SET(6, Register::L)),
-        (0xF6, Opcode::SETHL(6)),
-        (0xF7, Opcode::SET(6, Register::A)),
-        (0xF8, Opcode::SET(7, Register::B)),
-        (0xF9, Opcode::SET(7, Register::C)),
-        (0xFA, Opcode::SET(7, Register::D)),
-        (0xFB, Opcode::SET(7, Register::E)),
-        (0xFC, Opcode::SET(7, Register::H)),
-        (0xFD, Opcode::SET(7, Register::L)),
-        (0xFE, Opcode::SETHL(7)),
-        (0xFF, Opcode::SET(7, Register::A)),
-    ]);
-}
+// Unprefixed opcodes indexed by their byte. The 11 unused opcodes are `None`.
+pub static OPCODES: [Option<Opcode>; 256] = [
+    Some(Opcode::NOP), // 0x00
+    Some(Opcode::LDWRI(WideRegister::BC)), // 0x01
+    Some(Opcode::LDWRA(WideRegister::BC)), // 0x02
+    Some(Opcode::INCW(WideRegister::BC)), // 0x03
+    Some(Opcode::INC(Register::B)), // 0x04
+    Some(Opcode::DEC(Register::B)), // 0x05
+    Some(Opcode::LDRI(Register::B)), // 0x06
+    Some(Opcode::RLCA), // 0x07
+    Some(Opcode::LDISP), // 0x08
+    Some(Opcode::ADDHLR(WideRegister::BC)), // 0x09
+    Some(Opcode::LDAWR(WideRegister::BC)), // 0x0A
+    Some(Opcode::DECW(WideRegister::BC)), // 0x0B
+    Some(Opcode::INC(Register::C)), // 0x0C
+    Some(Opcode::DEC(Register::C)), // 0x0D
+    Some(Opcode::LDRI(Register::C)), // 0x0E
+    Some(Opcode::RRCA), // 0x0F
+    Some(Opcode::STOP), // 0x10
+    Some(Opcode::LDWRI(WideRegister::DE)), // 0x11
+    Some(Opcode::LDWRA(WideRegister::DE)), // 0x12
+    Some(Opcode::INCW(WideRegister::DE)), // 0x13
+    Some(Opcode::INC(Register::D)), // 0x14
+    Some(Opcode::DEC(Register::D)), // 0x15
+    Some(Opcode::LDRI(Register::D)), // 0x16
+    Some(Opcode::RLA), // 0x17
+    Some(Opcode::JR), // 0x18
+    Some(Opcode::ADDHLR(WideRegister::DE)), // 0x19
+    Some(Opcode::LDAWR(WideRegister::DE)), // 0x1A
+    Some(Opcode::DECW(WideRegister::DE)), // 0x1B
+    Some(Opcode::INC(Register::E)), // 0x1C
+    Some(Opcode::DEC(Register::E)), // 0x1D
+    Some(Opcode::LDRI(Register::E)), // 0x1E
+    Some(Opcode::RRA), // 0x1F
+    Some(Opcode::JRNCC(CpuFlags::Z)), // 0x20
+    Some(Opcode::LDWRI(WideRegister::HL)), // 0x21
+    Some(Opcode::LDHLIA), // 0x22
+    Some(Opcode::INCW(WideRegister::HL)), // 0x23
+    Some(Opcode::INC(Register::H)), // 0x24
+    Some(Opcode::DEC(Register::H)), // 0x25
+    Some(Opcode::LDRI(Register::H)), // 0x26
+    Some(Opcode::DAA), // 0x27
+    Some(Opcode::JRCC(CpuFlags::Z)), // 0x28
+    Some(Opcode::ADDHLR(WideRegister::HL)), // 0x29
+    Some(Opcode::LDAHLI), // 0x2A
+    Some(Opcode::DECW(WideRegister::HL)), // 0x2B
+    Some(Opcode::INC(Register::L)), // 0x2C
+    Some(Opcode::DEC(Register::L)), // 0x2D
+    Some(Opcode::LDRI(Register::L)), // 0x2E
+    Some(Opcode::CPL), // 0x2F
+    Some(Opcode::JRNCC(CpuFlags::C)), // 0x30
+    Some(Opcode::LDWRI(WideRegister::SP)), // 0x31
+    Some(Opcode::LDHLDA), // 0x32
+    Some(Opcode::INCW(WideRegister::SP)), // 0x33
+    Some(Opcode::INCHL), // 0x34
+    Some(Opcode::DECHL), // 0x35
+    Some(Opcode::LDHLI), // 0x36
+    Some(Opcode::SCF), // 0x37
+    Some(Opcode::JRCC(CpuFlags::C)), // 0x38
+    Some(Opcode::ADDHLR(WideRegister::SP)), // 0x39
+    Some(Opcode::LDAHLD), // 0x3A
+    Some(Opcode::DECW(WideRegister::SP)), // 0x3B
+    Some(Opcode::INC(Register::A)), // 0x3C
+    Some(Opcode::DEC(Register::A)), // 0x3D
+    Some(Opcode::LDRI(Register::A)), // 0x3E
+    Some(Opcode::CCF), // 0x3F
+    Some(Opcode::LDRR(Register::B, Register::B)), // 0x40
+    Some(Opcode::LDRR(Register::B, Register::C)), // 0x41
+    Some(Opcode::LDRR(Register::B, Register::D)), // 0x42
+    Some(Opcode::LDRR(Register::B, Register::E)), // 0x43
+    Some(Opcode::LDRR(Register::B, Register::H)), // 0x44
+    Some(Opcode::LDRR(Register::B, Register::L)), // 0x45
+    Some(Opcode::LDRHL(Register::B)), // 0x46
+    Some(Opcode::LDRR(Register::B, Register::A)), // 0x47
+    Some(Opcode::LDRR(Register::C, Register::B)), // 0x48
+    Some(Opcode::LDRR(Register::C, Register::C)), // 0x49
+    Some(Opcode::LDRR(Register::C, Register::D)), // 0x4A
+    Some(Opcode::LDRR(Register::C, Register::E)), // 0x4B
+    Some(Opcode::LDRR(Register::C, Register::H)), // 0x4C
+    Some(Opcode::LDRR(Register::C, Register::L)), // 0x4D
+    Some(Opcode::LDRHL(Register::C)), // 0x4E
+    Some(Opcode::LDRR(Register::C, Register::A)), // 0x4F
+    Some(Opcode::LDRR(Register::D, Register::B)), // 0x50
+    Some(Opcode::LDRR(Register::D, Register::C)), // 0x51
+    Some(Opcode::LDRR(Register::D, Register::D)), // 0x52
+    Some(Opcode::LDRR(Register::D, Register::E)), // 0x53
+    Some(Opcode::LDRR(Register::D, Register::H)), // 0x54
+    Some(Opcode::LDRR(Register::D, Register::L)), // 0x55
+    Some(Opcode::LDRHL(Register::D)), // 0x56
+    Some(Opcode::LDRR(Register::D, Register::A)), // 0x57
+    Some(Opcode::LDRR(Register::E, Register::B)), // 0x58
+    Some(Opcode::LDRR(Register::E, Register::C)), // 0x59
+    Some(Opcode::LDRR(Register::E, Register::D)), // 0x5A
+    Some(Opcode::LDRR(Register::E, Register::E)), // 0x5B
+    Some(Opcode::LDRR(Register::E, Register::H)), // 0x5C
+    Some(Opcode::LDRR(Register::E, Register::L)), // 0x5D
+    Some(Opcode::LDRHL(Register::E)), // 0x5E
+    Some(Opcode::LDRR(Register::E, Register::A)), // 0x5F
+    Some(Opcode::LDRR(Register::H, Register::B)), // 0x60
+    Some(Opcode::LDRR(Register::H, Register::C)), // 0x61
+    Some(Opcode::LDRR(Register::H, Register::D)), // 0x62
+    Some(Opcode::LDRR(Register::H, Register::E)), // 0x63
+    Some(Opcode::LDRR(Register::H, Register::H)), // 0x64
+    Some(Opcode::LDRR(Register::H, Register::L)), // 0x65
+    Some(Opcode::LDRHL(Register::H)), // 0x66
+    Some(Opcode::LDRR(Register::H, Register::A)), // 0x67
+    Some(Opcode::LDRR(Register::L, Register::B)), // 0x68
+    Some(Opcode::LDRR(Register::L, Register::C)), // 0x69
+    Some(Opcode::LDRR(Register::L, Register::D)), // 0x6A
+    Some(Opcode::LDRR(Register::L, Register::E)), // 0x6B
+    Some(Opcode::LDRR(Register::L, Register::H)), // 0x6C
+    Some(Opcode::LDRR(Register::L, Register::L)), // 0x6D
+    Some(Opcode::LDRHL(Register::L)), // 0x6E
+    Some(Opcode::LDRR(Register::L, Register::A)), // 0x6F
+    Some(Opcode::LDHLR(Register::B)), // 0x70
+    Some(Opcode::LDHLR(Register::C)), // 0x71
+    Some(Opcode::LDHLR(Register::D)), // 0x72
+    Some(Opcode::LDHLR(Register::E)), // 0x73
+    Some(Opcode::LDHLR(Register::H)), // 0x74
+    Some(Opcode::LDHLR(Register::L)), // 0x75
+    Some(Opcode::HALT), // 0x76
+    Some(Opcode::LDHLR(Register::A)), // 0x77
+    Some(Opcode::LDRR(Register::A, Register::B)), // 0x78
+    Some(Opcode::LDRR(Register::A, Register::C)), // 0x79
+    Some(Opcode::LDRR(Register::A, Register::D)), // 0x7A
+    Some(Opcode::LDRR(Register::A, Register::E)), // 0x7B
+    Some(Opcode::LDRR(Register::A, Register::H)), // 0x7C
+    Some(Opcode::LDRR(Register::A, Register::L)), // 0x7D
+    Some(Opcode::LDRHL(Register::A)), // 0x7E
+    Some(Opcode::LDRR(Register::A, Register::A)), // 0x7F
+    Some(Opcode::ADD(Register::B)), // 0x80
+    Some(Opcode::ADD(Register::C)), // 0x81
+    Some(Opcode::ADD(Register::D)), // 0x82
+    Some(Opcode::ADD(Register::E)), // 0x83
+    Some(Opcode::ADD(Register::H)), // 0x84
+    Some(Opcode::ADD(Register::L)), // 0x85
+    Some(Opcode::ADDHL), // 0x86
+    Some(Opcode::ADD(Register::A)), // 0x87
+    Some(Opcode::ADC(Register::B)), // 0x88
+    Some(Opcode::ADC(Register::C)), // 0x89
+    Some(Opcode::ADC(Register::D)), // 0x8A
+    Some(Opcode::ADC(Register::E)), // 0x8B
+    Some(Opcode::ADC(Register::H)), // 0x8C
+    Some(Opcode::ADC(Register::L)), // 0x8D
+    Some(Opcode::ADCHL), // 0x8E
+    Some(Opcode::ADC(Register::A)), // 0x8F
+    Some(Opcode::SUB(Register::B)), // 0x90
+    Some(Opcode::SUB(Register::C)), // 0x91
+    Some(Opcode::SUB(Register::D)), // 0x92
+    Some(Opcode::SUB(Register::E)), // 0x93
+    Some(Opcode::SUB(Register::H)), // 0x94
+    Some(Opcode::SUB(Register::L)), // 0x95
+    Some(Opcode::SUBHL), // 0x96
+    Some(Opcode::SUB(Register::A)), // 0x97
+    Some(Opcode::SBC(Register::B)), // 0x98
+    Some(Opcode::SBC(Register::C)), // 0x99
+    Some(Opcode::SBC(Register::D)), // 0x9A
+    Some(Opcode::SBC(Register::E)), // 0x9B
+    Some(Opcode::SBC(Register::H)), // 0x9C
+    Some(Opcode::SBC(Register::L)), // 0x9D
+    Some(Opcode::SBCHL), // 0x9E
+    Some(Opcode::SBC(Register::A)), // 0x9F
+    Some(Opcode::AND(Register::B)), // 0xA0
+    Some(Opcode::AND(Register::C)), // 0xA1
+    Some(Opcode::AND(Register::D)), // 0xA2
+    Some(Opcode::AND(Register::E)), // 0xA3
+    Some(Opcode::AND(Register::H)), // 0xA4
+    Some(Opcode::AND(Register::L)), // 0xA5
+    Some(Opcode::ANDHL), // 0xA6
+    Some(Opcode::AND(Register::A)), // 0xA7
+    Some(Opcode::XOR(Register::B)), // 0xA8
+    Some(Opcode::XOR(Register::C)), // 0xA9
+    Some(Opcode::XOR(Register::D)), // 0xAA
+    Some(Opcode::XOR(Register::E)), // 0xAB
+    Some(Opcode::XOR(Register::H)), // 0xAC
+    Some(Opcode::XOR(Register::L)), // 0xAD
+    Some(Opcode::XORHL), // 0xAE
+    Some(Opcode::XOR(Register::A)), // 0xAF
+    Some(Opcode::OR(Register::B)), // 0xB0
+    Some(Opcode::OR(Register::C)), // 0xB1
+    Some(Opcode::OR(Register::D)), // 0xB2
+    Some(Opcode::OR(Register::E)), // 0xB3
+    Some(Opcode::OR(Register::H)), // 0xB4
+    Some(Opcode::OR(Register::L)), // 0xB5
+    Some(Opcode::ORHL), // 0xB6
+    Some(Opcode::OR(Register::A)), // 0xB7
+    Some(Opcode::CP(Register::B)), // 0xB8
+    Some(Opcode::CP(Register::C)), // 0xB9
+    Some(Opcode::CP(Register::D)), // 0xBA
+    Some(Opcode::CP(Register::E)), // 0xBB
+    Some(Opcode::CP(Register::H)), // 0xBC
+    Some(Opcode::CP(Register::L)), // 0xBD
+    Some(Opcode::CPHL), // 0xBE
+    Some(Opcode::CP(Register::A)), // 0xBF
+    Some(Opcode::RETNCC(CpuFlags::Z)), // 0xC0
+    Some(Opcode::POPWR(WideRegister::BC)), // 0xC1
+    Some(Opcode::JPNCC(CpuFlags::Z)), // 0xC2
+    Some(Opcode::JP), // 0xC3
+    Some(Opcode::CALLNCC(CpuFlags::Z)), // 0xC4
+    Some(Opcode::PUSHWR(WideRegister::BC)), // 0xC5
+    Some(Opcode::ADDI), // 0xC6
+    Some(Opcode::RST(0x00)), // 0xC7
+    Some(Opcode::RETCC(CpuFlags::Z)), // 0xC8
+    Some(Opcode::RET), // 0xC9
+    Some(Opcode::JPCC(CpuFlags::Z)), // 0xCA
+    Some(Opcode::CB), // 0xCB
+    Some(Opcode::CALLCC(CpuFlags::Z)), // 0xCC
+    Some(Opcode::CALL), // 0xCD
+    Some(Opcode::ADCI), // 0xCE
+    Some(Opcode::RST(0x08)), // 0xCF
+    Some(Opcode::RETNCC(CpuFlags::C)), // 0xD0
+    Some(Opcode::POPWR(WideRegister::DE)), // 0xD1
+    Some(Opcode::JPNCC(CpuFlags::C)), // 0xD2
+    None, // 0xD3
+    Some(Opcode::CALLNCC(CpuFlags::C)), // 0xD4
+    Some(Opcode::PUSHWR(WideRegister::DE)), // 0xD5
+    Some(Opcode::SUBI), // 0xD6
+    Some(Opcode::RST(0x10)), // 0xD7
+    Some(Opcode::RETCC(CpuFlags::C)), // 0xD8
+    Some(Opcode::RETI), // 0xD9
+    Some(Opcode::JPCC(CpuFlags::C)), // 0xDA
+    None, // 0xDB
+    Some(Opcode::CALLCC(CpuFlags::C)), // 0xDC
+    None, // 0xDD
+    Some(Opcode::SBCI), // 0xDE
+    Some(Opcode::RST(0x18)), // 0xDF
+    Some(Opcode::LDIOA), // 0xE0
+    Some(Opcode::POPWR(WideRegister::HL)), // 0xE1
+    Some(Opcode::LDIOCA), // 0xE2
+    None, // 0xE3
+    None, // 0xE4
+    Some(Opcode::PUSHWR(WideRegister::HL)), // 0xE5
+    Some(Opcode::ANDI), // 0xE6
+    Some(Opcode::RST(0x20)), // 0xE7
+    Some(Opcode::ADDSP), // 0xE8
+    Some(Opcode::JPHL), // 0xE9
+    Some(Opcode::LDIWA), // 0xEA
+    None, // 0xEB
+    None, // 0xEC
+    None, // 0xED
+    Some(Opcode::XORI), // 0xEE
+    Some(Opcode::RST(0x28)), // 0xEF
+    Some(Opcode::LDAIO), // 0xF0
+    Some(Opcode::POPWR(WideRegister::AF)), // 0xF1
+    Some(Opcode::LDAIOC), // 0xF2
+    Some(Opcode::DI), // 0xF3
+    None, // 0xF4
+    Some(Opcode::PUSHWR(WideRegister::AF)), // 0xF5
+    Some(Opcode::ORI), // 0xF6
+    Some(Opcode::RST(0x30)), // 0xF7
+    Some(Opcode::LDHLSP), // 0xF8
+    Some(Opcode::LDSPHL), // 0xF9
+    Some(Opcode::LDAIW), // 0xFA
+    Some(Opcode::EI), // 0xFB
+    None, // 0xFC
+    None, // 0xFD
+    Some(Opcode::CPI), // 0xFE
+    Some(Opcode::RST(0x38)), // 0xFF
+];
+
+// Opcodes following a 0xCB prefix, indexed by their byte
+pub static CB_OPCODES: [Opcode; 256] = [
+    Opcode::RLC(Register::B), // 0x00
+    Opcode::RLC(Register::C), // 0x01
+    Opcode::RLC(Register::D), // 0x02
+    Opcode::RLC(Register::E), // 0x03
+    Opcode::RLC(Register::H), // 0x04
+    Opcode::RLC(Register::L), // 0x05
+    Opcode::RLCHL, // 0x06
+    Opcode::RLC(Register::A), // 0x07
+    Opcode::RRC(Register::B), // 0x08
+    Opcode::RRC(Register::C), // 0x09
+    Opcode::RRC(Register::D), // 0x0A
+    Opcode::RRC(Register::E), // 0x0B
+    Opcode::RRC(Register::H), // 0x0C
+    Opcode::RRC(Register::L), // 0x0D
+    Opcode::RRCHL, // 0x0E
+    Opcode::RRC(Register::A), // 0x0F
+    Opcode::RL(Register::B), // 0x10
+    Opcode::RL(Register::C), // 0x11
+    Opcode::RL(Register::D), // 0x12
+    Opcode::RL(Register::E), // 0x13
+    Opcode::RL(Register::H), // 0x14
+    Opcode::RL(Register::L), // 0x15
+    Opcode::RLHL, // 0x16
+    Opcode::RL(Register::A), // 0x17
+    Opcode::RR(Register::B), // 0x18
+    Opcode::RR(Register::C), // 0x19
+    Opcode::RR(Register::D), // 0x1A
+    Opcode::RR(Register::E), // 0x1B
+    Opcode::RR(Register::H), // 0x1C
+    Opcode::RR(Register::L), // 0x1D
+    Opcode::RRHL, // 0x1E
+    Opcode::RR(Register::A), // 0x1F
+    Opcode::SLA(Register::B), // 0x20
+    Opcode::SLA(Register::C), // 0x21
+    Opcode::SLA(Register::D), // 0x22
+    Opcode::SLA(Register::E), // 0x23
+    Opcode::SLA(Register::H), // 0x24
+    Opcode::SLA(Register::L), // 0x25
+    Opcode::SLAHL, // 0x26
+    Opcode::SLA(Register::A), // 0x27
+    Opcode::SRA(Register::B), // 0x28
+    Opcode::SRA(Register::C), // 0x29
+    Opcode::SRA(Register::D), // 0x2A
+    Opcode::SRA(Register::E), // 0x2B
+    Opcode::SRA(Register::H), // 0x2C
+    Opcode::SRA(Register::L), // 0x2D
+    Opcode::SRAHL, // 0x2E
+    Opcode::SRA(Register::A), // 0x2F
+    Opcode::SWAP(Register::B), // 0x30
+    Opcode::SWAP(Register::C), // 0x31
+    Opcode::SWAP(Register::D), // 0x32
+    Opcode::SWAP(Register::E), // 0x33
+    Opcode::SWAP(Register::H), // 0x34
+    Opcode::SWAP(Register::L), // 0x35
+    Opcode::SWAPHL, // 0x36
+    Opcode::SWAP(Register::A), // 0x37
+    Opcode::SRL(Register::B), // 0x38
+    Opcode::SRL(Register::C), // 0x39
+    Opcode::SRL(Register::D), // 0x3A
+    Opcode::SRL(Register::E), // 0x3B
+    Opcode::SRL(Register::H), // 0x3C
+    Opcode::SRL(Register::L), // 0x3D
+    Opcode::SRLHL, // 0x3E
+    Opcode::SRL(Register::A), // 0x3F
+    Opcode::BIT(0, Register::B), // 0x40
+    Opcode::BIT(0, Register::C), // 0x41
+    Opcode::BIT(0, Register::D), // 0x42
+    Opcode::BIT(0, Register::E), // 0x43
+    Opcode::BIT(0, Register::H), // 0x44
+    Opcode::BIT(0, Register::L), // 0x45
+    Opcode::BITHL(0), // 0x46
+    Opcode::BIT(0, Register::A), // 0x47
+    Opcode::BIT(1, Register::B), // 0x48
+    Opcode::BIT(1, Register::C), // 0x49
+    Opcode::BIT(1, Register::D), // 0x4A
+    Opcode::BIT(1, Register::E), // 0x4B
+    Opcode::BIT(1, Register::H), // 0x4C
+    Opcode::BIT(1, Register::L), // 0x4D
+    Opcode::BITHL(1), // 0x4E
+    Opcode::BIT(1, Register::A), // 0x4F
+    Opcode::BIT(2, Register::B), // 0x50
+    Opcode::BIT(2, Register::C), // 0x51
+    Opcode::BIT(2, Register::D), // 0x52
+    Opcode::BIT(2, Register::E), // 0x53
+    Opcode::BIT(2, Register::H), // 0x54
+    Opcode::BIT(2, Register::L), // 0x55
+    Opcode::BITHL(2), // 0x56
+    Opcode::BIT(2, Register::A), // 0x57
+    Opcode::BIT(3, Register::B), // 0x58
+    Opcode::BIT(3, Register::C), // 0x59
+    Opcode::BIT(3, Register::D), // 0x5A
+    Opcode::BIT(3, Register::E), // 0x5B
+    Opcode::BIT(3, Register::H), // 0x5C
+    Opcode::BIT(3, Register::L), // 0x5D
+    Opcode::BITHL(3), // 0x5E
+    Opcode::BIT(3, Register::A), // 0x5F
+    Opcode::BIT(4, Register::B), // 0x60
+    Opcode::BIT(4, Register::C), // 0x61
+    Opcode::BIT(4, Register::D), // 0x62
+    Opcode::BIT(4, Register::E), // 0x63
+    Opcode::BIT(4, Register::H), // 0x64
+    Opcode::BIT(4, Register::L), // 0x65
+    Opcode::BITHL(4), // 0x66
+    Opcode::BIT(4, Register::A), // 0x67
+    Opcode::BIT(5, Register::B), // 0x68
+    Opcode::BIT(5, Register::C), // 0x69
+    Opcode::BIT(5, Register::D), // 0x6A
+    Opcode::BIT(5, Register::E), // 0x6B
+    Opcode::BIT(5, Register::H), // 0x6C
+    Opcode::BIT(5, Register::L), // 0x6D
+    Opcode::BITHL(5), // 0x6E
+    Opcode::BIT(5, Register::A), // 0x6F
+    Opcode::BIT(6, Register::B), // 0x70
+    Opcode::BIT(6, Register::C), // 0x71
+    Opcode::BIT(6, Register::D), // 0x72
+    Opcode::BIT(6, Register::E), // 0x73
+    Opcode::BIT(6, Register::H), // 0x74
+    Opcode::BIT(6, Register::L), // 0x75
+    Opcode::BITHL(6), // 0x76
+    Opcode::BIT(6, Register::A), // 0x77
+    Opcode::BIT(7, Register::B), // 0x78
+    Opcode::BIT(7, Register::C), // 0x79
+    Opcode::BIT(7, Register::D), // 0x7A
+    Opcode::BIT(7, Register::E), // 0x7B
+    Opcode::BIT(7, Register::H), // 0x7C
+    Opcode::BIT(7, Register::L), // 0x7D
+    Opcode::BITHL(7), // 0x7E
+    Opcode::BIT(7, Register::A), // 0x7F
+    Opcode::RES(0, Register::B), // 0x80
+    Opcode::RES(0, Register::C), // 0x81
+    Opcode::RES(0, Register::D), // 0x82
+    Opcode::RES(0, Register::E), // 0x83
+    Opcode::RES(0, Register::H), // 0x84
+    Opcode::RES(0, Register::L), // 0x85
+    Opcode::RESHL(0), // 0x86
+    Opcode::RES(0, Register::A), // 0x87
+    Opcode::RES(1, Register::B), // 0x88
+    Opcode::RES(1, Register::C), // 0x89
+    Opcode::RES(1, Register::D), // 0x8A
+    Opcode::RES(1, Register::E), // 0x8B
+    Opcode::RES(1, Register::H), // 0x8C
+    Opcode::RES(1, Register::L), // 0x8D
+    Opcode::RESHL(1), // 0x8E
+    Opcode::RES(1, Register::A), // 0x8F
+    Opcode::RES(2, Register::B), // 0x90
+    Opcode::RES(2, Register::C), // 0x91
+    Opcode::RES(2, Register::D), // 0x92
+    Opcode::RES(2, Register::E), // 0x93
+    Opcode::RES(2, Register::H), // 0x94
+    Opcode::RES(2, Register::L), // 0x95
+    Opcode::RESHL(2), // 0x96
+    Opcode::RES(2, Register::A), // 0x97
+    Opcode::RES(3, Register::B), // 0x98
+    Opcode::RES(3, Register::C), // 0x99
+    Opcode::RES(3, Register::D), // 0x9A
+    Opcode::RES(3, Register::E), // 0x9B
+    Opcode::RES(3, Register::H), // 0x9C
+    Opcode::RES(3, Register::L), // 0x9D
+    Opcode::RESHL(3), // 0x9E
+    Opcode::RES(3, Register::A), // 0x9F
+    Opcode::RES(4, Register::B), // 0xA0
+    Opcode::RES(4, Register::C), // 0xA1
+    Opcode::RES(4, Register::D), // 0xA2
+    Opcode::RES(4, Register::E), // 0xA3
+    Opcode::RES(4, Register::H), // 0xA4
+    Opcode::RES(4, Register::L), // 0xA5
+    Opcode::RESHL(4), // 0xA6
+    Opcode::RES(4, Register::A), // 0xA7
+    Opcode::RES(5, Register::B), // 0xA8
+    Opcode::RES(5, Register::C), // 0xA9
+    Opcode::RES(5, Register::D), // 0xAA
+    Opcode::RES(5, Register::E), // 0xAB
+    Opcode::RES(5, Register::H), // 0xAC
+    Opcode::RES(5, Register::L), // 0xAD
+    Opcode::RESHL(5), // 0xAE
+    Opcode::RES(5, Register::A), // 0xAF
+    Opcode::RES(6, Register::B), // 0xB0
+    Opcode::RES(6, Register::C), // 0xB1
+    Opcode::RES(6, Register::D), // 0xB2
+    Opcode::RES(6, Register::E), // 0xB3
+    Opcode::RES(6, Register::H), // 0xB4
+    Opcode::RES(6, Register::L), // 0xB5
+    Opcode::RESHL(6), // 0xB6
+    Opcode::RES(6, Register::A), // 0xB7
+    Opcode::RES(7, Register::B), // 0xB8
+    Opcode::RES(7, Register::C), // 0xB9
+    Opcode::RES(7, Register::D), // 0xBA
+    Opcode::RES(7, Register::E), // 0xBB
+    Opcode::RES(7, Register::H), // 0xBC
+    Opcode::RES(7, Register::L), // 0xBD
+    Opcode::RESHL(7), // 0xBE
+    Opcode::RES(7, Register::A), // 0xBF
+    Opcode::SET(0, Register::B), // 0xC0
+    Opcode::SET(0, Register::C), // 0xC1
+    Opcode::SET(0, Register::D), // 0xC2
+    Opcode::SET(0, Register::E), // 0xC3
+    Opcode::SET(0, Register::H), // 0xC4
+    Opcode::SET(0, Register::L), // 0xC5
+    Opcode::SETHL(0), // 0xC6
+    Opcode::SET(0, Register::A), // 0xC7
+    Opcode::SET(1, Register::B), // 0xC8
+    Opcode::SET(1, Register::C), // 0xC9
+    Opcode::SET(1, Register::D), // 0xCA
+    Opcode::SET(1, Register::E), // 0xCB
+    Opcode::SET(1, Register::H), // 0xCC
+    Opcode::SET(1, Register::L), // 0xCD
+    Opcode::SETHL(1), // 0xCE
+    Opcode::SET(1, Register::A), // 0xCF
+    Opcode::SET(2, Register::B), // 0xD0
+    Opcode::SET(2, Register::C), // 0xD1
+    Opcode::SET(2, Register::D), // 0xD2
+    Opcode::SET(2, Register::E), // 0xD3
+    Opcode::SET(2, Register::H), // 0xD4
+    Opcode::SET(2, Register::L), // 0xD5
+    Opcode::SETHL(2), // 0xD6
+    Opcode::SET(2, Register::A), // 0xD7
+    Opcode::SET(3, Register::B), // 0xD8
+    Opcode::SET(3, Register::C), // 0xD9
+    Opcode::SET(3, Register::D), // 0xDA
+    Opcode::SET(3, Register::E), // 0xDB
+    Opcode::SET(3, Register::H), // 0xDC
+    Opcode::SET(3, Register::L), // 0xDD
+    Opcode::SETHL(3), // 0xDE
+    Opcode::SET(3, Register::A), // 0xDF
+    Opcode::SET(4, Register::B), // 0xE0
+    Opcode::SET(4, Register::C), // 0xE1
+    Opcode::SET(4, Register::D), // 0xE2
+    Opcode::SET(4, Register::E), // 0xE3
+    Opcode::SET(4, Register::H), // 0xE4
+    Opcode::SET(4, Register::L), // 0xE5
+    Opcode::SETHL(4), // 0xE6
+    Opcode::SET(4, Register::A), // 0xE7
+    Opcode::SET(5, Register::B), // 0xE8
+    Opcode::SET(5, Register::C), // 0xE9
+    Opcode::SET(5, Register::D), // 0xEA
+    Opcode::SET(5, Register::E), // 0xEB
+    Opcode::SET(5, Register::H), // 0xEC
+    Opcode::SET(5, Register::L), // 0xED
+    Opcode::SETHL(5), // 0xEE
+    Opcode::SET(5, Register::A), // 0xEF
+    Opcode::SET(6, Register::B), // 0xF0
+    Opcode::SET(6, Register::C), // 0xF1
+    Opcode::SET(6, Register::D), // 0xF2
+    Opcode::SET(6, Register::E), // 0xF3
+    Opcode::SET(6, Register::H), // 0xF4
+    Opcode::SET(6, Register::L), // 0xF5
+    Opcode::SETHL(6), // 0xF6
+    Opcode::SET(6, Register::A), // 0xF7
+    Opcode::SET(7, Register::B), // 0xF8
+    Opcode::SET(7, Register::C), // 0xF9
+    Opcode::SET(7, Register::D), // 0xFA
+    Opcode::SET(7, Register::E), // 0xFB
+    Opcode::SET(7, Register::H), // 0xFC
+    Opcode::SET(7, Register::L), // 0xFD
+    Opcode::SETHL(7), // 0xFE
+    Opcode::SET(7, Register::A), // 0xFF
+];
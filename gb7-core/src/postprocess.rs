@@ -0,0 +1,105 @@
+// Optional CPU-side filters applied to completed RGBA frames
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCorrection {
+    // Gamma used to linearize incoming colours and to re-encode the result
+    pub target_gamma: f32,
+    pub display_gamma: f32,
+    // Overall brightness applied to the mixed colour
+    pub luminance: f32,
+    // Each output channel as a mix of linear input red, green and blue
+    pub matrix: [[f32; 3]; 3],
+}
+
+impl ColorCorrection {
+    // Approximates the washed-out, slightly blue-shifted Gameboy Color screen
+    pub const CGB: ColorCorrection = ColorCorrection {
+        target_gamma: 2.2,
+        display_gamma: 2.2,
+        luminance: 0.94,
+        matrix: [
+            [0.78, 0.12, 0.10],
+            [0.16, 0.72, 0.12],
+            [0.02, 0.22, 0.76],
+        ],
+    };
+
+    // Approximates the darker, unlit Gameboy Advance screen
+    pub const GBA: ColorCorrection = ColorCorrection {
+        target_gamma: 2.2 + 1.0,
+        display_gamma: 2.2,
+        luminance: 0.93,
+        matrix: [
+            [0.80, 0.275, -0.075],
+            [0.135, 0.64, 0.225],
+            [0.195, 0.155, 0.65],
+        ],
+    };
+
+    pub fn correct(&self, rgb: [u8; 3]) -> [u8; 3] {
+        let linear = rgb.map(|c| (c as f32 / 255.0).powf(self.target_gamma));
+
+        let mut out = [0; 3];
+        for (channel, weights) in out.iter_mut().zip(self.matrix.iter()) {
+            let mixed = weights[0] * linear[0] + weights[1] * linear[1] + weights[2] * linear[2];
+            let value = (mixed * self.luminance).clamp(0.0, 1.0);
+            *channel = (value.powf(1.0 / self.display_gamma) * 255.0).round() as u8;
+        }
+        out
+    }
+}
+
+#[derive(Default)]
+pub struct PostProcess {
+    pub color_correction: Option<ColorCorrection>,
+    // Weight of the previous frame when blending, emulating the slow LCD response that
+    // some games rely on for flicker transparency. 0.5 is an even mix.
+    pub ghosting: Option<f32>,
+    previous: Vec<u8>,
+}
+
+impl PostProcess {
+    pub fn new(color_correction: Option<ColorCorrection>, ghosting: Option<f32>) -> Self {
+        PostProcess {
+            color_correction,
+            ghosting,
+            previous: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.color_correction.is_some() || self.ghosting.is_some()
+    }
+
+    // Filter a completed frame into `out`, remembering it for the next frame's blending
+    pub fn process(&mut self, frame: &[u8], out: &mut [u8]) {
+        self.render(frame, out);
+        self.previous.clear();
+        self.previous.extend_from_slice(frame);
+    }
+
+    // Filter a frame without advancing the blending history, e.g. to redraw while paused
+    pub fn render(&self, frame: &[u8], out: &mut [u8]) {
+        let ghosting = match self.ghosting {
+            Some(weight) if self.previous.len() == frame.len() => Some(weight.clamp(0.0, 1.0)),
+            _ => None,
+        };
+
+        for (i, (dst, src)) in out.chunks_exact_mut(4).zip(frame.chunks_exact(4)).enumerate() {
+            let mut rgb = [src[0], src[1], src[2]];
+
+            if let Some(weight) = ghosting {
+                let prev = &self.previous[i * 4..i * 4 + 3];
+                for (c, &p) in rgb.iter_mut().zip(prev) {
+                    *c = (*c as f32 * (1.0 - weight) + p as f32 * weight).round() as u8;
+                }
+            }
+
+            if let Some(correction) = &self.color_correction {
+                rgb = correction.correct(rgb);
+            }
+
+            dst.copy_from_slice(&[rgb[0], rgb[1], rgb[2], src[3]]);
+        }
+    }
+}
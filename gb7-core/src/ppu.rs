@@ -1,14 +1,40 @@
+use std::io;
+
 use crate::{
     lcd::Lcd,
     memory::{IORegs, Oam, VideoMem, VideoRam},
+    savestate::{self, Snapshot, StateReader, StateWriter},
 };
 
+// Debugging aids that change what is drawn without affecting emulation
+#[derive(Default, Clone, Copy)]
+pub struct PpuDebug {
+    pub hide_background: bool,
+    pub hide_window: bool,
+    pub hide_sprites: bool,
+    // Bit n hides OAM entry n. Hidden sprites still count towards the 10 per line limit.
+    pub hidden_sprites: u64,
+    // Tint each pixel by the layer that produced it
+    pub tint_layers: bool,
+}
+
+// The layer that produced a pixel
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    #[default]
+    Background,
+    Window,
+    Sprite,
+}
+
 #[derive(Default)]
 pub struct Ppu {
+    pub debug: PpuDebug,
     mode: PpuMode,
     line_cycles: u32,
     reached_window: bool,
     window_line_counter: u16,
+    window_full_next_line: bool,
 }
 
 impl Ppu {
@@ -34,12 +60,16 @@ impl Ppu {
         match (self.mode, self.line_cycles) {
             (_, 456..) => {
                 // Any mode and > 456 line cycles: go to next line
-                self.move_to_next_line(io_regs);
+                self.move_to_next_line(io_regs, lcd);
             }
             (PpuMode::OAMScan, 80..) => {
                 // Change from OAMScan to Drawing
-                let line = self.get_line(ly, vram, oam, io_regs);
-                lcd.set_line(ly, line);
+                let (line, layers) = self.get_line(ly, vram, oam, io_regs);
+                if self.debug.tint_layers {
+                    lcd.set_line_tinted(ly, line, &layers);
+                } else {
+                    lcd.set_line(ly, line);
+                }
                 self.mode = PpuMode::Drawing;
             }
             (PpuMode::Drawing, 252..) => {
@@ -71,12 +101,13 @@ impl Ppu {
         io_regs.write(0xFF41, new_stat);
     }
 
-    fn move_to_next_line(&mut self, io_regs: &mut IORegs) {
+    fn move_to_next_line(&mut self, io_regs: &mut IORegs, lcd: &mut Lcd) {
         // Add one to our line number
         let new_ly = (io_regs.read(0xFF44) + 1) % 154;
 
-        // Indicate if window has been reached
-        if new_ly == io_regs.read(0xFF4A) {
+        // The WY condition is checked once per line at the start of OAM scan. Once it has
+        // triggered, the window stays eligible for the rest of the frame even if WY changes.
+        if new_ly < 144 && new_ly == io_regs.read(0xFF4A) {
             self.reached_window = true;
         }
 
@@ -102,8 +133,10 @@ impl Ppu {
                     Ppu::req_stat_interrupt(io_regs);
                 }
                 Ppu::req_vblank_interrupt(io_regs);
+                lcd.finish_frame();
                 self.reached_window = false;
                 self.window_line_counter = 0;
+                self.window_full_next_line = false;
             }
             PpuMode::VBlank
         } else {
@@ -114,27 +147,44 @@ impl Ppu {
         };
     }
 
-    fn get_line(&mut self, ly: u8, vram: &VideoRam, oam: &Oam, io_regs: &IORegs) -> [u8; 160] {
+    fn get_line(
+        &mut self,
+        ly: u8,
+        vram: &VideoRam,
+        oam: &Oam,
+        io_regs: &IORegs,
+    ) -> ([u8; 160], [Layer; 160]) {
         let mut line: [u8; 160] = [0; 160];
+        let mut layers: [Layer; 160] = [Layer::Background; 160];
 
         let lcdc = io_regs.read(0xFF40);
 
         // Background and Window are only drawn if bit 0 of LCDC is set
         if (lcdc & 0b0000_0001) != 0 {
-            self.apply_background_line(ly, &mut line, vram, io_regs);
+            if !self.debug.hide_background {
+                self.apply_background_line(ly, &mut line, vram, io_regs);
+            }
 
-            // Window additionally needs bit 5 of LCDC
-            if lcdc & 0b0010_0000 != 0 {
-                self.apply_window_line(ly, &mut line, vram, io_regs);
+            // Window additionally needs bit 5 of LCDC and the WY condition for this frame
+            if lcdc & 0b0010_0000 != 0 && self.reached_window {
+                if self.debug.hide_window {
+                    // Draw into a scratch line so the window line counter still advances
+                    let (mut hidden_line, mut hidden_layers) = (line, layers);
+                    self.apply_window_line(&mut hidden_line, &mut hidden_layers, vram, io_regs);
+                } else {
+                    self.apply_window_line(&mut line, &mut layers, vram, io_regs);
+                }
+            } else {
+                self.window_full_next_line = false;
             }
         }
 
         // Sprites are only drawn if bit 1 of LCDC is set
-        if (lcdc & 0b0000_0010) != 0 {
-            self.apply_sprite_line(ly, &mut line, vram, oam, io_regs);
+        if (lcdc & 0b0000_0010) != 0 && !self.debug.hide_sprites {
+            self.apply_sprite_line(ly, &mut line, &mut layers, vram, oam, io_regs);
         }
 
-        line
+        (line, layers)
     }
 
     fn apply_background_line(
@@ -194,8 +244,8 @@ impl Ppu {
 
     fn apply_window_line(
         &mut self,
-        ly: u8,
         line: &mut [u8; 160],
+        layers: &mut [Layer; 160],
         vram: &VideoRam,
         io_regs: &IORegs,
     ) {
@@ -212,42 +262,62 @@ impl Ppu {
 
         let bg_palette = io_regs.read(0xFF47);
 
-        // Get window X/Y position
-        let (wy, wx) = (io_regs.read(0xFF4A), io_regs.read(0xFF4B));
-
-        // Check to make sure the window is in range:
-        if ly >= wy && wx >= 7 && wx < 167 {
-            for x_counter in 0..20 {
-                let addr =
-                    window_tilemap + (x_counter as u16) + (self.window_line_counter / 8) * 32;
-                let tile_num = vram.read(addr);
-
-                let tile_addr = match tile_mode_8000 {
-                    true => 0x8000 + (tile_num as u16) * 16,
-                    false => 0x8800 + ((tile_num as i8 as i16 + 128) as u16) * 16,
-                } + (self.window_line_counter % 8) * 2 as u16;
-
-                let b1 = vram.read(tile_addr);
-                let b2 = vram.read(tile_addr + 1);
-                for px in 0..8 {
-                    let linepos = x_counter as u16 * 8 + (px + wx - 7) as u16;
-                    if linepos < 160 {
-                        let px_val = if b1 & (1 << 7 - px) != 0 { 1 } else { 0 }
-                            | if b2 & (1 << 7 - px) != 0 { 2 } else { 0 };
-                        let color = (bg_palette >> (px_val * 2)) & 0x3;
-                        line[linepos as usize] = color;
-                    }
-                }
-            }
+        // Get window X position and background X scroll (for the WX=0 quirk)
+        let (wx, scx) = (io_regs.read(0xFF4B), io_regs.read(0xFF43));
+
+        // A WX of 166 on the previous line makes the window span this entire line
+        let full_line = self.window_full_next_line;
+        self.window_full_next_line = false;
+
+        // Number of window pixels that fall off the left edge of the screen, and the first
+        // screen position the window covers. WX=0 additionally inherits the fine background
+        // scroll, which makes the window "stutter" as SCX changes.
+        let (clipped, start) = match wx {
+            _ if full_line => (0, 0),
+            0 => (7 + (scx % 8) as u16, 0),
+            1..=6 => (7 - wx as u16, 0),
+            7..=166 => (0, wx as u16 - 7),
+            _ => return,
+        };
 
-            self.window_line_counter += 1;
+        if wx == 166 {
+            self.window_full_next_line = true;
         }
+
+        let tile_row = self.window_line_counter % 8;
+        let map_row = (self.window_line_counter / 8) & 0x1F;
+
+        for linepos in start..160 {
+            let window_x = linepos - start + clipped;
+
+            let addr = window_tilemap + ((window_x / 8) & 0x1F) + map_row * 32;
+            let tile_num = vram.read(addr);
+
+            let tile_addr = match tile_mode_8000 {
+                true => 0x8000 + (tile_num as u16) * 16,
+                false => 0x8800 + ((tile_num as i8 as i16 + 128) as u16) * 16,
+            } + tile_row * 2;
+
+            let b1 = vram.read(tile_addr);
+            let b2 = vram.read(tile_addr + 1);
+
+            let px = 7 - (window_x % 8);
+            let px_val = if b1 & (1 << px) != 0 { 1 } else { 0 }
+                | if b2 & (1 << px) != 0 { 2 } else { 0 };
+            let color = (bg_palette >> (px_val * 2)) & 0x3;
+            line[linepos as usize] = color;
+            layers[linepos as usize] = Layer::Window;
+        }
+
+        // The internal line counter only advances on lines where the window was drawn
+        self.window_line_counter += 1;
     }
 
     fn apply_sprite_line(
         &self,
         ly: u8,
         line: &mut [u8; 160],
+        layers: &mut [Layer; 160],
         vram: &VideoRam,
         oam: &Oam,
         io_regs: &IORegs,
@@ -260,49 +330,53 @@ impl Ppu {
         let tall_sprite_mode = lcdc & 0b0000_0100 != 0;
         let sprite_height = if tall_sprite_mode { 16 } else { 8 };
         let mut buffered_sprites = 0;
-        for (y, x, mut tidx, flags) in oam.iter_entries() {
+        for (index, (y, x, mut tidx, flags)) in oam.iter_entries().enumerate() {
             tidx &= if tall_sprite_mode { 0xFE } else { 0xFF };
 
             // Check to make sure this sprite is in range
             if x > 0 && (ly + 16) >= y && (ly + 16) < (y + sprite_height) {
                 buffered_sprites += 1;
 
-                // Read flags
-                let background_priority = flags & 0b1000_0000 != 0;
-                let yflip = flags & 0b0100_0000 != 0;
-                let xflip = flags & 0b0010_0000 != 0;
-                let sprite_palette = if flags & 0b0001_0000 != 0 {
-                    io_regs.read(0xFF49)
-                } else {
-                    io_regs.read(0xFF48)
-                };
-
-                let y_line_skew = if yflip {
-                    sprite_height - 1 - (ly + 16).wrapping_sub(y)
-                } else {
-                    ly + 16 - y
-                } as u16;
-
-                // Read sprite from vram
-                let tile_addr = 0x8000 + (tidx as u16 * 16 + (y_line_skew * 2));
-                let b1 = vram.read(tile_addr);
-                let b2 = vram.read(tile_addr + 1);
-
-                // Iterate sprite pixels for this line
-                for px in 0..8 {
-                    if x <= 255 - px && x + px >= 8 {
-                        let linepos = (x + px - 8) as usize;
-                        if linepos > 0 && linepos < 160 {
-                            let sprite_pos = if xflip { px } else { 7 - px };
-                            let px_val: u8 = if b1 & (1 << sprite_pos) != 0 { 1 } else { 0 }
-                                | if b2 & (1 << sprite_pos) != 0 { 2 } else { 0 };
-                            let color = (sprite_palette >> (px_val * 2)) & 0x3;
-
-                            if priority[linepos] > x && px_val != 0 {
-                                priority[linepos] = x;
-
-                                if line[linepos] == 0 || !background_priority {
-                                    line[linepos] = color;
+                // Hidden sprites still take up one of the 10 slots on the line
+                if self.debug.hidden_sprites & (1 << index) == 0 {
+                    // Read flags
+                    let background_priority = flags & 0b1000_0000 != 0;
+                    let yflip = flags & 0b0100_0000 != 0;
+                    let xflip = flags & 0b0010_0000 != 0;
+                    let sprite_palette = if flags & 0b0001_0000 != 0 {
+                        io_regs.read(0xFF49)
+                    } else {
+                        io_regs.read(0xFF48)
+                    };
+
+                    let y_line_skew = if yflip {
+                        sprite_height - 1 - (ly + 16).wrapping_sub(y)
+                    } else {
+                        ly + 16 - y
+                    } as u16;
+
+                    // Read sprite from vram
+                    let tile_addr = 0x8000 + (tidx as u16 * 16 + (y_line_skew * 2));
+                    let b1 = vram.read(tile_addr);
+                    let b2 = vram.read(tile_addr + 1);
+
+                    // Iterate sprite pixels for this line
+                    for px in 0..8 {
+                        if x <= 255 - px && x + px >= 8 {
+                            let linepos = (x + px - 8) as usize;
+                            if linepos > 0 && linepos < 160 {
+                                let sprite_pos = if xflip { px } else { 7 - px };
+                                let px_val: u8 = if b1 & (1 << sprite_pos) != 0 { 1 } else { 0 }
+                                    | if b2 & (1 << sprite_pos) != 0 { 2 } else { 0 };
+                                let color = (sprite_palette >> (px_val * 2)) & 0x3;
+
+                                if priority[linepos] > x && px_val != 0 {
+                                    priority[linepos] = x;
+
+                                    if line[linepos] == 0 || !background_priority {
+                                        line[linepos] = color;
+                                        layers[linepos] = Layer::Sprite;
+                                    }
                                 }
                             }
                         }
@@ -339,3 +413,28 @@ impl Default for PpuMode {
         PpuMode::OAMScan
     }
 }
+
+impl Snapshot for Ppu {
+    fn save(&self, w: &mut StateWriter) {
+        w.u8(self.mode as u8);
+        w.u32(self.line_cycles);
+        w.bool(self.reached_window);
+        w.u16(self.window_line_counter);
+        w.bool(self.window_full_next_line);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.mode = match r.u8()? {
+            0 => PpuMode::HBlank,
+            1 => PpuMode::VBlank,
+            2 => PpuMode::OAMScan,
+            3 => PpuMode::Drawing,
+            _ => return Err(savestate::invalid("invalid PPU mode in save state")),
+        };
+        self.line_cycles = r.u32()?;
+        self.reached_window = r.bool()?;
+        self.window_line_counter = r.u16()?;
+        self.window_full_next_line = r.bool()?;
+        Ok(())
+    }
+}
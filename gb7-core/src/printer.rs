@@ -0,0 +1,298 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use crate::serial::SerialDevice;
+
+const COMMAND_INIT: u8 = 0x01;
+const COMMAND_PRINT: u8 = 0x02;
+const COMMAND_DATA: u8 = 0x04;
+const COMMAND_STATUS: u8 = 0x0F;
+
+const STATUS_CHECKSUM_ERROR: u8 = 0b0000_0001;
+const STATUS_PRINTING: u8 = 0b0000_0010;
+const STATUS_IMAGE_FULL: u8 = 0b0000_0100;
+const STATUS_UNPROCESSED: u8 = 0b0000_1000;
+
+// The printer's buffer holds 9 DATA packets of 40 tiles, a full 160x144 screen
+const BAND_BYTES: usize = 640;
+const BUFFER_BYTES: usize = BAND_BYTES * 9;
+const WIDTH: usize = 160;
+// Pixel rows of blank paper fed for each margin unit
+const FEED_HEIGHT: usize = 16;
+// STATUS requests answered as busy after each PRINT
+const PRINT_POLLS: u8 = 4;
+// Paper shades for colour 0-3
+const SHADES: [u8; 4] = [255, 170, 85, 0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PacketState {
+    Magic1,
+    Magic2,
+    Command,
+    Compression,
+    LengthLo,
+    LengthHi,
+    Data,
+    ChecksumLo,
+    ChecksumHi,
+    Alive,
+    Status,
+}
+
+// A Game Boy Printer on the serial port. Packets are `88 33`, command, compression flag,
+// little-endian data length, data and a little-endian checksum of everything after the
+// magic bytes, followed by two bytes in which the printer answers 0x81 and its status.
+// Each PRINT writes the buffered image as a PNG to the output directory.
+pub struct Printer {
+    output_dir: PathBuf,
+    next_file: usize,
+    pub printed: Vec<PathBuf>,
+    // The last print that couldn't be written, until taken
+    error: Option<io::Error>,
+
+    state: PacketState,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    data: Vec<u8>,
+    checksum: u16,
+    received_checksum: u16,
+
+    buffer: Vec<u8>,
+    status: u8,
+    printing_polls: u8,
+}
+
+impl Printer {
+    pub fn new(output_dir: impl Into<PathBuf>) -> Self {
+        Printer {
+            output_dir: output_dir.into(),
+            next_file: 1,
+            printed: Vec::new(),
+            error: None,
+            state: PacketState::Magic1,
+            command: 0,
+            compressed: false,
+            length: 0,
+            data: Vec::new(),
+            checksum: 0,
+            received_checksum: 0,
+            buffer: Vec::new(),
+            status: 0,
+            printing_polls: 0,
+        }
+    }
+
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    // The error from the last print that failed to be written, if any since last taken
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn handle_command(&mut self) {
+        if self.checksum != self.received_checksum {
+            self.status |= STATUS_CHECKSUM_ERROR;
+            return;
+        }
+        self.status &= !STATUS_CHECKSUM_ERROR;
+
+        match self.command {
+            COMMAND_INIT => {
+                self.buffer.clear();
+                self.status = 0;
+                self.printing_polls = 0;
+            }
+            COMMAND_DATA => {
+                let data = match self.compressed {
+                    true => decompress(&self.data),
+                    false => self.data.clone(),
+                };
+                let space = BUFFER_BYTES - self.buffer.len();
+                self.buffer.extend_from_slice(&data[..data.len().min(space)]);
+
+                // An empty DATA packet ends the image
+                if !self.buffer.is_empty() {
+                    self.status |= STATUS_UNPROCESSED;
+                }
+                if self.buffer.len() == BUFFER_BYTES {
+                    self.status |= STATUS_IMAGE_FULL;
+                }
+            }
+            COMMAND_PRINT if self.data.len() >= 4 => {
+                // Sheet count, margins (feeds before in the upper nibble, after in the
+                // lower), palette and exposure, which doesn't affect the output
+                let margins = self.data[1];
+                let palette = match self.data[2] {
+                    // Some games send 0 for the default palette
+                    0 => 0b1110_0100,
+                    palette => palette,
+                };
+                self.print(margins >> 4, margins & 0x0F, palette);
+
+                self.buffer.clear();
+                self.status = STATUS_PRINTING;
+                self.printing_polls = PRINT_POLLS;
+            }
+            COMMAND_STATUS if self.printing_polls > 0 => {
+                self.printing_polls -= 1;
+                if self.printing_polls == 0 {
+                    self.status &= !STATUS_PRINTING;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn print(&mut self, feed_before: u8, feed_after: u8, palette: u8) {
+        // Nothing to put on paper, margins alone only feed blank paper
+        if self.buffer.is_empty() {
+            return;
+        }
+        let image = render(&self.buffer, feed_before as usize, feed_after as usize, palette);
+
+        let path = loop {
+            let path = self.output_dir.join(format!("print-{:04}.png", self.next_file));
+            self.next_file += 1;
+            if !path.exists() {
+                break path;
+            }
+        };
+
+        match write_png(&path, &image) {
+            Ok(()) => self.printed.push(path),
+            Err(e) => {
+                self.error = Some(io::Error::new(e.kind(), format!("failed to write {}: {}", path.display(), e)));
+            }
+        }
+    }
+}
+
+impl SerialDevice for Printer {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        let mut reply = 0x00;
+
+        // The checksum covers the command, compression flag, length and data
+        if matches!(
+            self.state,
+            PacketState::Command | PacketState::Compression | PacketState::LengthLo | PacketState::LengthHi | PacketState::Data
+        ) {
+            self.checksum = self.checksum.wrapping_add(byte as u16);
+        }
+
+        self.state = match self.state {
+            PacketState::Magic1 if byte == 0x88 => PacketState::Magic2,
+            PacketState::Magic1 => PacketState::Magic1,
+            PacketState::Magic2 if byte == 0x33 => PacketState::Command,
+            PacketState::Magic2 => PacketState::Magic1,
+            PacketState::Command => {
+                self.command = byte;
+                self.checksum = byte as u16;
+                self.data.clear();
+                PacketState::Compression
+            }
+            PacketState::Compression => {
+                self.compressed = byte & 1 != 0;
+                PacketState::LengthLo
+            }
+            PacketState::LengthLo => {
+                self.length = byte as u16;
+                PacketState::LengthHi
+            }
+            PacketState::LengthHi => {
+                self.length |= (byte as u16) << 8;
+                match self.length {
+                    0 => PacketState::ChecksumLo,
+                    _ => PacketState::Data,
+                }
+            }
+            PacketState::Data => {
+                self.data.push(byte);
+                match self.data.len() == self.length as usize {
+                    true => PacketState::ChecksumLo,
+                    false => PacketState::Data,
+                }
+            }
+            PacketState::ChecksumLo => {
+                self.received_checksum = byte as u16;
+                PacketState::ChecksumHi
+            }
+            PacketState::ChecksumHi => {
+                self.received_checksum |= (byte as u16) << 8;
+                PacketState::Alive
+            }
+            PacketState::Alive => {
+                reply = 0x81;
+                self.handle_command();
+                PacketState::Status
+            }
+            PacketState::Status => {
+                reply = self.status;
+                PacketState::Magic1
+            }
+        };
+
+        reply
+    }
+}
+
+// Expand the printer's run-length encoding: a control byte with the top bit set repeats
+// the next byte (n & 0x7F) + 2 times, otherwise the next n + 1 bytes are copied as-is
+pub fn decompress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let control = data[i];
+        i += 1;
+        if control & 0x80 != 0 {
+            if let Some(&byte) = data.get(i) {
+                out.extend(std::iter::repeat_n(byte, (control & 0x7F) as usize + 2));
+            }
+            i += 1;
+        } else {
+            let end = (i + control as usize + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        }
+    }
+    out
+}
+
+// Lay out the buffered tiles 20 to a row as 8-bit grayscale, with blank feeds around them
+fn render(buffer: &[u8], feed_before: usize, feed_after: usize, palette: u8) -> (usize, Vec<u8>) {
+    let tile_rows = buffer.len().div_ceil(16 * 20);
+    let image_top = feed_before * FEED_HEIGHT;
+    let height = image_top + tile_rows * 8 + feed_after * FEED_HEIGHT;
+    let mut pixels = vec![SHADES[0]; WIDTH * height];
+
+    for (tile_num, tile) in buffer.chunks_exact(16).enumerate() {
+        let (tile_x, tile_y) = ((tile_num % 20) * 8, image_top + (tile_num / 20) * 8);
+        for row in 0..8 {
+            let (b1, b2) = (tile[row * 2], tile[row * 2 + 1]);
+            for px in 0..8 {
+                let index = ((b1 >> (7 - px)) & 1) | (((b2 >> (7 - px)) & 1) << 1);
+                let shade = (palette >> (index * 2)) & 0x3;
+                pixels[(tile_y + row) * WIDTH + tile_x + px] = SHADES[shade as usize];
+            }
+        }
+    }
+
+    (height, pixels)
+}
+
+fn write_png(path: &Path, (height, pixels): &(usize, Vec<u8>)) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), WIDTH as u32, *height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(pixels).map_err(io::Error::other)
+}
@@ -0,0 +1,165 @@
+use std::{collections::VecDeque, io};
+
+use crate::gameboy::Gameboy;
+
+// History of save states captured every `interval` frames. Only the newest state is kept
+// whole, each older one is stored as the difference from the state after it, which is
+// small since most memory doesn't change in a few frames. The oldest states are dropped
+// to stay within the memory budget.
+pub struct Rewind {
+    interval: u32,
+    budget: usize,
+    // Frames run since the newest state was captured or rewound to
+    frames: u32,
+    newest: Option<Vec<u8>>,
+    // Deltas back to each older state, oldest first
+    deltas: VecDeque<Vec<u8>>,
+    delta_bytes: usize,
+}
+
+impl Rewind {
+    // Capture every `interval` frames, using at most `budget` bytes
+    pub fn new(interval: u32, budget: usize) -> Self {
+        Rewind {
+            interval: interval.max(1),
+            budget,
+            frames: 0,
+            newest: None,
+            deltas: VecDeque::new(),
+            delta_bytes: 0,
+        }
+    }
+
+    // Call after each frame is executed
+    pub fn frame(&mut self, gameboy: &Gameboy) {
+        self.frames += 1;
+        if self.newest.is_none() || self.frames >= self.interval {
+            self.capture(gameboy);
+        }
+    }
+
+    pub fn capture(&mut self, gameboy: &Gameboy) {
+        let state = gameboy.save_state();
+        if let Some(newest) = self.newest.take() {
+            let delta = encode_delta(&state, &newest);
+            self.delta_bytes += delta.len();
+            self.deltas.push_back(delta);
+        }
+        self.newest = Some(state);
+        self.frames = 0;
+
+        while self.memory_used() > self.budget {
+            match self.deltas.pop_front() {
+                Some(delta) => self.delta_bytes -= delta.len(),
+                None => break,
+            }
+        }
+    }
+
+    // Go back to the last captured state, or the one before it if the Gameboy is already
+    // there. Returns false once there's nothing older to go back to.
+    pub fn rewind(&mut self, gameboy: &mut Gameboy) -> io::Result<bool> {
+        let Some(newest) = &self.newest else {
+            return Ok(false);
+        };
+        if self.frames == 0 {
+            let Some(delta) = self.deltas.pop_back() else {
+                return Ok(false);
+            };
+            self.delta_bytes -= delta.len();
+            self.newest = Some(apply_delta(newest, &delta));
+        }
+        gameboy.load_state(self.newest.as_ref().unwrap())?;
+        self.frames = 0;
+        Ok(true)
+    }
+
+    // Number of states that can be rewound to
+    pub fn len(&self) -> usize {
+        self.newest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.newest.as_ref().map_or(0, Vec::len) + self.delta_bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.delta_bytes = 0;
+        self.frames = 0;
+    }
+}
+
+// A delta is the length of `to`, then alternating runs of bytes that are the same in
+// both and bytes XORed with `from`'s, each run prefixed with its length. Bytes past the
+// end of the shorter state count as 0.
+fn encode_delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let len = from.len().max(to.len());
+    let xor = |i: usize| from.get(i).copied().unwrap_or(0) ^ to.get(i).copied().unwrap_or(0);
+
+    let mut delta = Vec::new();
+    write_varint(&mut delta, to.len());
+    let mut i = 0;
+    while i < len {
+        let same_start = i;
+        while i < len && xor(i) == 0 {
+            i += 1;
+        }
+        let changed_start = i;
+        while i < len && xor(i) != 0 {
+            i += 1;
+        }
+        write_varint(&mut delta, changed_start - same_start);
+        write_varint(&mut delta, i - changed_start);
+        delta.extend((changed_start..i).map(xor));
+    }
+    delta
+}
+
+fn apply_delta(from: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut pos = 0;
+    let len = read_varint(delta, &mut pos);
+    let mut to = from.to_vec();
+    to.resize(from.len().max(len), 0);
+
+    let mut i = 0;
+    while pos < delta.len() {
+        i += read_varint(delta, &mut pos);
+        let changed = read_varint(delta, &mut pos);
+        for (byte, xor) in to[i..i + changed].iter_mut().zip(&delta[pos..pos + changed]) {
+            *byte ^= xor;
+        }
+        i += changed;
+        pos += changed;
+    }
+    to.truncate(len);
+    to
+}
+
+// LEB128, so short runs take a single byte
+fn write_varint(out: &mut Vec<u8>, mut val: usize) {
+    while val >= 0x80 {
+        out.push(val as u8 | 0x80);
+        val >>= 7;
+    }
+    out.push(val as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> usize {
+    let mut val = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*pos];
+        *pos += 1;
+        val |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return val;
+        }
+        shift += 7;
+    }
+}
@@ -0,0 +1,158 @@
+use std::io;
+
+// Save states start with the magic, the format version and a CRC-32 of the ROM they were
+// made with, followed by each component's state in a fixed order. Bump the version
+// whenever the layout of any component changes.
+pub const MAGIC: &[u8; 4] = b"GB7S";
+pub const VERSION: u16 = 2;
+pub const HEADER_LEN: usize = 10;
+
+// A component whose state goes into save states
+pub(crate) trait Snapshot {
+    fn save(&self, w: &mut StateWriter);
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()>;
+}
+
+#[derive(Default)]
+pub(crate) struct StateWriter {
+    pub(crate) data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn u8(&mut self, val: u8) {
+        self.data.push(val);
+    }
+
+    pub fn bool(&mut self, val: bool) {
+        self.u8(val as u8);
+    }
+
+    pub fn u16(&mut self, val: u16) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, val: u32) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, val: u64) {
+        self.data.extend_from_slice(&val.to_le_bytes());
+    }
+
+    // Bytes whose length is fixed by the component
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    // Bytes of varying length, e.g. cartridge RAM
+    pub fn vec(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes(bytes);
+    }
+}
+
+pub(crate) struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        StateReader { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(invalid("save state is truncated"));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    pub fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn bytes(&mut self, out: &mut [u8]) -> io::Result<()> {
+        out.copy_from_slice(self.take(out.len())?);
+        Ok(())
+    }
+
+    // Read bytes saved with `StateWriter::vec` into `out`, which must be the same length
+    pub fn vec_into(&mut self, out: &mut [u8]) -> io::Result<()> {
+        if self.u32()? as usize != out.len() {
+            return Err(invalid("save state doesn't match the emulated hardware"));
+        }
+        self.bytes(out)
+    }
+
+    pub fn vec(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+pub(crate) fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Check a save state's header, returning the ROM checksum it was made with
+pub fn read_header(data: &[u8]) -> io::Result<u32> {
+    if data.len() < HEADER_LEN || &data[..4] != MAGIC {
+        return Err(invalid("not a gb7 save state"));
+    }
+    let version = u16::from_le_bytes([data[4], data[5]]);
+    if version != VERSION {
+        return Err(invalid(&format!(
+            "save state is version {}, expected {}",
+            version, VERSION
+        )));
+    }
+    Ok(u32::from_le_bytes(data[6..10].try_into().unwrap()))
+}
+
+// CRC-32 as used by zip and No-Intro ROM sets
+pub fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = match crc & 1 {
+                    1 => (crc >> 1) ^ 0xEDB8_8320,
+                    _ => crc >> 1,
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    !data
+        .iter()
+        .fold(!0, |crc, &byte| (crc >> 8) ^ TABLE[((crc ^ byte as u32) & 0xFF) as usize])
+}
@@ -0,0 +1,133 @@
+use std::{any::Any, io};
+
+use crate::{
+    memory::IORegs,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+// The internal clock shifts one bit every 512 T-cycles (8192 Hz)
+const M_CYCLES_PER_BIT: u16 = 128;
+
+// Whatever is plugged into the link port. Transfers are done a byte at a time: when the
+// Gameboy clocks a transfer itself, the device receives the byte shifted out of SB once
+// all 8 bits have been sent, and returns the byte that was shifted in.
+pub trait SerialDevice: Any {
+    fn exchange(&mut self, byte: u8) -> u8;
+}
+
+// Nothing connected, the data line floats high
+#[derive(Default)]
+pub struct Disconnected;
+
+impl SerialDevice for Disconnected {
+    fn exchange(&mut self, _byte: u8) -> u8 {
+        0xFF
+    }
+}
+
+// Records every byte sent, e.g. the text output of test ROMs
+#[derive(Default)]
+pub struct CaptureBuffer {
+    pub data: Vec<u8>,
+}
+
+impl SerialDevice for CaptureBuffer {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        self.data.push(byte);
+        0xFF
+    }
+}
+
+pub struct Serial {
+    device: Box<dyn SerialDevice>,
+    // M-cycles left in a transfer on the internal clock
+    transfer_cycles: Option<u16>,
+}
+
+impl Default for Serial {
+    fn default() -> Self {
+        Serial {
+            device: Box::new(Disconnected),
+            transfer_cycles: None,
+        }
+    }
+}
+
+impl Serial {
+    pub fn connect(&mut self, device: Box<dyn SerialDevice>) {
+        self.device = device;
+    }
+
+    pub fn disconnect(&mut self) -> Box<dyn SerialDevice> {
+        std::mem::replace(&mut self.device, Box::new(Disconnected))
+    }
+
+    // The connected device, if it is a `T`
+    pub fn device<T: SerialDevice>(&self) -> Option<&T> {
+        (self.device.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    pub fn device_mut<T: SerialDevice>(&mut self) -> Option<&mut T> {
+        (self.device.as_mut() as &mut dyn Any).downcast_mut()
+    }
+
+    // M-cycles until the current transfer on the internal clock completes
+    pub fn transfer_remaining(&self) -> Option<u16> {
+        self.transfer_cycles
+    }
+
+    pub fn tick(&mut self, io_regs: &mut IORegs, m_cycles: u8) {
+        let sc = io_regs.read(0xFF02);
+
+        // Only transfers started with the internal clock are timed here
+        if sc & 0b1000_0001 != 0b1000_0001 {
+            self.transfer_cycles = None;
+            return;
+        }
+
+        let remaining = self.transfer_cycles.unwrap_or(M_CYCLES_PER_BIT * 8);
+        if remaining > m_cycles as u16 {
+            self.transfer_cycles = Some(remaining - m_cycles as u16);
+            return;
+        }
+
+        let received = self.device.exchange(io_regs.read(0xFF01));
+        Serial::complete_transfer(io_regs, received);
+        self.transfer_cycles = None;
+    }
+
+    // Shift in a byte clocked by the other end of the cable. If a transfer on the
+    // external clock is waiting, this completes it and returns the byte that was in SB,
+    // otherwise the other end reads 0xFF.
+    pub fn receive(&mut self, io_regs: &mut IORegs, byte: u8) -> u8 {
+        if io_regs.read(0xFF02) & 0b1000_0001 != 0b1000_0000 {
+            return 0xFF;
+        }
+
+        let sent = io_regs.read(0xFF01);
+        Serial::complete_transfer(io_regs, byte);
+        sent
+    }
+
+    fn complete_transfer(io_regs: &mut IORegs, received: u8) {
+        // Store the received byte, clear the transfer flag and request the serial interrupt
+        io_regs.write(0xFF01, received);
+        io_regs.write(0xFF02, io_regs.read(0xFF02) & 0b0111_1111);
+        io_regs.write(0xFF0F, io_regs.read(0xFF0F) | 0b0_1000);
+    }
+}
+
+// The connected device isn't part of the Gameboy and isn't saved
+impl Snapshot for Serial {
+    fn save(&self, w: &mut StateWriter) {
+        w.bool(self.transfer_cycles.is_some());
+        w.u16(self.transfer_cycles.unwrap_or(0));
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        let transferring = r.bool()?;
+        let cycles = r.u16()?;
+        self.transfer_cycles = transferring.then_some(cycles);
+        Ok(())
+    }
+}
@@ -0,0 +1,643 @@
+use std::io;
+
+use crate::{
+    cartridge::{CartMemory, Cartridge},
+    lcd::{Lcd, LCD_HEIGHT, LCD_WIDTH},
+    memory::{IORegs, VideoMem, VideoRam},
+    postprocess::PostProcess,
+    savestate::{self, Snapshot, StateReader, StateWriter},
+};
+
+pub const SGB_WIDTH: usize = 256;
+pub const SGB_HEIGHT: usize = 224;
+
+// Position of the Gameboy screen inside the SGB frame
+const SCREEN_X: usize = 48;
+const SCREEN_Y: usize = 40;
+
+// Colours used by the SGB before any palette commands arrive (RGB555)
+const DEFAULT_PALETTE: [u16; 4] = [0x67BF, 0x265B, 0x10B5, 0x2866];
+
+// A game opts into SGB features with 0x03 at 0x0146 and the old licensee code 0x33
+pub fn supports_sgb(cartridge: &Cartridge) -> bool {
+    cartridge.read(0x0146) == 0x03 && cartridge.read(0x014B) == 0x33
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mask {
+    Off,
+    Freeze,
+    Black,
+    Color0,
+}
+
+#[derive(Clone, Copy)]
+enum Transfer {
+    Palettes,
+    Attributes,
+    BorderTiles(bool),
+    BorderMap,
+}
+
+pub struct Sgb {
+    commands_enabled: bool,
+
+    // Packet reception over the JOYP register
+    receiving: bool,
+    packet_bit: usize,
+    packet: [u8; 16],
+    command: Vec<u8>,
+    last_select: u8,
+    packet_ending: bool,
+
+    // MLT_REQ multiplayer state
+    player_count: u8,
+    current_player: u8,
+
+    // Colour state: the four active palettes, the 512 system palettes from PAL_TRN,
+    // a palette number for each of the 20x18 screen tiles and the 45 attribute files
+    palettes: [[u16; 4]; 4],
+    system_palettes: Vec<[u16; 4]>,
+    attributes: [u8; 20 * 18],
+    attribute_files: Vec<[u8; 20 * 18]>,
+    mask: Mask,
+
+    // Border state from CHR_TRN and PCT_TRN
+    border_tiles: Vec<u8>,
+    border_map: [u16; 32 * 28],
+    border_palettes: [[u16; 16]; 4],
+
+    pending_transfer: Option<Transfer>,
+    last_ly: u8,
+    // The composed frame, RGBA
+    image: Vec<u8>,
+    // The last composed frame after post-processing, which the SGB applies itself as the
+    // LCD's image isn't used. The layer tint is lost with it.
+    output: Vec<u8>,
+    post_process: PostProcess,
+}
+
+impl Sgb {
+    pub fn new(commands_enabled: bool) -> Self {
+        let mut sgb = Sgb {
+            commands_enabled,
+            receiving: false,
+            packet_bit: 0,
+            packet: [0; 16],
+            command: Vec::new(),
+            last_select: 0x30,
+            packet_ending: false,
+            player_count: 1,
+            current_player: 0,
+            palettes: [DEFAULT_PALETTE; 4],
+            system_palettes: vec![[0; 4]; 512],
+            attributes: [0; 20 * 18],
+            attribute_files: vec![[0; 20 * 18]; 45],
+            mask: Mask::Off,
+            border_tiles: vec![0; 256 * 32],
+            border_map: [0; 32 * 28],
+            border_palettes: [[0; 16]; 4],
+            pending_transfer: None,
+            last_ly: 0,
+            image: vec![0; SGB_WIDTH * SGB_HEIGHT * 4],
+            output: Vec::new(),
+            post_process: PostProcess::default(),
+        };
+        sgb.fill_frame(sgb.palettes[0][0]);
+        sgb.output = sgb.image.clone();
+        sgb
+    }
+
+    // The most recent composited image as tightly packed RGBA rows, 256x224
+    pub fn frame(&self) -> &[u8] {
+        &self.output
+    }
+
+    pub fn post_process(&self) -> &PostProcess {
+        &self.post_process
+    }
+
+    pub fn set_post_process(&mut self, post_process: PostProcess) {
+        self.post_process = post_process;
+        self.refresh_output();
+    }
+
+    fn refresh_output(&mut self) {
+        if self.post_process.is_enabled() {
+            self.post_process.render(&self.image, &mut self.output);
+        } else {
+            self.output.copy_from_slice(&self.image);
+        }
+    }
+
+    pub fn write_joyp(&mut self, val: u8) {
+        let select = val & 0x30;
+
+        match select {
+            // Both lines low: reset pulse, starts a new packet
+            0x00 => {
+                self.receiving = true;
+                self.packet_bit = 0;
+                self.packet = [0; 16];
+            }
+            // P15 low sends a 1, P14 low sends a 0. Bits are only counted when the
+            // lines were released in between.
+            0x10 | 0x20 if self.receiving && self.last_select == 0x30 => {
+                let bit = (select == 0x10) as u8;
+                if self.packet_bit < 128 {
+                    self.packet[self.packet_bit / 8] |= bit << (self.packet_bit % 8);
+                    self.packet_bit += 1;
+                } else {
+                    // 129th bit is the stop bit, which must be a 0
+                    self.receiving = false;
+                    self.packet_ending = true;
+                    if bit == 0 {
+                        self.receive_packet();
+                    }
+                }
+            }
+            // Releasing both lines advances the selected joypad in multiplayer mode,
+            // except for the release that completes a packet
+            0x30 if self.last_select != 0x30 && !self.receiving => {
+                if self.player_count > 1 && !self.packet_ending {
+                    self.current_player = (self.current_player + 1) % self.player_count;
+                }
+                self.packet_ending = false;
+            }
+            _ => (),
+        }
+
+        self.last_select = select;
+    }
+
+    // Override JOYP with the multiplayer joypad ID or inputs for joypads 2-4
+    pub fn apply_joyp(&self, io_regs: &mut IORegs) {
+        if self.player_count == 1 {
+            return;
+        }
+
+        let joyp = io_regs.read(0xFF00);
+        if joyp & 0x30 == 0x30 {
+            io_regs.write(0xFF00, (joyp & 0xF0) | (0x0F - self.current_player));
+        } else if self.current_player != 0 {
+            // Only the first joypad is connected
+            io_regs.write(0xFF00, joyp | 0x0F);
+        }
+    }
+
+    pub fn tick(&mut self, vram: &VideoRam, io_regs: &IORegs, lcd: &Lcd) {
+        // Watch for the start of VBlank to run VRAM transfers and compose the frame
+        let ly = io_regs.read(0xFF44);
+        if ly == 144 && self.last_ly != 144 {
+            if let Some(transfer) = self.pending_transfer.take() {
+                self.do_transfer(transfer, &Sgb::read_transfer_data(vram, io_regs));
+            }
+            self.compose_frame(lcd);
+            if self.post_process.is_enabled() {
+                self.post_process.process(&self.image, &mut self.output);
+            } else {
+                self.output.copy_from_slice(&self.image);
+            }
+        }
+        self.last_ly = ly;
+    }
+
+    fn receive_packet(&mut self) {
+        if self.command.is_empty() {
+            // First packet of a command: the low 3 bits give the number of packets
+            if self.packet[0] & 0x07 == 0 {
+                return;
+            }
+        }
+        self.command.extend_from_slice(&self.packet);
+
+        let length = (self.command[0] & 0x07) as usize;
+        if self.command.len() >= length * 16 {
+            let command = std::mem::take(&mut self.command);
+            if self.commands_enabled {
+                self.run_command(&command);
+            }
+        }
+    }
+
+    fn run_command(&mut self, data: &[u8]) {
+        match data[0] >> 3 {
+            0x00 => self.set_palette_pair(0, 1, data),
+            0x01 => self.set_palette_pair(2, 3, data),
+            0x02 => self.set_palette_pair(0, 3, data),
+            0x03 => self.set_palette_pair(1, 2, data),
+            0x04 => self.attr_blk(data),
+            0x05 => self.attr_lin(data),
+            0x06 => self.attr_div(data),
+            0x07 => self.attr_chr(data),
+            0x0A => self.pal_set(data),
+            0x0B => self.pending_transfer = Some(Transfer::Palettes),
+            0x11 => {
+                self.player_count = match data[1] & 0x03 {
+                    1 => 2,
+                    3 => 4,
+                    _ => 1,
+                };
+                self.current_player = 0;
+            }
+            0x13 => self.pending_transfer = Some(Transfer::BorderTiles(data[1] & 0x01 != 0)),
+            0x14 => self.pending_transfer = Some(Transfer::BorderMap),
+            0x15 => self.pending_transfer = Some(Transfer::Attributes),
+            0x16 => {
+                self.attributes = self.attribute_files[(data[1] & 0x3F) as usize % 45];
+                if data[1] & 0x40 != 0 {
+                    self.mask = Mask::Off;
+                }
+            }
+            0x17 => {
+                self.mask = match data[1] & 0x03 {
+                    0 => Mask::Off,
+                    1 => Mask::Freeze,
+                    2 => Mask::Black,
+                    _ => Mask::Color0,
+                }
+            }
+            // Sound, SNES program and other commands are ignored
+            _ => (),
+        }
+    }
+
+    fn set_palette_pair(&mut self, first: usize, second: usize, data: &[u8]) {
+        let color = |i: usize| u16::from_le_bytes([data[1 + i * 2], data[2 + i * 2]]);
+
+        // Colour 0 is shared between all palettes
+        for palette in self.palettes.iter_mut() {
+            palette[0] = color(0);
+        }
+        for i in 1..4 {
+            self.palettes[first][i] = color(i);
+            self.palettes[second][i] = color(i + 3);
+        }
+    }
+
+    fn attr_blk(&mut self, data: &[u8]) {
+        let count = (data[1] & 0x1F) as usize;
+
+        for set in data[2..].chunks_exact(6).take(count) {
+            let mut control = set[0] & 0x07;
+            let inside = set[1] & 0x03;
+            let mut border = (set[1] >> 2) & 0x03;
+            let outside = (set[1] >> 4) & 0x03;
+            let (x1, y1, x2, y2) = (set[2] & 0x1F, set[3] & 0x1F, set[4] & 0x1F, set[5] & 0x1F);
+
+            // Colouring only the inside or only the outside also colours the border
+            if control == 0b001 {
+                control = 0b011;
+                border = inside;
+            } else if control == 0b100 {
+                control = 0b110;
+                border = outside;
+            }
+
+            for y in 0..18 {
+                for x in 0..20 {
+                    let in_box = x >= x1 && x <= x2 && y >= y1 && y <= y2;
+                    let on_border = in_box && (x == x1 || x == x2 || y == y1 || y == y2);
+
+                    let palette = if on_border {
+                        (control & 0b010 != 0).then_some(border)
+                    } else if in_box {
+                        (control & 0b001 != 0).then_some(inside)
+                    } else {
+                        (control & 0b100 != 0).then_some(outside)
+                    };
+
+                    if let Some(palette) = palette {
+                        self.attributes[y as usize * 20 + x as usize] = palette;
+                    }
+                }
+            }
+        }
+    }
+
+    fn attr_lin(&mut self, data: &[u8]) {
+        let count = data[1] as usize;
+
+        for &line in data[2..].iter().take(count) {
+            let number = (line & 0x1F) as usize;
+            let palette = (line >> 5) & 0x03;
+
+            if line & 0x80 != 0 {
+                // Horizontal line: a row of tiles
+                if number < 18 {
+                    self.attributes[number * 20..(number + 1) * 20].fill(palette);
+                }
+            } else if number < 20 {
+                // Vertical line: a column of tiles
+                for y in 0..18 {
+                    self.attributes[y * 20 + number] = palette;
+                }
+            }
+        }
+    }
+
+    fn attr_div(&mut self, data: &[u8]) {
+        let after = data[1] & 0x03;
+        let before = (data[1] >> 2) & 0x03;
+        let on_line = (data[1] >> 4) & 0x03;
+        let horizontal = data[1] & 0x40 != 0;
+        let coordinate = (data[2] & 0x1F) as usize;
+
+        for y in 0..18 {
+            for x in 0..20 {
+                let position = if horizontal { y } else { x };
+                self.attributes[y * 20 + x] = match position.cmp(&coordinate) {
+                    std::cmp::Ordering::Less => before,
+                    std::cmp::Ordering::Equal => on_line,
+                    std::cmp::Ordering::Greater => after,
+                };
+            }
+        }
+    }
+
+    fn attr_chr(&mut self, data: &[u8]) {
+        let (mut x, mut y) = ((data[1] as usize).min(19), (data[2] as usize).min(17));
+        let count = (u16::from_le_bytes([data[3], data[4]]) as usize).min(360);
+        let vertical = data[5] & 0x01 != 0;
+
+        for i in 0..count {
+            let Some(&byte) = data.get(6 + i / 4) else {
+                break;
+            };
+            self.attributes[y * 20 + x] = (byte >> (6 - (i % 4) * 2)) & 0x03;
+
+            if vertical {
+                y += 1;
+                if y == 18 {
+                    y = 0;
+                    x = (x + 1) % 20;
+                }
+            } else {
+                x += 1;
+                if x == 20 {
+                    x = 0;
+                    y = (y + 1) % 18;
+                }
+            }
+        }
+    }
+
+    fn pal_set(&mut self, data: &[u8]) {
+        for i in 0..4 {
+            let number = u16::from_le_bytes([data[1 + i * 2], data[2 + i * 2]]) as usize;
+            self.palettes[i] = self.system_palettes[number % 512];
+        }
+
+        // Colour 0 of the first palette is shared between all palettes
+        for i in 1..4 {
+            self.palettes[i][0] = self.palettes[0][0];
+        }
+
+        if data[9] & 0x80 != 0 {
+            self.attributes = self.attribute_files[(data[9] & 0x3F) as usize % 45];
+        }
+        if data[9] & 0x40 != 0 {
+            self.mask = Mask::Off;
+        }
+    }
+
+    fn do_transfer(&mut self, transfer: Transfer, data: &[u8; 4096]) {
+        match transfer {
+            Transfer::Palettes => {
+                for (palette, colors) in self.system_palettes.iter_mut().zip(data.chunks_exact(8)) {
+                    for i in 0..4 {
+                        palette[i] = u16::from_le_bytes([colors[i * 2], colors[i * 2 + 1]]);
+                    }
+                }
+            }
+            Transfer::Attributes => {
+                // 45 files of 90 bytes, each packing four 2-bit palette numbers per byte
+                for (file, packed) in self.attribute_files.iter_mut().zip(data.chunks_exact(90)) {
+                    for (i, attribute) in file.iter_mut().enumerate() {
+                        *attribute = (packed[i / 4] >> (6 - (i % 4) * 2)) & 0x03;
+                    }
+                }
+            }
+            Transfer::BorderTiles(upper) => {
+                let offset = if upper { 128 * 32 } else { 0 };
+                self.border_tiles[offset..offset + 4096].copy_from_slice(data);
+            }
+            Transfer::BorderMap => {
+                for (entry, bytes) in self.border_map.iter_mut().zip(data.chunks_exact(2)) {
+                    *entry = u16::from_le_bytes([bytes[0], bytes[1]]);
+                }
+                for (palette, colors) in self
+                    .border_palettes
+                    .iter_mut()
+                    .zip(data[0x800..0x880].chunks_exact(32))
+                {
+                    for i in 0..16 {
+                        palette[i] = u16::from_le_bytes([colors[i * 2], colors[i * 2 + 1]]);
+                    }
+                }
+            }
+        }
+    }
+
+    // VRAM transfers send the 4 KiB of tile data shown by the first 256 background
+    // tiles on screen, read left to right, top to bottom.
+    fn read_transfer_data(vram: &VideoRam, io_regs: &IORegs) -> [u8; 4096] {
+        let lcdc = io_regs.read(0xFF40);
+        let tile_mode_8000 = (lcdc & 0b0001_0000) != 0;
+        let bg_tilemap: u16 = match lcdc & 0b0000_1000 {
+            0 => 0x9800,
+            _ => 0x9C00,
+        };
+
+        let mut data = [0; 4096];
+        for (i, tile) in data.chunks_exact_mut(16).enumerate() {
+            let tile_num = vram.read(bg_tilemap + (i as u16 / 20) * 32 + (i as u16 % 20));
+            let tile_addr = match tile_mode_8000 {
+                true => 0x8000 + (tile_num as u16) * 16,
+                false => 0x8800 + ((tile_num as i8 as i16 + 128) as u16) * 16,
+            };
+            for (offset, byte) in tile.iter_mut().enumerate() {
+                *byte = vram.read(tile_addr + offset as u16);
+            }
+        }
+        data
+    }
+
+    fn compose_frame(&mut self, lcd: &Lcd) {
+        let backdrop = self.palettes[0][0];
+
+        match self.mask {
+            Mask::Freeze => return,
+            Mask::Black => self.fill_screen(0x0000),
+            Mask::Color0 => self.fill_screen(backdrop),
+            Mask::Off => {
+                for y in 0..LCD_HEIGHT {
+                    for x in 0..LCD_WIDTH {
+                        let shade = lcd.pixels[y * LCD_WIDTH + x] as usize;
+                        let palette = self.attributes[(y / 8) * 20 + x / 8] as usize;
+                        let color = if shade == 0 {
+                            backdrop
+                        } else {
+                            self.palettes[palette][shade]
+                        };
+                        self.set_pixel(SCREEN_X + x, SCREEN_Y + y, color);
+                    }
+                }
+            }
+        }
+
+        self.draw_border(backdrop);
+    }
+
+    fn draw_border(&mut self, backdrop: u16) {
+        for map_y in 0..28 {
+            for map_x in 0..32 {
+                let entry = self.border_map[map_y * 32 + map_x];
+                let tile = (entry & 0xFF) as usize * 32;
+                let palette = ((entry >> 10) & 0x07).saturating_sub(4) as usize;
+                let xflip = entry & 0x4000 != 0;
+                let yflip = entry & 0x8000 != 0;
+
+                for row in 0..8 {
+                    let tile_row = if yflip { 7 - row } else { row };
+                    let planes = [
+                        self.border_tiles[tile + tile_row * 2],
+                        self.border_tiles[tile + tile_row * 2 + 1],
+                        self.border_tiles[tile + 16 + tile_row * 2],
+                        self.border_tiles[tile + 16 + tile_row * 2 + 1],
+                    ];
+
+                    for col in 0..8 {
+                        let bit = if xflip { col } else { 7 - col };
+                        let index = planes
+                            .iter()
+                            .enumerate()
+                            .fold(0, |acc, (plane, byte)| acc | (((byte >> bit) & 1) << plane));
+
+                        let (x, y) = (map_x * 8 + col, map_y * 8 + row);
+                        let in_screen = (SCREEN_X..SCREEN_X + LCD_WIDTH).contains(&x)
+                            && (SCREEN_Y..SCREEN_Y + LCD_HEIGHT).contains(&y);
+
+                        // Colour 0 is transparent, showing the game screen or the backdrop
+                        if index != 0 {
+                            self.set_pixel(x, y, self.border_palettes[palette][index as usize]);
+                        } else if !in_screen {
+                            self.set_pixel(x, y, backdrop);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn fill_screen(&mut self, color: u16) {
+        for y in 0..LCD_HEIGHT {
+            for x in 0..LCD_WIDTH {
+                self.set_pixel(SCREEN_X + x, SCREEN_Y + y, color);
+            }
+        }
+    }
+
+    fn fill_frame(&mut self, color: u16) {
+        for y in 0..SGB_HEIGHT {
+            for x in 0..SGB_WIDTH {
+                self.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: u16) {
+        let i = (y * SGB_WIDTH + x) * 4;
+        self.image[i..i + 4].copy_from_slice(&rgb555_to_rgba(color));
+    }
+}
+
+fn rgb555_to_rgba(color: u16) -> [u8; 4] {
+    let expand = |c: u16| ((c << 3) | (c >> 2)) as u8;
+    [
+        expand(color & 0x1F),
+        expand((color >> 5) & 0x1F),
+        expand((color >> 10) & 0x1F),
+        255,
+    ]
+}
+
+// Whether SGB commands are enabled is a setting and isn't saved
+impl Snapshot for Sgb {
+    fn save(&self, w: &mut StateWriter) {
+        w.bool(self.receiving);
+        w.u8(self.packet_bit as u8);
+        w.bytes(&self.packet);
+        w.vec(&self.command);
+        w.u8(self.last_select);
+        w.bool(self.packet_ending);
+        w.u8(self.player_count);
+        w.u8(self.current_player);
+        self.palettes.iter().flatten().for_each(|&color| w.u16(color));
+        self.system_palettes.iter().flatten().for_each(|&color| w.u16(color));
+        w.bytes(&self.attributes);
+        self.attribute_files.iter().for_each(|file| w.bytes(file));
+        w.u8(self.mask as u8);
+        w.bytes(&self.border_tiles);
+        self.border_map.iter().for_each(|&entry| w.u16(entry));
+        self.border_palettes.iter().flatten().for_each(|&color| w.u16(color));
+        w.u8(match self.pending_transfer {
+            None => 0,
+            Some(Transfer::Palettes) => 1,
+            Some(Transfer::Attributes) => 2,
+            Some(Transfer::BorderTiles(false)) => 3,
+            Some(Transfer::BorderTiles(true)) => 4,
+            Some(Transfer::BorderMap) => 5,
+        });
+        w.u8(self.last_ly);
+        w.bytes(&self.image);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.receiving = r.bool()?;
+        self.packet_bit = (r.u8()? as usize).min(128);
+        r.bytes(&mut self.packet)?;
+        self.command = r.vec()?;
+        self.last_select = r.u8()?;
+        self.packet_ending = r.bool()?;
+        self.player_count = r.u8()?.max(1);
+        self.current_player = r.u8()? % self.player_count;
+        for color in self.palettes.iter_mut().flatten() {
+            *color = r.u16()?;
+        }
+        for color in self.system_palettes.iter_mut().flatten() {
+            *color = r.u16()?;
+        }
+        r.bytes(&mut self.attributes)?;
+        for file in self.attribute_files.iter_mut() {
+            r.bytes(file)?;
+        }
+        self.mask = match r.u8()? {
+            0 => Mask::Off,
+            1 => Mask::Freeze,
+            2 => Mask::Black,
+            3 => Mask::Color0,
+            _ => return Err(savestate::invalid("invalid SGB mask in save state")),
+        };
+        r.bytes(&mut self.border_tiles)?;
+        for entry in self.border_map.iter_mut() {
+            *entry = r.u16()?;
+        }
+        for color in self.border_palettes.iter_mut().flatten() {
+            *color = r.u16()?;
+        }
+        self.pending_transfer = match r.u8()? {
+            0 => None,
+            1 => Some(Transfer::Palettes),
+            2 => Some(Transfer::Attributes),
+            3 => Some(Transfer::BorderTiles(false)),
+            4 => Some(Transfer::BorderTiles(true)),
+            5 => Some(Transfer::BorderMap),
+            _ => return Err(savestate::invalid("invalid SGB transfer in save state")),
+        };
+        self.last_ly = r.u8()?;
+        r.bytes(&mut self.image)?;
+        self.refresh_output();
+        Ok(())
+    }
+}
@@ -1,53 +1,132 @@
-use crate::memory::IORegs;
+use std::io;
 
+use crate::{
+    memory::IORegs,
+    savestate::{Snapshot, StateReader, StateWriter},
+};
+
+// DIV, TIMA, TMA and TAC, all driven by the 16-bit system counter. DIV is the upper
+// byte of the counter, and TIMA increments on a falling edge of the counter bit
+// selected by TAC (ANDed with the timer enable bit).
 #[derive(Default)]
 pub struct Timers {
-    div_partial: u8,
-    tima_partial: u16,
+    system_counter: u16,
+    // TIMA overflowed on the previous M-cycle and reads as 0 until it is reloaded
+    reload_pending: bool,
+    // TIMA was reloaded from TMA on the last M-cycle. TIMA writes are ignored and TMA
+    // writes also go to TIMA until the next M-cycle.
+    reloading: bool,
 }
 
 impl Timers {
     pub fn tick(&mut self, io_regs: &mut IORegs, m_cycles: u8) {
         // Given an amount of m-cycles, do timer-related tasks and request interrupts
-        let t_cycles = m_cycles * 4;
+        for _ in 0..m_cycles {
+            self.reloading = false;
+
+            // Reload TIMA from TMA and request the interrupt one M-cycle after overflow
+            if self.reload_pending {
+                self.reload_pending = false;
+                self.reloading = true;
+                io_regs.write(0xFF05, io_regs.read(0xFF06));
+                io_regs.write(0xFF0F, io_regs.read(0xFF0F) | 0b00100);
+            }
 
-        // Do DIV register
-        let (res, inc_div) = self.div_partial.overflowing_add(t_cycles);
-        self.div_partial = res;
-        if inc_div {
-            io_regs.write(0xFF04, io_regs.read(0xFF04).wrapping_add(1));
+            let tac = io_regs.read(0xFF07);
+            let old_counter = self.system_counter;
+            self.system_counter = self.system_counter.wrapping_add(4);
+            self.detect_edge(io_regs, old_counter, tac, self.system_counter, tac);
         }
+        io_regs.write(0xFF04, self.div());
+    }
 
-        // Do TIMA register
-        let tac = io_regs.read(0xFF07);
-        // Check if the timer is enabled:
-        if tac & 0b100 != 0 {
-            // DO partial timer ticks according to CPU progress
-            self.tima_partial += t_cycles as u16;
-            let timer_step = match tac & 0b011 {
-                0b00 => 1024,
-                0b01 => 16,
-                0b10 => 64,
-                0b11 => 256,
-                _ => unreachable!(),
-            };
-
-            // Check partial tick progress compared to threshold
-            while self.tima_partial > timer_step {
-                // Increment TIMA register, throw interrupt if wrapping
-                let prev_tima = io_regs.read(0xFF05);
-                let (new_tima, overflow) = prev_tima.overflowing_add(1);
-                // If TIMA overflowed, reset it to TMA and throw interrupt
-                if overflow {
-                    let tma = io_regs.read(0xFF06);
-                    io_regs.write(0xFF05, tma);
-                    io_regs.write(0xFF0F, io_regs.read(0xFF0F) | 0b00100);
-                } else {
-                    io_regs.write(0xFF05, new_tima);
-                }
+    pub fn div(&self) -> u8 {
+        (self.system_counter >> 8) as u8
+    }
+
+    pub fn system_counter(&self) -> u16 {
+        self.system_counter
+    }
 
-                self.tima_partial -= timer_step;
+    // Handle a CPU write to one of the timer registers, 0xFF04-0xFF07
+    pub fn write(&mut self, io_regs: &mut IORegs, addr: u16, val: u8) {
+        match addr {
+            0xFF04 => {
+                // Any write resets the whole counter, which can itself cause a falling edge
+                let tac = io_regs.read(0xFF07);
+                let old_counter = self.system_counter;
+                self.system_counter = 0;
+                self.detect_edge(io_regs, old_counter, tac, 0, tac);
+                io_regs.write(0xFF04, 0);
+            }
+            0xFF05 => {
+                // Writing during the overflow delay cancels the reload, writing on the
+                // reload cycle itself is ignored
+                if !self.reloading {
+                    self.reload_pending = false;
+                    io_regs.write(0xFF05, val);
+                }
             }
+            0xFF06 => {
+                io_regs.write(0xFF06, val);
+                if self.reloading {
+                    io_regs.write(0xFF05, val);
+                }
+            }
+            0xFF07 => {
+                // Changing the selected bit or disabling the timer while the old bit is
+                // high looks like a falling edge and increments TIMA
+                let old_tac = io_regs.read(0xFF07);
+                io_regs.write(0xFF07, val);
+                let counter = self.system_counter;
+                self.detect_edge(io_regs, counter, old_tac, counter, val);
+            }
+            _ => unreachable!("Invalid timer register: {:#06X}", addr),
         }
     }
+
+    fn detect_edge(&mut self, io_regs: &mut IORegs, old_counter: u16, old_tac: u8, new_counter: u16, new_tac: u8) {
+        if Timers::timer_bit(old_counter, old_tac) && !Timers::timer_bit(new_counter, new_tac) {
+            self.increment_tima(io_regs);
+        }
+    }
+
+    fn timer_bit(counter: u16, tac: u8) -> bool {
+        // Check if the timer is enabled
+        if tac & 0b100 == 0 {
+            return false;
+        }
+        let bit = match tac & 0b011 {
+            0b00 => 9, // 4096 Hz
+            0b01 => 3, // 262144 Hz
+            0b10 => 5, // 65536 Hz
+            0b11 => 7, // 16384 Hz
+            _ => unreachable!(),
+        };
+        counter & (1 << bit) != 0
+    }
+
+    fn increment_tima(&mut self, io_regs: &mut IORegs) {
+        // Increment TIMA register, delaying the reload and interrupt if wrapping
+        let (new_tima, overflow) = io_regs.read(0xFF05).overflowing_add(1);
+        io_regs.write(0xFF05, new_tima);
+        if overflow {
+            self.reload_pending = true;
+        }
+    }
+}
+
+impl Snapshot for Timers {
+    fn save(&self, w: &mut StateWriter) {
+        w.u16(self.system_counter);
+        w.bool(self.reload_pending);
+        w.bool(self.reloading);
+    }
+
+    fn load(&mut self, r: &mut StateReader) -> io::Result<()> {
+        self.system_counter = r.u16()?;
+        self.reload_pending = r.bool()?;
+        self.reloading = r.bool()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,194 @@
+use std::{
+    any::Any,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    cpu::CpuFlags,
+    disasm::{self, Syntax},
+    gameboy::{Gameboy, CYCLES_PER_FRAME},
+};
+
+// Hook for watching execution, installed as `Gameboy::tracer`
+pub trait Tracer: Any {
+    // Called with the Gameboy about to execute the instruction at PC. Interrupt dispatch
+    // and M-cycles spent halted aren't traced.
+    fn trace(&mut self, gameboy: &Gameboy);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceFormat {
+    // `A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,37,06`, as
+    // compared by gameboy-doctor. Its logs are made with LY stubbed to 0x90, see
+    // `Gameboy::stub_ly`.
+    #[default]
+    Doctor,
+    // The ROM bank and address, disassembly, registers, flags, LY, IME and the M-cycles
+    // executed so far
+    Rich,
+}
+
+pub fn trace_line(gameboy: &Gameboy, format: TraceFormat) -> String {
+    let cpu = &gameboy.cpu;
+    let r = &cpu.registers;
+    match format {
+        TraceFormat::Doctor => {
+            let pcmem = [0, 1, 2, 3].map(|i| gameboy.peek(cpu.pc.wrapping_add(i)));
+            format!(
+                "A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+                r.a, r.flags.bits(), r.b, r.c, r.d, r.e, r.h, r.l, cpu.sp, cpu.pc,
+                pcmem[0], pcmem[1], pcmem[2], pcmem[3]
+            )
+        }
+        TraceFormat::Rich => {
+            let instruction = disasm::disassemble(gameboy, cpu.pc, Syntax::Conventional);
+            let flag = |flag, c| if r.flags.contains(flag) { c } else { '-' };
+            format!(
+                "{:02X}:{:04X}  {:<18} A:{:02X} F:{}{}{}{} BC:{:04X} DE:{:04X} HL:{:04X} SP:{:04X} LY:{:02X} IME:{} CYC:{}",
+                gameboy.rom_bank(cpu.pc).unwrap_or(0), cpu.pc, instruction.text,
+                r.a, flag(CpuFlags::Z, 'Z'), flag(CpuFlags::N, 'N'), flag(CpuFlags::H, 'H'), flag(CpuFlags::C, 'C'),
+                r.bc(), r.de(), r.hl(), cpu.sp, gameboy.peek(0xFF44), cpu.ime as u8, gameboy.cycles()
+            )
+        }
+    }
+}
+
+// Writes a line per instruction, e.g. to a file. Writing stops at the first error, which
+// is kept for the caller to report.
+pub struct TraceLogger {
+    writer: Box<dyn Write>,
+    format: TraceFormat,
+    error: Option<io::Error>,
+}
+
+impl TraceLogger {
+    pub fn new(writer: impl Write + 'static, format: TraceFormat) -> Self {
+        TraceLogger {
+            writer: Box::new(writer),
+            format,
+            error: None,
+        }
+    }
+
+    pub fn create(path: &Path, format: TraceFormat) -> io::Result<Self> {
+        Ok(TraceLogger::new(BufWriter::new(File::create(path)?), format))
+    }
+
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    // Flush the log, returning the first error writing it
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.error.is_none() {
+            self.error = self.writer.flush().err();
+        }
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Tracer for TraceLogger {
+    fn trace(&mut self, gameboy: &Gameboy) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(e) = writeln!(self.writer, "{}", trace_line(gameboy, self.format)) {
+            self.error = Some(e);
+        }
+    }
+}
+
+// Keeps the lines in memory
+pub struct TraceBuffer {
+    pub lines: Vec<String>,
+    format: TraceFormat,
+}
+
+impl TraceBuffer {
+    pub fn new(format: TraceFormat) -> Self {
+        TraceBuffer {
+            lines: Vec::new(),
+            format,
+        }
+    }
+}
+
+impl Tracer for TraceBuffer {
+    fn trace(&mut self, gameboy: &Gameboy) {
+        self.lines.push(trace_line(gameboy, self.format));
+    }
+}
+
+// The first line where a trace differs from its reference log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceMismatch {
+    // 1-based, like an editor
+    pub line: usize,
+    pub expected: String,
+    pub actual: String,
+    // The lines leading up to the mismatch, oldest first
+    pub context: Vec<String>,
+}
+
+impl fmt::Display for TraceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "trace differs from reference at line {}", self.line)?;
+        for line in &self.context {
+            writeln!(f, "    {}", line)?;
+        }
+        writeln!(f, "expected: {}", self.expected)?;
+        write!(f, "actual:   {}", self.actual)
+    }
+}
+
+const MISMATCH_CONTEXT: usize = 5;
+
+// Run `gameboy` alongside a reference log in `format`, returning the number of lines that
+// matched once the log runs out, or where the two first differ
+pub fn compare_log(gameboy: &mut Gameboy, reference: impl BufRead, format: TraceFormat) -> io::Result<Result<usize, TraceMismatch>> {
+    let previous = gameboy.tracer.replace(Box::new(TraceBuffer::new(format)));
+    let result = run_against(gameboy, reference);
+    gameboy.tracer = previous;
+    result
+}
+
+fn run_against(gameboy: &mut Gameboy, reference: impl BufRead) -> io::Result<Result<usize, TraceMismatch>> {
+    let mut context = Vec::new();
+    let mut matched = 0;
+    for expected in reference.lines() {
+        let expected = expected?;
+
+        // Execute until the next instruction is traced, giving up if nothing runs for
+        // a frame
+        let mut actual = None;
+        let mut cycles = 0;
+        while actual.is_none() && cycles < CYCLES_PER_FRAME {
+            cycles += gameboy.execute() as u32 * 4;
+            let tracer = gameboy.tracer.as_mut().unwrap().as_mut() as &mut dyn Any;
+            let buffer = tracer.downcast_mut::<TraceBuffer>().unwrap();
+            actual = buffer.lines.pop();
+        }
+        let actual = actual.unwrap_or_else(|| "<no instruction executed>".to_string());
+
+        if actual != expected {
+            return Ok(Err(TraceMismatch {
+                line: matched + 1,
+                expected,
+                actual,
+                context,
+            }));
+        }
+        if context.len() == MISMATCH_CONTEXT {
+            context.remove(0);
+        }
+        context.push(actual);
+        matched += 1;
+    }
+    Ok(Ok(matched))
+}
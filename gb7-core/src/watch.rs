@@ -0,0 +1,167 @@
+use std::{cell::RefCell, collections::BTreeMap, fmt, ops::RangeInclusive};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    // Fetching the first byte of an instruction
+    Execute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchCondition {
+    // The value read, written or executed
+    Equals(u8),
+    // A write that changes what memory holds
+    Changed,
+    // The address is in ROM and this bank is mapped
+    Bank(usize),
+}
+
+// Watches the CPU's accesses to a range of addresses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub range: RangeInclusive<u16>,
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+    // All of which must hold for an access to hit
+    pub conditions: Vec<WatchCondition>,
+}
+
+impl Watchpoint {
+    pub fn new(range: RangeInclusive<u16>, accesses: &[Access]) -> Self {
+        Watchpoint {
+            range,
+            read: accesses.contains(&Access::Read),
+            write: accesses.contains(&Access::Write),
+            execute: accesses.contains(&Access::Execute),
+            conditions: Vec::new(),
+        }
+    }
+
+    pub fn with_condition(mut self, condition: WatchCondition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    fn hits(&self, access: Access, addr: u16, value: u8, changed: bool, bank: Option<usize>) -> bool {
+        let watched = match access {
+            Access::Read => self.read,
+            Access::Write => self.write,
+            Access::Execute => self.execute,
+        };
+        watched
+            && self.range.contains(&addr)
+            && self.conditions.iter().all(|condition| match *condition {
+                WatchCondition::Equals(expected) => value == expected,
+                WatchCondition::Changed => access == Access::Write && changed,
+                WatchCondition::Bank(expected) => bank == Some(expected),
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugEvent {
+    Watchpoint {
+        id: usize,
+        access: Access,
+        addr: u16,
+        value: u8,
+    },
+    // The interrupt's bit in IF, e.g. 0 for VBlank
+    Interrupt(u8),
+    Line(u8),
+    // OAM DMA started copying from this address
+    DmaStart(u16),
+    BankSwitch {
+        from: usize,
+        to: usize,
+    },
+}
+
+impl fmt::Display for DebugEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugEvent::Watchpoint { id, access, addr, value } => {
+                write!(f, "Watchpoint {}: {:?} {:04X} = {:02X}", id, access, addr, value)
+            }
+            DebugEvent::Interrupt(bit) => {
+                let name = ["VBlank", "STAT", "Timer", "Serial", "Joypad"].get(*bit as usize).unwrap_or(&"Unknown");
+                write!(f, "{} interrupt dispatched", name)
+            }
+            DebugEvent::Line(ly) => write!(f, "LY reached {:02X}", ly),
+            DebugEvent::DmaStart(source) => write!(f, "OAM DMA started from {:04X}", source),
+            DebugEvent::BankSwitch { from, to } => write!(f, "ROM bank switched from {:02X} to {:02X}", from, to),
+        }
+    }
+}
+
+type Callback = Box<dyn FnMut(&DebugEvent)>;
+
+// Watchpoints and event breakpoints, installed as `Gameboy::watches`. Hits are reported
+// to the callback as they happen, partway through the instruction that caused them.
+// Accesses through `Gameboy::read` and `Gameboy::write` are watched, which includes the
+// CPU's, but not `Gameboy::peek` and `Gameboy::poke`, OAM DMA, or CPU accesses cut off
+// by OAM DMA.
+pub struct Watches {
+    watchpoints: BTreeMap<usize, Watchpoint>,
+    next_id: usize,
+    pub break_on_interrupt: bool,
+    pub break_on_line: Option<u8>,
+    pub break_on_dma: bool,
+    pub break_on_bank_switch: bool,
+    // Called from `Gameboy::read`, which only borrows the Gameboy
+    callback: RefCell<Callback>,
+}
+
+impl Watches {
+    pub fn new(callback: impl FnMut(&DebugEvent) + 'static) -> Self {
+        Watches {
+            watchpoints: BTreeMap::new(),
+            next_id: 0,
+            break_on_interrupt: false,
+            break_on_line: None,
+            break_on_dma: false,
+            break_on_bank_switch: false,
+            callback: RefCell::new(Box::new(callback)),
+        }
+    }
+
+    // Returns the watchpoint's ID
+    pub fn add(&mut self, watchpoint: Watchpoint) -> usize {
+        self.next_id += 1;
+        self.watchpoints.insert(self.next_id, watchpoint);
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Watchpoint> {
+        self.watchpoints.remove(&id)
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = (usize, &Watchpoint)> {
+        self.watchpoints.iter().map(|(&id, watchpoint)| (id, watchpoint))
+    }
+
+    // `changed` is whether a write changed what memory holds
+    pub(crate) fn access(&self, access: Access, addr: u16, value: u8, changed: bool, bank: Option<usize>) {
+        for (&id, watchpoint) in &self.watchpoints {
+            if watchpoint.hits(access, addr, value, changed, bank) {
+                (self.callback.borrow_mut())(&DebugEvent::Watchpoint { id, access, addr, value });
+            }
+        }
+    }
+
+    pub(crate) fn event(&self, event: DebugEvent) {
+        let enabled = match event {
+            DebugEvent::Watchpoint { .. } => true,
+            DebugEvent::Interrupt(_) => self.break_on_interrupt,
+            DebugEvent::Line(ly) => self.break_on_line == Some(ly),
+            DebugEvent::DmaStart(_) => self.break_on_dma,
+            DebugEvent::BankSwitch { .. } => self.break_on_bank_switch,
+        };
+        if enabled {
+            (self.callback.borrow_mut())(&event);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use gb7_core::{
+    cartridge::{self, Cartridge, NoMBC},
+    gameboy::Gameboy,
+};
+
+// Build a 32 KiB ROM-only cartridge with `program` placed at the 0x0100 entry point.
+pub fn test_cart(program: &[u8]) -> Cartridge {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0100 + program.len()].copy_from_slice(program);
+    NoMBC::new(&rom).into()
+}
+
+// Build a 32 KiB ROM-only cartridge with each `(address, code)` section in place, e.g.
+// to put handlers at the interrupt vectors.
+pub fn test_cart_sections(sections: &[(usize, &[u8])]) -> Cartridge {
+    let mut rom = vec![0; 0x8000];
+    for (addr, code) in sections {
+        rom[*addr..*addr + code.len()].copy_from_slice(code);
+    }
+    NoMBC::new(&rom).into()
+}
+
+// A Gameboy running a cartridge that does nothing but spin (`JR -2`), so tests can
+// poke the hardware through the bus and observe the result.
+pub fn idle_gameboy() -> Gameboy {
+    Gameboy::new_dmg(test_cart(&[0x18, 0xFE]))
+}
+
+// Start a serial transfer of `sb` with SC set to `sc`, then spin: LD A,`sb`; LDH (SB),A;
+// LD A,`sc`; LDH (SC),A; JR -2
+pub fn transfer_program(sb: u8, sc: u8) -> Gameboy {
+    Gameboy::new_dmg(test_cart(&[0x3E, sb, 0xE0, 0x01, 0x3E, sc, 0xE0, 0x02, 0x18, 0xFE]))
+}
+
+// A Gameboy running one of Blargg's test ROMs from resources/blargg, e.g. "09-op r,r"
+pub fn blargg(test_name: &str) -> Gameboy {
+    let mut cart_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    cart_path.push(format!("resources/blargg/{}.gb", test_name));
+    Gameboy::new_dmg(cartridge::load_from_path(&cart_path))
+}
+
+// Execute until LY reads `line`.
+pub fn run_until_line(gb: &mut Gameboy, line: u8) {
+    while gb.read(0xFF44) != line {
+        gb.execute();
+    }
+}
+
+// Execute until the start of the next VBlank, so that a full frame has been drawn
+// if this is called at the start of a previous VBlank.
+pub fn run_until_vblank(gb: &mut Gameboy) {
+    while gb.read(0xFF44) == 144 {
+        gb.execute();
+    }
+    run_until_line(gb, 144);
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::test_cart;
+use gb7_core::{cartridge, gameboy::Gameboy, serial::CaptureBuffer};
+use test_case::test_case;
+
+// A Gameboy about to run `program` with the system counter at 0 and TIMA counting every
+// 4 M-cycles, so TIMA and the counter show exactly when an access happened
+fn timed_gameboy(program: &[u8], cycle_accurate: bool) -> Gameboy {
+    let mut gb = Gameboy::new_dmg(test_cart(program));
+    gb.cycle_accurate = cycle_accurate;
+    gb.write(0xFF07, 0b101);
+    gb.write(0xFF04, 0);
+    gb
+}
+
+#[test]
+fn read_sees_state_at_its_m_cycle() {
+    // NOP, LDH A,(0x05)
+    let program = [0x00, 0xF0, 0x05];
+
+    // The read is on the 4th M-cycle, by which time TIMA has ticked
+    let mut gb = timed_gameboy(&program, true);
+    gb.execute();
+    assert_eq!(gb.execute(), 3);
+    assert_eq!(gb.cpu.registers.a, 1);
+
+    // Otherwise the read happens before any of the instruction's cycles
+    let mut gb = timed_gameboy(&program, false);
+    gb.execute();
+    gb.execute();
+    assert_eq!(gb.cpu.registers.a, 0);
+}
+
+#[test]
+fn write_lands_on_its_m_cycle() {
+    // LDH (0x04),A resets DIV on its last M-cycle
+    let mut gb = timed_gameboy(&[0xE0, 0x04], true);
+    assert_eq!(gb.execute(), 3);
+    assert_eq!(gb.timers.system_counter(), 0);
+
+    let mut gb = timed_gameboy(&[0xE0, 0x04], false);
+    gb.execute();
+    assert_eq!(gb.timers.system_counter(), 12);
+}
+
+#[test]
+fn push_writes_after_internal_cycle() {
+    // LD SP,0xFF06; PUSH BC writes B to TIMA on its 3rd M-cycle and C to DIV on its 4th
+    let mut gb = timed_gameboy(&[0x31, 0x06, 0xFF, 0xC5], true);
+    gb.cpu.registers.b = 0x42;
+    gb.execute();
+    gb.write(0xFF04, 0);
+    assert_eq!(gb.execute(), 4);
+    // TIMA ticked once more between the two writes
+    assert_eq!(gb.read(0xFF05), 0x43);
+    assert_eq!(gb.timers.system_counter(), 0);
+}
+
+// Instructions take as many M-cycles as without the accuracy mode, which would be caught
+// by a debug assertion if any made more memory accesses than its cycle count
+#[test_case("02-interrupts" ; "interrupts")]
+#[test_case("07-jr,jp,call,ret,rst" ; "jr jp call ret rst")]
+#[test_case("11-op a,(hl)" ; "op a,hl")]
+fn blargg_passes_cycle_accurate(test_name: &str) {
+    let mut cart_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    cart_path.push(format!("resources/blargg/{}.gb", test_name));
+
+    let mut gb = Gameboy::new_dmg(cartridge::load_from_path(&cart_path));
+    gb.cycle_accurate = true;
+    gb.serial.connect(Box::new(CaptureBuffer::default()));
+
+    let mut frames = 0;
+    loop {
+        gb.execute_frame();
+        let output = String::from_utf8_lossy(&gb.serial.device::<CaptureBuffer>().unwrap().data).into_owned();
+        if output.contains("Passed") {
+            return;
+        }
+        assert!(!output.contains("Failed"), "{}", output);
+        frames += 1;
+        assert!(frames < 60 * 60, "timed out: {}", output);
+    }
+}
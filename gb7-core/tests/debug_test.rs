@@ -0,0 +1,72 @@
+mod common;
+
+use gb7_core::{debug, lcd::Palette};
+
+use common::idle_gameboy;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+
+#[test]
+fn tile_sheet_shows_raw_tiles() {
+    let mut gb = idle_gameboy();
+    // Tile 17 (row 1, column 1 of the sheet): top row colour 3
+    gb.write(0x8000 + 17 * 16, 0xFF);
+    gb.write(0x8000 + 17 * 16 + 1, 0xFF);
+
+    let image = debug::tile_sheet(&gb.vram, &Palette::GRAYSCALE);
+    assert_eq!((image.width, image.height), (128, 192));
+    assert_eq!(image.data.len(), 128 * 192 * 4);
+    assert_eq!(image.pixel(8, 8), Palette::GRAYSCALE.color(3));
+    assert_eq!(image.pixel(8, 9), Palette::GRAYSCALE.color(0));
+}
+
+#[test]
+fn tile_map_outlines_viewport() {
+    let mut gb = idle_gameboy();
+    gb.write(0xFF40, 0b1001_0001);
+    gb.write(0xFF42, 200);
+    gb.write(0xFF43, 10);
+
+    let image = debug::tile_map(&gb.vram, &gb.io_regs, 0x9800, &Palette::GRAYSCALE);
+    assert_eq!((image.width, image.height), (256, 256));
+    assert_eq!(image.pixel(10, 200), RED);
+    assert_eq!(image.pixel(169, 200), RED);
+    // The viewport wraps around the bottom of the map
+    assert_eq!(image.pixel(10, (200 + 143) % 256), RED);
+    assert_ne!(image.pixel(11, 201), RED);
+
+    // The other map isn't used by the background, so has no viewport
+    let image = debug::tile_map(&gb.vram, &gb.io_regs, 0x9C00, &Palette::GRAYSCALE);
+    assert_ne!(image.pixel(10, 200), RED);
+}
+
+#[test]
+fn oam_entries_are_decoded() {
+    let mut gb = idle_gameboy();
+    gb.write(0xFE04, 32);
+    gb.write(0xFE05, 40);
+    gb.write(0xFE06, 7);
+    gb.write(0xFE07, 0b1011_0000);
+
+    let entries = debug::oam_entries(&gb.oam);
+    assert_eq!(entries.len(), 40);
+    let entry = entries[1];
+    assert_eq!((entry.index, entry.y, entry.x, entry.tile), (1, 32, 40, 7));
+    assert!(entry.background_priority());
+    assert!(entry.xflip());
+    assert!(!entry.yflip());
+    assert_eq!(entry.dmg_palette(), 1);
+}
+
+#[test]
+fn oam_table_draws_sprites() {
+    let mut gb = idle_gameboy();
+    gb.write(0xFE06, 1);
+    gb.write(0x8010, 0x80);
+    gb.write(0xFF49, 0b1110_0100);
+    gb.write(0xFE07, 0b0001_0000);
+
+    let image = debug::oam_table(&gb.vram, &gb.oam, &gb.io_regs, &Palette::GRAYSCALE);
+    // Entry 1 is the second cell: its sprite's top-left pixel is colour 1
+    assert_eq!(image.pixel(26 + 2, 2), Palette::GRAYSCALE.color(1));
+}
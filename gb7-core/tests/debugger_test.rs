@@ -0,0 +1,125 @@
+mod common;
+
+use common::test_cart_sections;
+use gb7_core::{
+    cartridge::MBC1,
+    debugger::{BankAddr, Command, Debugger, StopReason},
+    gameboy::Gameboy,
+};
+
+fn run(debugger: &mut Debugger, gb: &mut Gameboy, line: &str) -> String {
+    debugger.execute(gb, Command::parse(line).unwrap()).unwrap()
+}
+
+#[test]
+fn parses_bank_addresses() {
+    assert_eq!(BankAddr::parse("03:4A20"), Ok(BankAddr { bank: Some(3), addr: 0x4A20 }));
+    assert_eq!(BankAddr::parse("$C000"), Ok(BankAddr { bank: None, addr: 0xC000 }));
+    assert_eq!(BankAddr::parse("0x150"), Ok(BankAddr { bank: None, addr: 0x0150 }));
+    assert!(BankAddr::parse("03:C000").is_err());
+    assert!(BankAddr::parse("01:0150").is_err());
+    assert!(BankAddr::parse("10000").is_err());
+    assert_eq!(BankAddr::parse("03:4A20").unwrap().to_string(), "03:4A20");
+}
+
+#[test]
+fn continue_stops_at_breakpoint_in_bank() {
+    // Switch to bank 2, then 3, and jump into each
+    let mut rom = vec![0; 0x10000];
+    rom[0x0147] = 0x01;
+    rom[0x0100..0x0111].copy_from_slice(&[
+        0x3E, 0x02, 0xEA, 0x00, 0x20, 0xCD, 0x00, 0x40, // LD A,2; LD (0x2000),A; CALL 0x4000
+        0x3C, 0xEA, 0x00, 0x20, 0xCD, 0x00, 0x40, // INC A; LD (0x2000),A; CALL 0x4000
+        0x18, 0xFE, // JR -2
+    ]);
+    rom[0x8000] = 0xC9; // RET
+    rom[0xC000] = 0xC9;
+    let mut gb = Gameboy::new_dmg(MBC1::new(&rom, 0).into());
+    let mut debugger = Debugger::new();
+
+    run(&mut debugger, &mut gb, "b 03:4000");
+    let output = run(&mut debugger, &mut gb, "c");
+    assert!(output.starts_with("Breakpoint 1 hit"), "{}", output);
+    assert_eq!(gb.cpu.pc, 0x4000);
+    assert_eq!(gb.rom_bank(0x4000), Some(3));
+    assert!(output.ends_with("> 03:4000  C9        RET"), "{}", output);
+}
+
+#[test]
+fn next_steps_over_calls() {
+    // CALL 0x0200; NOP ... 0x0200: LD A,5; RET
+    let mut gb = Gameboy::new_dmg(test_cart_sections(&[
+        (0x0100, &[0xCD, 0x00, 0x02, 0x00]),
+        (0x0200, &[0x3E, 0x05, 0xC9]),
+    ]));
+    let mut debugger = Debugger::new();
+    assert_eq!(debugger.step_over(&mut gb), StopReason::Done);
+    assert_eq!(gb.cpu.pc, 0x0103);
+    assert_eq!(gb.cpu.registers.a, 5);
+
+    // Unless a breakpoint inside stops it
+    gb.cpu.pc = 0x0100;
+    run(&mut debugger, &mut gb, "b 0202");
+    assert_eq!(debugger.step_over(&mut gb), StopReason::Breakpoint(1));
+    assert_eq!(gb.cpu.pc, 0x0202);
+}
+
+#[test]
+fn edits_registers_and_memory() {
+    let mut gb = common::idle_gameboy();
+    let mut debugger = Debugger::new();
+    run(&mut debugger, &mut gb, "set hl C123");
+    run(&mut debugger, &mut gb, "set f 0x10");
+    assert_eq!(gb.cpu.registers.hl(), 0xC123);
+    assert!(run(&mut debugger, &mut gb, "r").contains("F:---C"));
+
+    assert_eq!(run(&mut debugger, &mut gb, "w C000 AB CD"), "C000  AB CD");
+    assert_eq!(gb.read(0xC001), 0xCD);
+    assert!(Command::parse("set a 100").is_err());
+    assert!(Command::parse("w C000 1FF").is_err());
+}
+
+#[test]
+fn memory_commands_stay_in_bounds() {
+    let mut gb = common::idle_gameboy();
+    let mut debugger = Debugger::new();
+    let mut error = |line: &str| debugger.execute(&mut gb, Command::parse(line).unwrap()).unwrap_err();
+
+    // ROM writes would hit the cartridge's bank registers instead
+    assert_eq!(error("w 03:4A20 12"), "03:4A20 is in ROM, which can't be written");
+    assert_eq!(error("w 2000 01"), "2000 is in ROM, which can't be written");
+    assert_eq!(error("w FFFF 01 02"), "writing 2 bytes at FFFF runs past the end of memory");
+
+    assert_eq!(error("x 00:3FF0 32"), "32 bytes from 00:3FF0 runs past the end of the bank");
+    assert_eq!(error("x FFF0 32"), "32 bytes from FFF0 runs past the end of memory");
+    assert_eq!(run(&mut debugger, &mut gb, "x 00:3FF8 8").len(), "00:3FF8 ".len() + 8 * 3);
+}
+
+#[test]
+fn runs_to_frame() {
+    let mut gb = common::idle_gameboy();
+    let mut debugger = Debugger::new();
+    let output = run(&mut debugger, &mut gb, "frame 3");
+    assert!(output.starts_with("frame 3  > 00:0100"), "{}", output);
+    assert_eq!(debugger.frame, 3);
+    assert!(debugger.execute(&mut gb, Command::RunToFrame(2)).is_err());
+}
+
+#[test]
+fn continue_stops_on_watchpoints_and_catches() {
+    // LD A,0x42; LD (0xC010),A; JR -2
+    let mut gb = Gameboy::new_dmg(test_cart_sections(&[(0x0100, &[0x3E, 0x42, 0xEA, 0x10, 0xC0, 0x18, 0xFE])]));
+    let mut debugger = Debugger::new();
+    assert_eq!(run(&mut debugger, &mut gb, "watch w C000-C0FF =42"), "Watchpoint 1 on w C000-C0FF =42");
+    let output = run(&mut debugger, &mut gb, "c");
+    assert!(output.starts_with("Watchpoint 1: Write C010 = 42"), "{}", output);
+    assert_eq!(gb.cpu.pc, 0x0105);
+
+    run(&mut debugger, &mut gb, "unwatch 1");
+    run(&mut debugger, &mut gb, "catch ly 90");
+    assert!(run(&mut debugger, &mut gb, "bl").contains("catch ly 90"));
+    let output = run(&mut debugger, &mut gb, "c");
+    assert!(output.starts_with("LY reached 90"), "{}", output);
+    assert_eq!(gb.read(0xFF44), 0x90);
+    assert!(Command::parse("watch q C000").is_err());
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::test_cart;
+use gb7_core::{
+    disasm::{self, Syntax},
+    gameboy::Gameboy,
+};
+
+fn text(bytes: &[u8], addr: u16, syntax: Syntax) -> String {
+    disasm::decode(bytes, addr, syntax).text
+}
+
+#[test]
+fn decodes_operands() {
+    assert_eq!(text(&[0x2A], 0, Syntax::Conventional), "LD A,(HL+)");
+    assert_eq!(text(&[0x01, 0x34, 0x12], 0, Syntax::Conventional), "LD BC,$1234");
+    assert_eq!(text(&[0xFA, 0x00, 0xC0], 0, Syntax::Conventional), "LD A,($C000)");
+    assert_eq!(text(&[0xE0, 0x46], 0, Syntax::Conventional), "LDH ($FF46),A");
+    assert_eq!(text(&[0xFE, 0x90], 0, Syntax::Conventional), "CP $90");
+    assert_eq!(text(&[0xE8, 0xFE], 0, Syntax::Conventional), "ADD SP,-2");
+    assert_eq!(text(&[0xF8, 0x05], 0, Syntax::Conventional), "LD HL,SP+5");
+    assert_eq!(text(&[0xCB, 0x7E], 0, Syntax::Conventional), "BIT 7,(HL)");
+    assert_eq!(text(&[0xD3], 0, Syntax::Conventional), "DB $D3");
+}
+
+#[test]
+fn resolves_branch_targets() {
+    let jr = disasm::decode(&[0x20, 0xFE], 0x0152, Syntax::Conventional);
+    assert_eq!(jr.text, "JR NZ,$0152");
+    assert_eq!(jr.target, Some(0x0152));
+
+    let jr = disasm::decode(&[0x18, 0x10], 0x0140, Syntax::Conventional);
+    assert_eq!(jr.text, "JR $0152");
+
+    let call = disasm::decode(&[0xCD, 0x50, 0x01], 0x0100, Syntax::Conventional);
+    assert_eq!((call.text.as_str(), call.target, call.next_addr()), ("CALL $0150", Some(0x0150), 0x0103));
+
+    assert_eq!(disasm::decode(&[0xEF], 0, Syntax::Conventional).target, Some(0x28));
+    assert_eq!(disasm::decode(&[0xE9], 0, Syntax::Conventional).target, None);
+}
+
+#[test]
+fn rgbds_syntax() {
+    assert_eq!(text(&[0x2A], 0, Syntax::Rgbds), "LD A,[HL+]");
+    assert_eq!(text(&[0x90], 0, Syntax::Rgbds), "SUB A,B");
+    assert_eq!(text(&[0xE2], 0, Syntax::Rgbds), "LDH [C],A");
+    assert_eq!(text(&[0xE9], 0, Syntax::Rgbds), "JP HL");
+    assert_eq!(text(&[0xEA, 0x00, 0xC0], 0, Syntax::Rgbds), "LD [$C000],A");
+    assert_eq!(text(&[0x88], 0, Syntax::Rgbds), "ADC A,B");
+}
+
+#[test]
+fn disassembles_bus_and_slices() {
+    let program = [0x3E, 0x01, 0xCB, 0x37, 0x20, 0xFA, 0xC3];
+    let gb = Gameboy::new_dmg(test_cart(&program));
+
+    let listing: Vec<String> = disasm::disassemble_count(&gb, 0x0100, 3, Syntax::Conventional)
+        .into_iter()
+        .map(|i| format!("{:04X} {}", i.addr, i.text))
+        .collect();
+    assert_eq!(listing, ["0100 LD A,$01", "0102 SWAP A", "0104 JR NZ,$0100"]);
+
+    // The JP at the end is cut off
+    let texts: Vec<String> = disasm::disassemble_slice(&program, 0x0100, Syntax::Conventional)
+        .into_iter()
+        .map(|i| i.text)
+        .collect();
+    assert_eq!(texts, ["LD A,$01", "SWAP A", "JR NZ,$0100", "DB $C3"]);
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::idle_gameboy;
+use gb7_core::dma::OamDma;
+use test_case::test_case;
+
+#[test]
+fn transfer_copies_160_bytes_after_startup() {
+    let mut dma = OamDma::default();
+    dma.start(0xC1);
+    assert!(dma.is_active());
+
+    // One M-cycle of start-up delay with the bus still free
+    assert_eq!(dma.tick(), None);
+    assert!(!dma.is_blocking());
+
+    for i in 0..160 {
+        assert_eq!(dma.tick(), Some((0xC100 + i, 0xFE00 + i)));
+        assert!(dma.is_blocking());
+    }
+    assert_eq!(dma.tick(), None);
+    assert!(!dma.is_active());
+    assert!(!dma.is_blocking());
+}
+
+#[test]
+fn sources_past_work_ram_read_echo() {
+    let mut dma = OamDma::default();
+    dma.start(0xE0);
+    dma.tick();
+    assert_eq!(dma.tick(), Some((0xC000, 0xFE00)));
+
+    // Restarted, the old transfer copies one more byte
+    dma.start(0xFE);
+    dma.tick();
+    assert_eq!(dma.tick(), Some((0xDE00, 0xFE00)));
+}
+
+#[test]
+fn restart_continues_old_transfer_until_new_one_starts() {
+    let mut dma = OamDma::default();
+    dma.start(0xC0);
+    for _ in 0..11 {
+        dma.tick();
+    }
+
+    dma.start(0xD0);
+    assert_eq!(dma.tick(), Some((0xC00A, 0xFE0A)));
+    assert!(dma.is_blocking());
+    assert_eq!(dma.tick(), Some((0xD000, 0xFE00)));
+}
+
+#[test_case(false ; "bulk timing")]
+#[test_case(true ; "cycle accurate")]
+fn cpu_only_reaches_hram_during_transfer(cycle_accurate: bool) {
+    let mut gb = idle_gameboy();
+    gb.cycle_accurate = cycle_accurate;
+    for addr in 0xC000..0xC0A0 {
+        gb.write(addr, 0x11);
+    }
+    gb.write(0xC100, 0x99);
+    gb.write(0xFF90, 0x55);
+
+    // Run from HRAM like games do
+    let program = [
+        0x3E, 0xC0, // LD A,0xC0
+        0xE0, 0x46, // LDH (0x46),A
+        0xFA, 0x00, 0xC1, // LD A,(0xC100)
+        0x47, // LD B,A
+        0xEA, 0x00, 0xC1, // LD (0xC100),A
+        0xF0, 0x90, // LDH A,(0x90)
+        0x4F, // LD C,A
+        0x18, 0xFE, // JR -2
+    ];
+    for (i, &byte) in program.iter().enumerate() {
+        gb.write(0xFF80 + i as u16, byte);
+    }
+    gb.cpu.pc = 0xFF80;
+
+    for _ in 0..100 {
+        gb.execute();
+    }
+    assert!(!gb.dma.is_active());
+
+    // The blocked read saw the byte being copied, the write was lost, and HRAM was fine
+    assert_eq!(gb.cpu.registers.b, 0x11);
+    assert_eq!(gb.read(0xC100), 0x99);
+    assert_eq!(gb.cpu.registers.c, 0x55);
+    for addr in 0xFE00..0xFEA0 {
+        assert_eq!(gb.oam.read(addr), 0x11);
+    }
+}
+
+#[test]
+fn transfer_is_not_instant() {
+    let mut gb = idle_gameboy();
+    gb.write(0xC09F, 0x22);
+    gb.write(0xFF46, 0xC0);
+    assert_eq!(gb.oam.read(0xFE9F), 0x00);
+
+    let mut m_cycles = 0;
+    while m_cycles < 161 {
+        m_cycles += gb.execute() as u32;
+    }
+    assert_eq!(gb.oam.read(0xFE9F), 0x22);
+    assert!(!gb.dma.is_active());
+}
@@ -0,0 +1,49 @@
+mod common;
+
+use common::test_cart;
+use gb7_core::{
+    gameboy::Gameboy,
+    instructions::{self, CB_INSTRUCTIONS, INSTRUCTIONS},
+};
+
+#[test]
+fn table_describes_common_instructions() {
+    let ld = &INSTRUCTIONS[0x2A];
+    assert_eq!((ld.mnemonic, ld.length, ld.cycles), ("LD A,(HL+)", 1, 2));
+
+    let call = &INSTRUCTIONS[0xC4];
+    assert_eq!((call.mnemonic, call.length), ("CALL NZ,a16", 3));
+    assert_eq!((call.cycles, call.taken_cycles), (3, 6));
+    assert!(call.is_conditional());
+
+    assert_eq!(INSTRUCTIONS[0x08].cycles, 5);
+    assert_eq!(INSTRUCTIONS[0xD3].mnemonic, "INVALID");
+    assert!(!INSTRUCTIONS[0xC3].is_conditional());
+}
+
+#[test]
+fn prefixed_instructions_include_prefix() {
+    assert!(CB_INSTRUCTIONS.iter().all(|info| info.length == 2));
+    assert_eq!(CB_INSTRUCTIONS[0x37].mnemonic, "SWAP A");
+    assert_eq!(CB_INSTRUCTIONS[0x46].cycles, 3);
+    assert_eq!(CB_INSTRUCTIONS[0xFE].mnemonic, "SET 7,(HL)");
+    assert_eq!(CB_INSTRUCTIONS[0xFE].cycles, 4);
+    assert_eq!(instructions::info(0xCB, 0x11).mnemonic, "RL C");
+    assert_eq!(instructions::info(0x18, 0x11).mnemonic, "JR e8");
+}
+
+#[test]
+fn executor_takes_cycles_from_table() {
+    // XOR A; JR NZ,+0; JR Z,+0; CB SET 0,(HL); JP 0x0100
+    let mut gb = Gameboy::new_dmg(test_cart(&[0xAF, 0x20, 0x00, 0x28, 0x00, 0xCB, 0xC6, 0xC3, 0x00, 0x01]));
+    gb.cpu.registers.h = 0xC0;
+    gb.cpu.registers.l = 0x00;
+
+    assert_eq!(gb.execute(), INSTRUCTIONS[0xAF].cycles);
+    assert_eq!(gb.execute(), INSTRUCTIONS[0x20].cycles);
+    assert_eq!(gb.execute(), INSTRUCTIONS[0x28].taken_cycles);
+    assert_eq!(gb.execute(), CB_INSTRUCTIONS[0xC6].cycles);
+    assert_eq!(gb.read(0xC000), 0x01);
+    assert_eq!(gb.execute(), INSTRUCTIONS[0xC3].cycles);
+    assert_eq!(gb.cpu.pc, 0x0100);
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use gb7_core::gameboy::Gameboy;
+
+use common::test_cart_sections;
+
+// LD A,`ie`; LDH (IE),A; LD A,`if_`; LDH (IF),A, followed by `program`. Every interrupt
+// vector and 0x0000 spin with JR -2.
+fn interrupt_gameboy(ie: u8, if_: u8, program: &[u8]) -> Gameboy {
+    let mut code = vec![0x3E, ie, 0xE0, 0xFF, 0x3E, if_, 0xE0, 0x0F];
+    code.extend_from_slice(program);
+
+    let spin: &[u8] = &[0x18, 0xFE];
+    let mut gb = Gameboy::new_dmg(test_cart_sections(&[
+        (0x0000, spin),
+        (0x0040, spin),
+        (0x0048, spin),
+        (0x0050, spin),
+        (0x0058, spin),
+        (0x0060, spin),
+        (0x0100, &code),
+    ]));
+    // Stop the PPU from raising interrupts of its own
+    gb.write(0xFF40, 0);
+    for _ in 0..4 {
+        gb.execute();
+    }
+    gb
+}
+
+#[test]
+fn ei_takes_effect_after_next_instruction() {
+    // EI; INC B; INC B; JR -2
+    let mut gb = interrupt_gameboy(0x04, 0x04, &[0xFB, 0x04, 0x04, 0x18, 0xFE]);
+
+    gb.execute();
+    assert!(!gb.cpu.ime);
+    gb.execute();
+    assert!(gb.cpu.ime);
+    assert_eq!(gb.cpu.registers.b, 1);
+
+    assert_eq!(gb.execute(), 5);
+    assert_eq!(gb.cpu.pc, 0x50);
+    assert_eq!(gb.cpu.registers.b, 1);
+    assert_eq!(gb.read(0xFF0F) & 0x04, 0);
+}
+
+#[test]
+fn di_right_after_ei_keeps_interrupts_disabled() {
+    // EI; DI; INC B; JR -2
+    let mut gb = interrupt_gameboy(0x04, 0x04, &[0xFB, 0xF3, 0x04, 0x18, 0xFE]);
+
+    for _ in 0..100 {
+        gb.execute();
+    }
+    assert!(!gb.cpu.ime);
+    assert_eq!(gb.cpu.registers.b, 1);
+    assert_eq!(gb.read(0xFF0F) & 0x04, 0x04);
+}
+
+#[test]
+fn halt_bug_repeats_next_byte() {
+    // HALT; INC B; JR -2 with IME off and an interrupt pending
+    let mut gb = interrupt_gameboy(0x04, 0x04, &[0x76, 0x04, 0x18, 0xFE]);
+
+    for _ in 0..10 {
+        gb.execute();
+    }
+    assert!(!gb.cpu.halted);
+    assert_eq!(gb.cpu.registers.b, 2);
+}
+
+#[test]
+fn halt_with_ime_off_wakes_without_dispatch() {
+    // HALT; INC B; JR -2 with nothing pending yet
+    let mut gb = interrupt_gameboy(0x04, 0x00, &[0x76, 0x04, 0x18, 0xFE]);
+
+    for _ in 0..10 {
+        gb.execute();
+    }
+    assert!(gb.cpu.halted);
+
+    gb.write(0xFF0F, 0x04);
+    // One M-cycle to wake up, then execution carries on after the HALT
+    assert_eq!(gb.execute(), 1);
+    assert!(!gb.cpu.halted);
+    assert_eq!(gb.cpu.registers.b, 0);
+    gb.execute();
+    assert_eq!(gb.cpu.registers.b, 1);
+    assert_eq!(gb.read(0xFF0F) & 0x04, 0x04);
+}
+
+#[test]
+fn halt_with_ime_on_wakes_then_dispatches() {
+    // EI; HALT; INC B; JR -2
+    let mut gb = interrupt_gameboy(0x04, 0x00, &[0xFB, 0x76, 0x04, 0x18, 0xFE]);
+
+    for _ in 0..10 {
+        gb.execute();
+    }
+    assert!(gb.cpu.halted);
+
+    gb.write(0xFF0F, 0x04);
+    assert_eq!(gb.execute(), 1);
+    assert_eq!(gb.execute(), 5);
+    assert_eq!(gb.cpu.pc, 0x50);
+    assert_eq!(gb.cpu.registers.b, 0);
+}
+
+#[test]
+fn ei_halt_with_interrupt_pending_returns_to_halt() {
+    // EI; HALT; INC B; JR -2 with an interrupt already pending
+    let mut gb = interrupt_gameboy(0x04, 0x04, &[0xFB, 0x76, 0x04, 0x18, 0xFE]);
+
+    gb.execute();
+    gb.execute();
+    assert_eq!(gb.execute(), 5);
+    assert_eq!(gb.cpu.pc, 0x50);
+    assert_eq!(gb.read_word(gb.cpu.sp), 0x0109);
+
+    // The handler runs normally rather than repeating its first byte
+    for _ in 0..10 {
+        gb.execute();
+    }
+    assert_eq!(gb.cpu.pc, 0x50);
+    assert!(!gb.cpu.halt_bug);
+}
+
+#[test]
+fn ie_overwritten_by_push_cancels_dispatch() {
+    // LD SP,$0000; EI; NOP; NOP. Pushing PC's upper byte (0x01) writes IE = 0x01,
+    // disabling the timer interrupt mid-dispatch.
+    let mut gb = interrupt_gameboy(0x04, 0x04, &[0x31, 0x00, 0x00, 0xFB, 0x00, 0x00]);
+    gb.execute();
+    gb.execute();
+    gb.execute();
+
+    assert_eq!(gb.execute(), 5);
+    assert_eq!(gb.cpu.pc, 0x0000);
+    assert_eq!(gb.read(0xFFFF), 0x01);
+    assert_eq!(gb.read(0xFF0F) & 0x04, 0x04);
+}
+
+#[test]
+fn ie_overwritten_by_push_redirects_dispatch() {
+    // As above, but with VBlank also pending so the new IE picks it instead
+    let mut gb = interrupt_gameboy(0x04, 0x05, &[0x31, 0x00, 0x00, 0xFB, 0x00, 0x00]);
+    gb.execute();
+    gb.execute();
+    gb.execute();
+
+    gb.execute();
+    assert_eq!(gb.cpu.pc, 0x0040);
+    assert_eq!(gb.read(0xFF0F) & 0x05, 0x04);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::{idle_gameboy, run_until_line};
+
+#[test]
+fn unused_bits_read_as_one() {
+    let mut gb = idle_gameboy();
+
+    gb.write(0xFF0F, 0x00);
+    assert_eq!(gb.read(0xFF0F), 0xE0);
+    gb.write(0xFF0F, 0x1F);
+    assert_eq!(gb.read(0xFF0F), 0xFF);
+
+    gb.write(0xFF07, 0x00);
+    assert_eq!(gb.read(0xFF07), 0xF8);
+
+    gb.write(0xFF02, 0x00);
+    assert_eq!(gb.read(0xFF02), 0x7E);
+}
+
+#[test]
+fn unmapped_registers_read_as_ff() {
+    let mut gb = idle_gameboy();
+
+    for addr in [0xFF03, 0xFF08, 0xFF15, 0xFF27, 0xFF4C, 0xFF4D, 0xFF50, 0xFF70, 0xFF7F] {
+        gb.write(addr, 0x00);
+        assert_eq!(gb.read(addr), 0xFF, "{:#06X}", addr);
+    }
+}
+
+#[test]
+fn ly_is_read_only() {
+    let mut gb = idle_gameboy();
+    run_until_line(&mut gb, 10);
+
+    gb.write(0xFF44, 0x42);
+    assert_eq!(gb.read(0xFF44), 10);
+}
+
+#[test]
+fn stat_mode_bits_are_read_only() {
+    let mut gb = idle_gameboy();
+    run_until_line(&mut gb, 144);
+    let mode = gb.read(0xFF41) & 0b11;
+    assert_eq!(mode, 1);
+
+    gb.write(0xFF41, 0b0111_1110);
+    assert_eq!(gb.read(0xFF41), 0b1111_1000 | (gb.read(0xFF41) & 0b111));
+    assert_eq!(gb.read(0xFF41) & 0b11, mode);
+
+    gb.write(0xFF41, 0x00);
+    assert_eq!(gb.read(0xFF41) & 0b1111_1000, 0x80);
+}
+
+#[test]
+fn div_write_resets_through_bus() {
+    let mut gb = idle_gameboy();
+    for _ in 0..200 {
+        gb.execute();
+    }
+    assert_ne!(gb.read(0xFF04), 0);
+
+    gb.write(0xFF04, 0x12);
+    assert_eq!(gb.read(0xFF04), 0);
+}
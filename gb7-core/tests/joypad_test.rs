@@ -0,0 +1,80 @@
+mod common;
+
+use gb7_core::{gameboy::Gameboy, joypad::JoypadButton};
+
+use common::{idle_gameboy, test_cart};
+
+#[test]
+fn press_requests_joypad_interrupt() {
+    let mut gb = idle_gameboy();
+    // Select the action buttons
+    gb.write(0xFF00, 0b0001_0000);
+    gb.execute();
+    gb.write(0xFF0F, 0x00);
+
+    gb.joypad.press(JoypadButton::A);
+    gb.execute();
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0b1110);
+    assert_eq!(gb.read(0xFF0F) & 0b1_0000, 0b1_0000);
+
+    // Holding the button doesn't request another
+    gb.write(0xFF0F, 0x00);
+    gb.execute();
+    assert_eq!(gb.read(0xFF0F) & 0b1_0000, 0);
+}
+
+#[test]
+fn unselected_press_does_not_interrupt() {
+    let mut gb = idle_gameboy();
+    // Select the directions only
+    gb.write(0xFF00, 0b0010_0000);
+    gb.execute();
+    gb.write(0xFF0F, 0x00);
+
+    gb.joypad.press(JoypadButton::Start);
+    gb.execute();
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0x0F);
+    assert_eq!(gb.read(0xFF0F) & 0b1_0000, 0);
+
+    // Selecting the group with the button held pulls its line low
+    gb.write(0xFF00, 0b0001_0000);
+    gb.execute();
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0b0111);
+    assert_eq!(gb.read(0xFF0F) & 0b1_0000, 0b1_0000);
+}
+
+#[test]
+fn button_lines_are_read_only() {
+    let mut gb = idle_gameboy();
+    gb.joypad.press(JoypadButton::Down);
+
+    gb.write(0xFF00, 0b0010_0000);
+    assert_eq!(gb.read(0xFF00), 0b1110_0111);
+    gb.write(0xFF00, 0b0011_1111);
+    assert_eq!(gb.read(0xFF00), 0xFF);
+}
+
+#[test]
+fn press_wakes_from_stop() {
+    // Select directions, STOP, then INC B forever
+    let mut gb = Gameboy::new_dmg(test_cart(&[0x3E, 0x20, 0xE0, 0x00, 0x10, 0x00, 0x04, 0x18, 0xFD]));
+    for _ in 0..3 {
+        gb.execute();
+    }
+    assert!(gb.cpu.stopped);
+    assert_eq!(gb.read(0xFF04), 0);
+
+    let b = gb.cpu.registers.b;
+    for _ in 0..1000 {
+        gb.execute();
+    }
+    assert!(gb.cpu.stopped);
+    assert_eq!(gb.cpu.registers.b, b);
+    assert_eq!(gb.read(0xFF04), 0);
+
+    gb.joypad.press(JoypadButton::Left);
+    gb.execute();
+    gb.execute();
+    assert!(!gb.cpu.stopped);
+    assert_eq!(gb.cpu.registers.b, b.wrapping_add(1));
+}
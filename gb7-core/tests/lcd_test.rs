@@ -0,0 +1,88 @@
+mod common;
+
+use gb7_core::{
+    lcd::{Lcd, Palette},
+    postprocess::{ColorCorrection, PostProcess},
+};
+
+use common::idle_gameboy;
+
+#[test]
+fn frame_is_rgba_through_palette() {
+    let mut lcd = Lcd::default();
+    let mut line = [0; 160];
+    line[1] = 1;
+    line[2] = 3;
+    lcd.set_line(5, line);
+    lcd.finish_frame();
+
+    let row = &lcd.frame()[5 * 160 * 4..6 * 160 * 4];
+    assert_eq!(&row[0..4], &[255, 255, 255, 255]);
+    assert_eq!(&row[4..8], &[175, 175, 175, 255]);
+    assert_eq!(&row[8..12], &[0, 0, 0, 255]);
+
+    // Changing the palette recolours the existing image
+    lcd.set_palette(Palette::CLASSIC);
+    let row = &lcd.frame()[5 * 160 * 4..6 * 160 * 4];
+    assert_eq!(&row[8..12], &Palette::CLASSIC.color(3));
+
+    let custom = Palette::new([[1, 2, 3], [4, 5, 6], [7, 8, 9], [10, 11, 12]]);
+    lcd.set_palette(custom);
+    let row = &lcd.frame()[5 * 160 * 4..6 * 160 * 4];
+    assert_eq!(&row[4..8], &[4, 5, 6, 255]);
+}
+
+#[test]
+fn execute_frame_stops_at_vblank() {
+    let mut gb = idle_gameboy();
+
+    for _ in 0..3 {
+        gb.execute_frame();
+        assert_eq!(gb.read(0xFF44), 144);
+        assert!(!gb.lcd.take_frame_ready());
+    }
+}
+
+#[test]
+fn frame_only_updates_when_complete() {
+    let mut lcd = Lcd::default();
+    lcd.set_line(0, [3; 160]);
+    assert_eq!(&lcd.frame()[0..4], &[255, 255, 255, 255]);
+
+    lcd.finish_frame();
+    assert_eq!(&lcd.frame()[0..4], &[0, 0, 0, 255]);
+}
+
+#[test]
+fn ghosting_blends_previous_frame() {
+    let mut lcd = Lcd::default();
+    lcd.set_post_process(PostProcess::new(None, Some(0.5)));
+
+    lcd.set_line(0, [3; 160]);
+    lcd.finish_frame();
+    assert_eq!(&lcd.frame()[0..4], &[0, 0, 0, 255]);
+
+    lcd.set_line(0, [0; 160]);
+    lcd.finish_frame();
+    assert_eq!(&lcd.frame()[0..4], &[128, 128, 128, 255]);
+
+    lcd.finish_frame();
+    assert_eq!(&lcd.frame()[0..4], &[255, 255, 255, 255]);
+}
+
+#[test]
+fn color_correction_mixes_channels() {
+    let mut lcd = Lcd::default();
+    lcd.set_palette(Palette::new([[255, 0, 0], [0, 0, 0], [0, 0, 0], [0, 0, 0]]));
+    lcd.set_post_process(PostProcess::new(Some(ColorCorrection::CGB), None));
+    lcd.finish_frame();
+
+    // Pure red bleeds into the other channels and loses some intensity
+    let [r, g, b, a] = lcd.frame()[0..4] else { unreachable!() };
+    assert!(r < 255 && r > 200);
+    assert!(g > 0 && b > 0);
+    assert_eq!(a, 255);
+
+    // Black stays black
+    assert_eq!(ColorCorrection::GBA.correct([0, 0, 0]), [0, 0, 0]);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use gb7_core::{gameboy::Gameboy, link::LinkCable};
+
+use common::{test_cart, transfer_program};
+
+fn run_until_transfer_done(link: &mut LinkCable, index: usize) {
+    for _ in 0..10 {
+        link.step();
+    }
+    while link.gameboys[index].read(0xFF02) & 0x80 != 0 {
+        link.step();
+    }
+}
+
+#[test]
+fn master_and_slave_exchange_bytes() {
+    let mut link = LinkCable::new(transfer_program(0x42, 0x81), transfer_program(0x99, 0x80));
+    for gb in link.gameboys.iter_mut() {
+        gb.write(0xFF0F, 0);
+    }
+    run_until_transfer_done(&mut link, 0);
+
+    let [master, slave] = &link.gameboys;
+    assert_eq!(master.read(0xFF01), 0x99);
+    assert_eq!(slave.read(0xFF01), 0x42);
+    assert_eq!(slave.read(0xFF02) & 0x80, 0);
+    assert_eq!(master.read(0xFF0F) & 0b1000, 0b1000);
+    assert_eq!(slave.read(0xFF0F) & 0b1000, 0b1000);
+}
+
+#[test]
+fn either_side_can_be_master() {
+    let mut link = LinkCable::new(transfer_program(0x12, 0x80), transfer_program(0x34, 0x81));
+    run_until_transfer_done(&mut link, 1);
+
+    assert_eq!(link.gameboys[0].read(0xFF01), 0x34);
+    assert_eq!(link.gameboys[1].read(0xFF01), 0x12);
+}
+
+#[test]
+fn master_reads_ff_without_waiting_slave() {
+    let idle = Gameboy::new_dmg(test_cart(&[0x18, 0xFE]));
+    let mut link = LinkCable::new(transfer_program(0x42, 0x81), idle);
+    run_until_transfer_done(&mut link, 0);
+
+    assert_eq!(link.gameboys[0].read(0xFF01), 0xFF);
+    assert_eq!(link.gameboys[1].read(0xFF01), 0x00);
+}
+
+#[test]
+fn gameboys_stay_in_lockstep() {
+    let mut link = LinkCable::new(transfer_program(0, 0), transfer_program(0, 0));
+    link.execute_frame();
+
+    let divs = [link.gameboys[0].timers.system_counter(), link.gameboys[1].timers.system_counter()];
+    assert!(divs[0].abs_diff(divs[1]) <= 16, "{:?}", divs);
+}
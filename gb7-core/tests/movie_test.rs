@@ -0,0 +1,81 @@
+mod common;
+
+use common::test_cart;
+use gb7_core::{
+    gameboy::Gameboy,
+    joypad::JoypadButton,
+    movie::{Movie, MoviePlayer, MovieRecorder, MovieStart},
+};
+
+// Select the action buttons, then keep adding JOYP into 0xC000
+const INPUT_SUM: [u8; 13] = [
+    0x3E, 0x10, // LD A,0x10
+    0xE0, 0x00, // LDH (0x00),A
+    0x21, 0x00, 0xC0, // LD HL,0xC000
+    0xF0, 0x00, // LDH A,(0x00)
+    0x86, // ADD A,(HL)
+    0x77, // LD (HL),A
+    0x18, 0xFA, // JR -6
+];
+
+// Record a movie pressing some buttons, returning it and the state it ends in
+fn record(frames: usize) -> (Movie, Vec<u8>) {
+    let mut gb = Gameboy::new_dmg(test_cart(&INPUT_SUM));
+    let mut recorder = MovieRecorder::new(&gb, MovieStart::PowerOn);
+    for frame in 0..frames {
+        match frame % 7 {
+            0 => gb.joypad.press(JoypadButton::A),
+            3 => gb.joypad.press(JoypadButton::Start),
+            5 => gb.joypad.release(JoypadButton::A),
+            _ => gb.joypad.release(JoypadButton::Start),
+        }
+        recorder.execute_frame(&mut gb);
+    }
+    (recorder.finish(), gb.save_state())
+}
+
+#[test]
+fn playback_reproduces_recording() {
+    let (movie, recorded) = record(40);
+    let mut replayed = Gameboy::new_dmg(test_cart(&INPUT_SUM));
+    let mut player = MoviePlayer::new(movie, &mut replayed).unwrap();
+    while player.execute_frame(&mut replayed) {}
+    assert_eq!(replayed.save_state(), recorded);
+
+    // While no input at all doesn't
+    let mut idle = Gameboy::new_dmg(test_cart(&INPUT_SUM));
+    (0..40).for_each(|_| idle.execute_frame());
+    assert_ne!(idle.read(0xC000), replayed.read(0xC000));
+}
+
+#[test]
+fn playback_stops_at_end_of_movie() {
+    let mut gb = Gameboy::new_dmg(test_cart(&INPUT_SUM));
+    let mut player = MoviePlayer::new(record(10).0, &mut gb).unwrap();
+    for _ in 0..10 {
+        assert!(player.execute_frame(&mut gb));
+    }
+    assert!(player.is_finished());
+    let cycles = gb.cycles();
+    assert!(!player.execute_frame(&mut gb));
+    assert_eq!(player.frame(), 10);
+    assert_eq!(gb.cycles(), cycles);
+}
+
+#[test]
+fn movie_round_trips_through_bytes() {
+    let (movie, _) = record(20);
+    assert_eq!(Movie::from_bytes(&movie.to_bytes()).unwrap(), movie);
+
+    let mut bytes = movie.to_bytes();
+    bytes.push(0);
+    assert!(Movie::from_bytes(&bytes).is_err());
+    assert!(Movie::from_bytes(b"GB7S").is_err());
+}
+
+#[test]
+fn movie_for_another_rom_is_rejected() {
+    let mut gb = common::idle_gameboy();
+    let error = MoviePlayer::new(record(5).0, &mut gb).err().unwrap();
+    assert_eq!(error.to_string(), "movie is for a different ROM");
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::{net::TcpListener, thread};
+
+use gb7_core::{link::LinkCable, netlink::TcpLink};
+
+use common::transfer_program;
+
+// Run both programs on either end of a TCP link for a few frames, returning SB, SC, IF
+// and the system counter of each side
+fn run_linked(first: (u8, u8), second: (u8, u8)) -> [(u8, u8, u8, u16); 2] {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let run = |mut link: TcpLink, (sb, sc): (u8, u8)| {
+        let mut gb = transfer_program(sb, sc);
+        link.attach(&mut gb);
+        gb.write(0xFF0F, 0);
+        for _ in 0..3 {
+            link.execute_frame(&mut gb).unwrap();
+        }
+        (gb.read(0xFF01), gb.read(0xFF02), gb.read(0xFF0F), gb.timers.system_counter())
+    };
+
+    let host = thread::spawn(move || run(TcpLink::accept(&listener).unwrap(), first));
+    let guest = run(TcpLink::connect(addr).unwrap(), second);
+    [host.join().unwrap(), guest]
+}
+
+#[test]
+fn master_and_slave_exchange_bytes() {
+    let [master, slave] = run_linked((0x42, 0x81), (0x99, 0x80));
+
+    assert_eq!(master.0, 0x99);
+    assert_eq!(slave.0, 0x42);
+    assert_eq!(master.1 & 0x80, 0);
+    assert_eq!(slave.1 & 0x80, 0);
+    assert_eq!(master.2 & 0b1000, 0b1000);
+    assert_eq!(slave.2 & 0b1000, 0b1000);
+}
+
+#[test]
+fn slave_can_be_the_connecting_side() {
+    let [slave, master] = run_linked((0x12, 0x80), (0x34, 0x81));
+
+    assert_eq!(master.0, 0x12);
+    assert_eq!(slave.0, 0x34);
+}
+
+#[test]
+fn master_reads_ff_without_waiting_slave() {
+    let [master, other] = run_linked((0x42, 0x81), (0x55, 0x00));
+
+    assert_eq!(master.0, 0xFF);
+    assert_eq!(other.0, 0x55);
+    assert_eq!(other.2 & 0b1000, 0);
+}
+
+#[test]
+fn matches_in_process_link() {
+    let [net_master, net_slave] = run_linked((0x42, 0x81), (0x99, 0x80));
+
+    let mut link = LinkCable::new(transfer_program(0x42, 0x81), transfer_program(0x99, 0x80));
+    for gb in link.gameboys.iter_mut() {
+        gb.write(0xFF0F, 0);
+    }
+    for _ in 0..3 {
+        link.execute_frame();
+    }
+    let [master, slave] = &link.gameboys;
+
+    assert_eq!((net_master.0, net_master.1), (master.read(0xFF01), master.read(0xFF02)));
+    assert_eq!((net_slave.0, net_slave.1), (slave.read(0xFF01), slave.read(0xFF02)));
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use gb7_core::gameboy::Gameboy;
+
+use common::{idle_gameboy, run_until_line, run_until_vblank};
+
+// Background of solid shade 1, a window from x=80 of solid shade 2 and two sprites of
+// shade 3 at the left of line 0 (OAM entries 0 and 1).
+fn layered_gameboy() -> Gameboy {
+    let mut gb = idle_gameboy();
+
+    for i in 0..8 {
+        gb.write(0x8010 + i * 2, 0xFF);
+        gb.write(0x8021 + i * 2, 0xFF);
+        gb.write(0x8030 + i * 2, 0xFF);
+        gb.write(0x8031 + i * 2, 0xFF);
+    }
+    for i in 0..1024 {
+        gb.write(0x9800 + i, 1);
+        gb.write(0x9C00 + i, 2);
+    }
+
+    gb.write(0xFE00, 16);
+    gb.write(0xFE01, 8);
+    gb.write(0xFE02, 3);
+    gb.write(0xFE04, 16);
+    gb.write(0xFE05, 24);
+    gb.write(0xFE06, 3);
+
+    gb.write(0xFF47, 0b1110_0100);
+    gb.write(0xFF48, 0b1110_0100);
+    gb.write(0xFF4A, 0);
+    gb.write(0xFF4B, 87);
+    gb.write(0xFF40, 0b1111_0011);
+    run_until_line(&mut gb, 144);
+    gb
+}
+
+fn pixel(gb: &Gameboy, x: usize, y: usize) -> u8 {
+    gb.lcd.pixels[y * 160 + x]
+}
+
+#[test]
+fn all_layers_drawn_by_default() {
+    let mut gb = layered_gameboy();
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 4, 0), 3);
+    assert_eq!(pixel(&gb, 40, 0), 1);
+    assert_eq!(pixel(&gb, 100, 0), 2);
+}
+
+#[test]
+fn layers_can_be_hidden() {
+    let mut gb = layered_gameboy();
+    gb.ppu.debug.hide_background = true;
+    gb.ppu.debug.hide_window = true;
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 4, 0), 3);
+    assert_eq!(pixel(&gb, 40, 0), 0);
+    assert_eq!(pixel(&gb, 100, 0), 0);
+
+    gb.ppu.debug = Default::default();
+    gb.ppu.debug.hide_sprites = true;
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 4, 0), 1);
+    assert_eq!(pixel(&gb, 100, 0), 2);
+}
+
+#[test]
+fn hidden_window_keeps_line_counter() {
+    let mut gb = layered_gameboy();
+    // Window row 1 uses tile 1 (shade 1) so the counter position is visible
+    for i in 0..32 {
+        gb.write(0x9C20 + i, 1);
+    }
+
+    run_until_line(&mut gb, 0);
+    gb.ppu.debug.hide_window = true;
+    run_until_line(&mut gb, 8);
+    gb.ppu.debug.hide_window = false;
+    run_until_line(&mut gb, 144);
+
+    // The window resumes at its 9th line, as if it had been drawn all along
+    assert_eq!(pixel(&gb, 100, 8), 1);
+}
+
+#[test]
+fn individual_sprites_can_be_hidden() {
+    let mut gb = layered_gameboy();
+    gb.ppu.debug.hidden_sprites = 0b01;
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 4, 0), 1);
+    assert_eq!(pixel(&gb, 20, 0), 3);
+}
+
+#[test]
+fn tinting_marks_layers() {
+    let mut gb = layered_gameboy();
+    gb.ppu.debug.tint_layers = true;
+    run_until_vblank(&mut gb);
+
+    let rgba = |x: usize| -> [u8; 4] { gb.lcd.frame()[x * 4..x * 4 + 4].try_into().unwrap() };
+    let [r, g, b, _] = rgba(4);
+    assert!(r > g && r > b);
+    let [r, g, b, _] = rgba(40);
+    assert!(b > r && b > g);
+    let [r, g, b, _] = rgba(100);
+    assert!(g > r && g > b);
+}
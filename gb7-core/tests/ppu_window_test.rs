@@ -0,0 +1,131 @@
+mod common;
+
+use gb7_core::gameboy::Gameboy;
+
+use common::{idle_gameboy, run_until_line, run_until_vblank};
+
+const LCDC_WINDOW_ON: u8 = 0b1111_0001;
+const LCDC_WINDOW_OFF: u8 = 0b1101_0001;
+
+// Background map 0x9800 is all blank tile 0 (shade 0). The window map 0x9C00 uses
+// solid tile 1 (shade 3) on even tile rows and solid tile 2 (shade 1) on odd rows.
+fn window_gameboy() -> Gameboy {
+    let mut gb = idle_gameboy();
+
+    for i in 0..8 {
+        gb.write(0x8010 + i * 2, 0xFF);
+        gb.write(0x8011 + i * 2, 0xFF);
+        gb.write(0x8020 + i * 2, 0xFF);
+        gb.write(0x8021 + i * 2, 0x00);
+    }
+    for row in 0..32 {
+        for col in 0..32 {
+            gb.write(0x9C00 + row * 32 + col, if row % 2 == 0 { 1 } else { 2 });
+        }
+    }
+
+    gb.write(0xFF47, 0b1110_0100);
+    gb.write(0xFF40, LCDC_WINDOW_ON);
+    gb.write(0xFF4A, 0);
+    gb.write(0xFF4B, 7);
+    run_until_line(&mut gb, 144);
+    gb
+}
+
+fn line(gb: &Gameboy, ly: usize) -> &[u8] {
+    &gb.lcd.pixels[ly * 160..(ly + 1) * 160]
+}
+
+#[test]
+fn window_covers_screen_from_wx() {
+    let mut gb = window_gameboy();
+    gb.write(0xFF4B, 87);
+    run_until_vblank(&mut gb);
+
+    for ly in 0..144 {
+        assert!(line(&gb, ly)[..80].iter().all(|&c| c == 0));
+        assert!(line(&gb, ly)[80..].iter().all(|&c| c != 0), "gap in window on line {}", ly);
+    }
+}
+
+#[test]
+fn window_with_low_wx_is_clipped_not_hidden() {
+    let mut gb = window_gameboy();
+    gb.write(0xFF4B, 3);
+    run_until_vblank(&mut gb);
+
+    assert!(line(&gb, 0).iter().all(|&c| c == 3));
+    assert!(line(&gb, 8).iter().all(|&c| c == 1));
+}
+
+#[test]
+fn window_line_counter_pauses_while_disabled() {
+    let mut gb = window_gameboy();
+    run_until_line(&mut gb, 0);
+
+    // Window drawn for lines 0-7, hidden for 8-15, then resumes at window line 8
+    run_until_line(&mut gb, 8);
+    gb.write(0xFF40, LCDC_WINDOW_OFF);
+    run_until_line(&mut gb, 16);
+    gb.write(0xFF40, LCDC_WINDOW_ON);
+    run_until_line(&mut gb, 144);
+
+    assert!(line(&gb, 7).iter().all(|&c| c == 3));
+    assert!(line(&gb, 12).iter().all(|&c| c == 0));
+    assert!(line(&gb, 16).iter().all(|&c| c == 1));
+    assert!(line(&gb, 24).iter().all(|&c| c == 3));
+}
+
+#[test]
+fn wy_trigger_latches_for_the_frame() {
+    let mut gb = window_gameboy();
+    gb.write(0xFF4A, 40);
+    run_until_line(&mut gb, 60);
+
+    // Moving WY below the current line after it triggered doesn't hide the window
+    gb.write(0xFF4A, 100);
+    run_until_line(&mut gb, 144);
+
+    assert!(line(&gb, 39).iter().all(|&c| c == 0));
+    assert!(line(&gb, 40).iter().all(|&c| c == 3));
+    assert!(line(&gb, 80).iter().all(|&c| c != 0));
+
+    // Next frame WY=100 is only reached at line 100
+    run_until_vblank(&mut gb);
+    assert!(line(&gb, 99).iter().all(|&c| c == 0));
+    assert!(line(&gb, 100).iter().all(|&c| c == 3));
+}
+
+#[test]
+fn wy_not_reached_keeps_window_hidden() {
+    let mut gb = window_gameboy();
+    gb.write(0xFF4A, 50);
+    run_until_line(&mut gb, 60);
+
+    // WY moved to a line that has already passed: the condition never triggers
+    gb.write(0xFF4A, 30);
+    run_until_line(&mut gb, 144);
+    run_until_line(&mut gb, 20);
+    gb.write(0xFF4A, 10);
+    run_until_line(&mut gb, 144);
+
+    for ly in 20..144 {
+        assert!(line(&gb, ly).iter().all(|&c| c == 0), "window visible on line {}", ly);
+    }
+}
+
+#[test]
+fn wx_166_spans_the_following_line() {
+    let mut gb = window_gameboy();
+    run_until_line(&mut gb, 0);
+    run_until_line(&mut gb, 10);
+    gb.write(0xFF4B, 166);
+    run_until_line(&mut gb, 11);
+    gb.write(0xFF4B, 200);
+    run_until_line(&mut gb, 144);
+
+    assert!(line(&gb, 10)[..159].iter().all(|&c| c == 0));
+    assert_eq!(line(&gb, 10)[159], 1);
+    assert!(line(&gb, 11).iter().all(|&c| c == 1));
+    assert!(line(&gb, 12).iter().all(|&c| c == 0));
+}
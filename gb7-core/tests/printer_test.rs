@@ -0,0 +1,166 @@
+use std::{fs::File, path::PathBuf};
+
+use gb7_core::{
+    printer::{self, Printer},
+    serial::SerialDevice,
+};
+
+fn output_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("gb7-printer-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+// Send a packet, returning the printer's two reply bytes
+fn send_packet(printer: &mut Printer, command: u8, compressed: bool, data: &[u8]) -> (u8, u8) {
+    let mut body = vec![command, compressed as u8, data.len() as u8, (data.len() >> 8) as u8];
+    body.extend_from_slice(data);
+    let checksum = body.iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16));
+
+    let mut packet = vec![0x88, 0x33];
+    packet.extend_from_slice(&body);
+    packet.extend_from_slice(&checksum.to_le_bytes());
+    for byte in packet {
+        assert_eq!(printer.exchange(byte), 0x00);
+    }
+    (printer.exchange(0), printer.exchange(0))
+}
+
+// One band of 40 tiles, the left half colour 3 and the right half colour 1
+fn band() -> Vec<u8> {
+    let mut data = Vec::new();
+    for tile in 0..40 {
+        let (lo, hi) = if tile % 20 < 10 { (0xFF, 0xFF) } else { (0xFF, 0x00) };
+        for _ in 0..8 {
+            data.extend_from_slice(&[lo, hi]);
+        }
+    }
+    data
+}
+
+fn read_png(path: &PathBuf) -> (u32, u32, Vec<u8>) {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    (info.width, info.height, buf)
+}
+
+#[test]
+fn print_writes_png() {
+    let dir = output_dir("print");
+    let mut printer = Printer::new(&dir);
+
+    assert_eq!(send_packet(&mut printer, 0x01, false, &[]), (0x81, 0x00));
+    assert_eq!(send_packet(&mut printer, 0x04, false, &band()), (0x81, 0x08));
+    assert_eq!(send_packet(&mut printer, 0x04, false, &[]), (0x81, 0x08));
+    assert_eq!(send_packet(&mut printer, 0x02, false, &[1, 0x00, 0xE4, 0x40]), (0x81, 0x02));
+
+    // Busy for a few status polls, then done
+    let mut polls = 0;
+    while send_packet(&mut printer, 0x0F, false, &[]).1 & 0x02 != 0 {
+        polls += 1;
+        assert!(polls < 100);
+    }
+
+    assert_eq!(printer.printed, vec![dir.join("print-0001.png")]);
+    let (width, height, pixels) = read_png(&printer.printed[0]);
+    assert_eq!((width, height), (160, 16));
+    assert_eq!(pixels[0], 0);
+    assert_eq!(pixels[15 * 160 + 100], 170);
+}
+
+#[test]
+fn print_applies_palette_and_margins() {
+    let dir = output_dir("margins");
+    let mut printer = Printer::new(&dir);
+
+    send_packet(&mut printer, 0x01, false, &[]);
+    send_packet(&mut printer, 0x04, false, &band());
+    send_packet(&mut printer, 0x04, false, &[]);
+    // One feed before, two after, with an inverted palette
+    send_packet(&mut printer, 0x02, false, &[1, 0x12, 0x1B, 0x40]);
+    send_packet(&mut printer, 0x02, false, &[1, 0x00, 0xE4, 0x40]);
+
+    // The buffer is empty after printing, so the second print has nothing to output
+    assert_eq!(printer.printed.len(), 1);
+    let (_, height, pixels) = read_png(&printer.printed[0]);
+    assert_eq!(height, 16 + 16 + 32);
+    assert_eq!(pixels[0], 255);
+    assert_eq!(pixels[16 * 160], 255);
+    assert_eq!(pixels[16 * 160 + 100], 85);
+    assert_eq!(pixels[(height as usize - 1) * 160], 255);
+}
+
+#[test]
+fn compressed_data_matches_uncompressed() {
+    let data = band();
+    // Encode each run of 8 identical tile rows as repeats, the rest literally
+    let mut compressed = Vec::new();
+    for tile in data.chunks_exact(16) {
+        if tile[0] == tile[1] {
+            compressed.extend_from_slice(&[0x80 | 14, tile[0]]);
+        } else {
+            compressed.push(15);
+            compressed.extend_from_slice(tile);
+        }
+    }
+    assert_eq!(printer::decompress(&compressed), data);
+
+    let dir = output_dir("compressed");
+    let mut printer = Printer::new(&dir);
+    send_packet(&mut printer, 0x01, false, &[]);
+    assert_eq!(send_packet(&mut printer, 0x04, true, &compressed).1, 0x08);
+    send_packet(&mut printer, 0x02, false, &[1, 0x00, 0xE4, 0x40]);
+
+    let (_, height, pixels) = read_png(&printer.printed[0]);
+    assert_eq!(height, 16);
+    assert_eq!(pixels[0], 0);
+    assert_eq!(pixels[100], 170);
+}
+
+#[test]
+fn bad_checksum_sets_error_and_is_ignored() {
+    let dir = output_dir("checksum");
+    let mut printer = Printer::new(&dir);
+
+    for byte in [0x88, 0x33, 0x02, 0x00, 0x04, 0x00, 1, 0, 0xE4, 0x40, 0x00, 0x00] {
+        printer.exchange(byte);
+    }
+    assert_eq!(printer.exchange(0), 0x81);
+    assert_eq!(printer.exchange(0), 0x01);
+    assert!(printer.printed.is_empty());
+
+    assert_eq!(send_packet(&mut printer, 0x0F, false, &[]), (0x81, 0x00));
+}
+
+#[test]
+fn print_with_only_margins_writes_nothing() {
+    let dir = output_dir("blank");
+    let mut printer = Printer::new(&dir);
+
+    send_packet(&mut printer, 0x01, false, &[]);
+    send_packet(&mut printer, 0x04, false, &[]);
+    assert_eq!(send_packet(&mut printer, 0x02, false, &[1, 0x13, 0xE4, 0x40]), (0x81, 0x02));
+
+    assert!(printer.printed.is_empty());
+    assert!(!dir.exists());
+    assert!(printer.take_error().is_none());
+}
+
+#[test]
+fn failed_write_is_kept_until_taken() {
+    // A file where the output directory should be
+    let dir = output_dir("unwritable");
+    std::fs::write(&dir, b"").unwrap();
+    let mut printer = Printer::new(&dir);
+
+    send_packet(&mut printer, 0x01, false, &[]);
+    send_packet(&mut printer, 0x04, false, &band());
+    send_packet(&mut printer, 0x02, false, &[1, 0x00, 0xE4, 0x40]);
+
+    assert!(printer.printed.is_empty());
+    assert!(printer.take_error().is_some());
+    assert!(printer.take_error().is_none());
+    std::fs::remove_file(&dir).unwrap();
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use gb7_core::rewind::Rewind;
+
+use common::blargg;
+
+#[test]
+fn rewinds_through_captured_states() {
+    let mut gb = blargg("09-op r,r");
+    let mut rewind = Rewind::new(2, usize::MAX);
+    let mut states = Vec::new();
+    for frame in 1..=20 {
+        gb.execute_frame();
+        rewind.frame(&gb);
+        // The first frame is captured straight away, then every other one
+        if frame == 1 || frame % 2 == 1 {
+            states.push(gb.save_state());
+        }
+    }
+    assert_eq!(rewind.len(), states.len());
+
+    // Mid-interval, so the first step goes back to the last capture
+    gb.execute_frame();
+    rewind.frame(&gb);
+    while let Some(expected) = states.pop() {
+        assert!(rewind.rewind(&mut gb).unwrap());
+        assert_eq!(gb.save_state(), expected);
+    }
+    assert!(!rewind.rewind(&mut gb).unwrap());
+    assert_eq!(rewind.len(), 1);
+}
+
+#[test]
+fn resumes_capturing_after_rewinding() {
+    let mut gb = blargg("09-op r,r");
+    let mut rewind = Rewind::new(1, usize::MAX);
+    (0..5).for_each(|_| {
+        gb.execute_frame();
+        rewind.frame(&gb);
+    });
+    rewind.rewind(&mut gb).unwrap();
+    let earlier = gb.save_state();
+
+    gb.execute_frame();
+    rewind.frame(&gb);
+    assert_eq!(rewind.len(), 5);
+    rewind.rewind(&mut gb).unwrap();
+    assert_eq!(gb.save_state(), earlier);
+}
+
+#[test]
+fn states_are_delta_compressed() {
+    let mut gb = blargg("09-op r,r");
+    let mut rewind = Rewind::new(1, usize::MAX);
+    let state_len = gb.save_state().len();
+    (0..60).for_each(|_| {
+        gb.execute_frame();
+        rewind.frame(&gb);
+    });
+    assert_eq!(rewind.len(), 60);
+    assert!(rewind.memory_used() < state_len * 10, "used {} bytes", rewind.memory_used());
+}
+
+#[test]
+fn oldest_states_are_dropped_over_budget() {
+    let mut gb = blargg("09-op r,r");
+    let budget = gb.save_state().len() + 20_000;
+    let mut rewind = Rewind::new(1, budget);
+    (0..300).for_each(|_| {
+        gb.execute_frame();
+        rewind.frame(&gb);
+    });
+    assert!(rewind.memory_used() <= budget);
+    assert!(rewind.len() > 1 && rewind.len() < 300);
+
+    while rewind.rewind(&mut gb).unwrap() {}
+    assert!(gb.cycles() > 0);
+}
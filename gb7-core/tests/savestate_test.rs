@@ -0,0 +1,97 @@
+mod common;
+
+use gb7_core::{cartridge::MBC1, gameboy::Gameboy, savestate};
+
+use common::blargg;
+
+#[test]
+fn loaded_state_continues_identically() {
+    let mut gb = blargg("09-op r,r");
+    (0..30).for_each(|_| gb.execute_frame());
+    let state = gb.save_state();
+
+    (0..30).for_each(|_| gb.execute_frame());
+    let expected_frame = gb.lcd.frame().to_vec();
+    let expected_state = gb.save_state();
+
+    // Into both the same Gameboy and a fresh one
+    gb.load_state(&state).unwrap();
+    (0..30).for_each(|_| gb.execute_frame());
+    assert_eq!(gb.save_state(), expected_state);
+
+    let mut fresh = blargg("09-op r,r");
+    fresh.load_state(&state).unwrap();
+    (0..30).for_each(|_| fresh.execute_frame());
+    assert_eq!(fresh.lcd.frame(), expected_frame.as_slice());
+    assert_eq!(fresh.cycles(), gb.cycles());
+}
+
+#[test]
+fn state_from_another_rom_is_rejected() {
+    let state = blargg("09-op r,r").save_state();
+    let mut gb = common::idle_gameboy();
+    let error = gb.load_state(&state).unwrap_err();
+    assert_eq!(error.to_string(), "save state is for a different ROM");
+}
+
+#[test]
+fn bad_header_is_rejected() {
+    let mut gb = common::idle_gameboy();
+    let mut state = gb.save_state();
+    assert_eq!(savestate::read_header(&state).unwrap(), gb.rom_checksum());
+
+    state[4] = savestate::VERSION as u8 + 1;
+    assert!(gb.load_state(&state).unwrap_err().to_string().contains("version"));
+    state[0] = b'X';
+    assert_eq!(gb.load_state(&state).unwrap_err().to_string(), "not a gb7 save state");
+}
+
+#[test]
+fn truncated_state_leaves_gameboy_untouched() {
+    let mut gb = common::idle_gameboy();
+    let state = gb.save_state();
+    gb.execute_frame();
+    let before = gb.save_state();
+
+    let error = gb.load_state(&state[..state.len() - 4]).unwrap_err();
+    assert_eq!(error.to_string(), "save state is truncated");
+    assert_eq!(gb.save_state(), before);
+
+    let mut padded = state.clone();
+    padded.push(0);
+    assert!(gb.load_state(&padded).is_err());
+    assert_eq!(gb.save_state(), before);
+}
+
+#[test]
+fn mbc1_banking_is_restored() {
+    // LD A,3; LD (0x2000),A; JR -2
+    let mut rom = vec![0; 0x10000];
+    rom[0x0147] = 0x03;
+    rom[0x0100..0x0107].copy_from_slice(&[0x3E, 0x03, 0xEA, 0x00, 0x20, 0x18, 0xFE]);
+    rom[0xC000] = 0x42;
+    let mut gb = Gameboy::new_dmg(MBC1::new(&rom, 8192).into());
+    gb.execute_frame();
+    let state = gb.save_state();
+
+    let mut fresh = Gameboy::new_dmg(MBC1::new(&rom, 8192).into());
+    fresh.load_state(&state).unwrap();
+    assert_eq!(fresh.rom_bank(0x4000), Some(3));
+    assert_eq!(fresh.read(0x4000), 0x42);
+}
+
+#[test]
+fn missing_ram_bank_is_rejected() {
+    // LD A,2; LD (0x4000),A; JR -2, selecting RAM bank 2 on a 1 MiB cartridge
+    let mut rom = vec![0; 0x100000];
+    rom[0x0147] = 0x03;
+    rom[0x0100..0x0107].copy_from_slice(&[0x3E, 0x02, 0xEA, 0x00, 0x40, 0x18, 0xFE]);
+    let mut gb = Gameboy::new_dmg(MBC1::new(&rom, 8192).into());
+    gb.execute_frame();
+    let state = gb.save_state();
+
+    // Only 8 KiB of RAM, so there is no bank 2 to restore
+    let mut fresh = Gameboy::new_dmg(MBC1::new(&rom, 8192).into());
+    let error = fresh.load_state(&state).unwrap_err();
+    assert_eq!(error.to_string(), "save state selects a bank the cartridge doesn't have");
+}
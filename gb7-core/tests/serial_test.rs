@@ -0,0 +1,86 @@
+mod common;
+
+use gb7_core::serial::{CaptureBuffer, SerialDevice};
+
+use common::idle_gameboy;
+
+// Replies to each byte with its complement
+struct Inverter;
+
+impl SerialDevice for Inverter {
+    fn exchange(&mut self, byte: u8) -> u8 {
+        !byte
+    }
+}
+
+#[test]
+fn internal_clock_transfer_takes_1024_m_cycles() {
+    let mut gb = idle_gameboy();
+    gb.serial.connect(Box::new(Inverter));
+    gb.write(0xFF0F, 0);
+    gb.write(0xFF01, 0x3C);
+    gb.write(0xFF02, 0x81);
+
+    let mut cycles = 0;
+    while gb.read(0xFF02) & 0x80 != 0 {
+        assert_eq!(gb.read(0xFF0F) & 0b1000, 0);
+        cycles += gb.execute() as u32;
+    }
+
+    // JR takes 3 M-cycles, so the transfer ends within one instruction of 1024
+    assert!((1024..1027).contains(&cycles), "{}", cycles);
+    assert_eq!(gb.read(0xFF01), 0xC3);
+    assert_eq!(gb.read(0xFF0F) & 0b1000, 0b1000);
+}
+
+#[test]
+fn disconnected_port_reads_ff() {
+    let mut gb = idle_gameboy();
+    gb.write(0xFF01, 0x12);
+    gb.write(0xFF02, 0x81);
+    while gb.read(0xFF02) & 0x80 != 0 {
+        gb.execute();
+    }
+    assert_eq!(gb.read(0xFF01), 0xFF);
+}
+
+#[test]
+fn external_clock_waits_for_other_end() {
+    let mut gb = idle_gameboy();
+    gb.serial.connect(Box::new(CaptureBuffer::default()));
+    gb.write(0xFF0F, 0);
+    gb.write(0xFF01, 0x55);
+    gb.write(0xFF02, 0x80);
+
+    for _ in 0..2000 {
+        gb.execute();
+    }
+    assert_eq!(gb.read(0xFF02) & 0x80, 0x80);
+    assert!(gb.serial.device::<CaptureBuffer>().unwrap().data.is_empty());
+
+    assert_eq!(gb.serial.receive(&mut gb.io_regs, 0xAA), 0x55);
+    assert_eq!(gb.read(0xFF01), 0xAA);
+    assert_eq!(gb.read(0xFF02) & 0x80, 0);
+    assert_eq!(gb.read(0xFF0F) & 0b1000, 0b1000);
+
+    // Nothing waiting now
+    assert_eq!(gb.serial.receive(&mut gb.io_regs, 0x11), 0xFF);
+    assert_eq!(gb.read(0xFF01), 0xAA);
+}
+
+#[test]
+fn capture_buffer_records_bytes() {
+    let mut gb = idle_gameboy();
+    gb.serial.connect(Box::new(CaptureBuffer::default()));
+
+    for byte in b"OK" {
+        gb.write(0xFF01, *byte);
+        gb.write(0xFF02, 0x81);
+        while gb.read(0xFF02) & 0x80 != 0 {
+            gb.execute();
+        }
+    }
+
+    assert_eq!(gb.serial.device::<CaptureBuffer>().unwrap().data, b"OK");
+    assert!(gb.serial.device::<Inverter>().is_none());
+}
@@ -0,0 +1,217 @@
+mod common;
+
+use gb7_core::{
+    cartridge::{Cartridge, NoMBC},
+    gameboy::Gameboy,
+    joypad::JoypadButton,
+    postprocess::{ColorCorrection, PostProcess},
+    sgb::{self, SGB_WIDTH},
+};
+
+use common::{run_until_line, run_until_vblank};
+
+fn sgb_cart(sgb_flag: bool) -> Cartridge {
+    let mut rom = vec![0; 0x8000];
+    rom[0x0100..0x0102].copy_from_slice(&[0x18, 0xFE]);
+    if sgb_flag {
+        rom[0x0146] = 0x03;
+        rom[0x014B] = 0x33;
+    }
+    NoMBC::new(&rom).into()
+}
+
+fn sgb_gameboy() -> Gameboy {
+    let mut gb = Gameboy::new_sgb(sgb_cart(true));
+    // Every background pixel is shade 3
+    gb.write(0xFF47, 0xFF);
+    gb.write(0xFF40, 0b1001_0001);
+    run_until_line(&mut gb, 144);
+    gb
+}
+
+fn send_packet(gb: &mut Gameboy, packet: [u8; 16]) {
+    gb.write(0xFF00, 0x00);
+    gb.write(0xFF00, 0x30);
+    for byte in packet {
+        for bit in 0..8 {
+            gb.write(0xFF00, if byte & (1 << bit) != 0 { 0x10 } else { 0x20 });
+            gb.write(0xFF00, 0x30);
+        }
+    }
+    // Stop bit
+    gb.write(0xFF00, 0x20);
+    gb.write(0xFF00, 0x30);
+}
+
+fn command(id: u8, data: &[u8]) -> [u8; 16] {
+    let mut packet = [0; 16];
+    packet[0] = (id << 3) | 1;
+    packet[1..1 + data.len()].copy_from_slice(data);
+    packet
+}
+
+fn pixel(gb: &Gameboy, x: usize, y: usize) -> [u8; 4] {
+    let frame = gb.sgb.as_ref().unwrap().frame();
+    let i = (y * SGB_WIDTH + x) * 4;
+    frame[i..i + 4].try_into().unwrap()
+}
+
+// PAL01 with colour 0 black, palette 0 colour 3 pure red and palette 1 colour 3 pure blue
+fn send_red_blue_palettes(gb: &mut Gameboy) {
+    send_packet(
+        gb,
+        command(0x00, &[0x00, 0x00, 0, 0, 0, 0, 0x1F, 0x00, 0, 0, 0, 0, 0x00, 0x7C]),
+    );
+}
+
+#[test]
+fn detects_sgb_header() {
+    assert!(sgb::supports_sgb(&sgb_cart(true)));
+    assert!(!sgb::supports_sgb(&sgb_cart(false)));
+}
+
+#[test]
+fn palette_commands_color_the_screen() {
+    let mut gb = sgb_gameboy();
+    send_red_blue_palettes(&mut gb);
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 48, 40), [255, 0, 0, 255]);
+    assert_eq!(pixel(&gb, 48 + 159, 40 + 143), [255, 0, 0, 255]);
+}
+
+#[test]
+fn post_process_applies_to_sgb_frame() {
+    let mut gb = sgb_gameboy();
+    send_red_blue_palettes(&mut gb);
+    gb.sgb.as_mut().unwrap().set_post_process(PostProcess::new(Some(ColorCorrection::CGB), None));
+    run_until_vblank(&mut gb);
+
+    let [r, g, b] = ColorCorrection::CGB.correct([255, 0, 0]);
+    assert_eq!(pixel(&gb, 48, 40), [r, g, b, 255]);
+}
+
+#[test]
+fn attr_blk_assigns_palettes_to_tiles() {
+    let mut gb = sgb_gameboy();
+    send_red_blue_palettes(&mut gb);
+    // One data set: inside only, palette 1, tiles (2,2)-(4,4)
+    send_packet(&mut gb, command(0x04, &[1, 0b001, 0b01, 2, 2, 4, 4]));
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 48 + 3 * 8, 40 + 3 * 8), [0, 0, 255, 255]);
+    // Inside-only also colours the border of the block
+    assert_eq!(pixel(&gb, 48 + 2 * 8, 40 + 2 * 8), [0, 0, 255, 255]);
+    assert_eq!(pixel(&gb, 48 + 5 * 8, 40 + 5 * 8), [255, 0, 0, 255]);
+}
+
+#[test]
+fn attr_lin_and_div() {
+    let mut gb = sgb_gameboy();
+    send_red_blue_palettes(&mut gb);
+    // Everything right of column 10 uses palette 1
+    send_packet(&mut gb, command(0x06, &[0b0000_0001, 10]));
+    // Row 0 uses palette 1
+    send_packet(&mut gb, command(0x05, &[1, 0b1010_0000]));
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 48, 40), [0, 0, 255, 255]);
+    assert_eq!(pixel(&gb, 48, 40 + 8), [255, 0, 0, 255]);
+    assert_eq!(pixel(&gb, 48 + 11 * 8, 40 + 8), [0, 0, 255, 255]);
+}
+
+#[test]
+fn mask_en_blanks_screen() {
+    let mut gb = sgb_gameboy();
+    send_red_blue_palettes(&mut gb);
+    send_packet(&mut gb, command(0x17, &[2]));
+    run_until_vblank(&mut gb);
+    assert_eq!(pixel(&gb, 100, 100), [0, 0, 0, 255]);
+
+    send_packet(&mut gb, command(0x17, &[0]));
+    run_until_vblank(&mut gb);
+    assert_eq!(pixel(&gb, 100, 100), [255, 0, 0, 255]);
+}
+
+#[test]
+fn border_transfer() {
+    let mut gb = sgb_gameboy();
+    // Map the first 256 screen tiles in order onto tile data at 0x8000,
+    // so the transferred data is exactly 0x8000-0x8FFF
+    for i in 0..256u16 {
+        gb.write(0x9800 + (i / 20) * 32 + i % 20, i as u8);
+    }
+
+    // Border tile 0 has colour 1 across its top row
+    gb.write(0x8000, 0xFF);
+    send_packet(&mut gb, command(0x13, &[0]));
+    run_until_vblank(&mut gb);
+
+    // Border map entry 0 uses tile 0 with palette 4, whose colour 1 is green
+    gb.write(0x8000, 0x00);
+    gb.write(0x8001, 0x10);
+    gb.write(0x8802, 0xE0);
+    gb.write(0x8803, 0x03);
+    send_packet(&mut gb, command(0x14, &[]));
+    run_until_vblank(&mut gb);
+    run_until_vblank(&mut gb);
+
+    assert_eq!(pixel(&gb, 0, 0), [0, 255, 0, 255]);
+    assert_eq!(pixel(&gb, 7, 0), [0, 255, 0, 255]);
+    assert_ne!(pixel(&gb, 0, 1), [0, 255, 0, 255]);
+}
+
+#[test]
+fn mlt_req_reports_joypad_ids() {
+    let mut gb = sgb_gameboy();
+
+    gb.write(0xFF00, 0x30);
+    gb.execute();
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0x0F);
+
+    send_packet(&mut gb, command(0x11, &[1]));
+    gb.execute();
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0x0F);
+
+    gb.write(0xFF00, 0x10);
+    gb.write(0xFF00, 0x30);
+    gb.execute();
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0x0E);
+
+    gb.write(0xFF00, 0x20);
+    gb.write(0xFF00, 0x30);
+    gb.execute();
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0x0F);
+}
+
+#[test]
+fn held_button_on_other_player_requests_one_interrupt() {
+    let mut gb = sgb_gameboy();
+    send_packet(&mut gb, command(0x11, &[1]));
+    // Move on to the second joypad, which reads as nothing pressed
+    gb.write(0xFF00, 0x10);
+    gb.write(0xFF00, 0x30);
+
+    gb.joypad.press(JoypadButton::Right);
+    gb.write(0xFF00, 0x20);
+    gb.execute();
+    gb.write(0xFF0F, 0x00);
+    for _ in 0..10 {
+        gb.execute();
+    }
+    assert_eq!(gb.read(0xFF00) & 0x0F, 0x0F);
+    assert_eq!(gb.read(0xFF0F) & 0x10, 0);
+}
+
+#[test]
+fn commands_ignored_without_sgb_header() {
+    let mut gb = Gameboy::new_sgb(sgb_cart(false));
+    gb.write(0xFF47, 0xFF);
+    gb.write(0xFF40, 0b1001_0001);
+    run_until_line(&mut gb, 144);
+
+    send_red_blue_palettes(&mut gb);
+    run_until_vblank(&mut gb);
+
+    assert_ne!(pixel(&gb, 48, 40), [255, 0, 0, 255]);
+}
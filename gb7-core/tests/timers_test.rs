@@ -0,0 +1,150 @@
+use gb7_core::{memory::IORegs, timers::Timers};
+
+// A running timer with TAC selecting 262144 Hz (TIMA ticks every 4 M-cycles)
+fn fast_timer() -> (Timers, IORegs) {
+    let mut timers = Timers::default();
+    let mut io_regs = IORegs::default();
+    timers.write(&mut io_regs, 0xFF07, 0b101);
+    (timers, io_regs)
+}
+
+#[test]
+fn div_increments_every_64_m_cycles() {
+    let mut timers = Timers::default();
+    let mut io_regs = IORegs::default();
+
+    timers.tick(&mut io_regs, 63);
+    assert_eq!(io_regs.read(0xFF04), 0);
+    timers.tick(&mut io_regs, 1);
+    assert_eq!(io_regs.read(0xFF04), 1);
+    for _ in 0..4 {
+        timers.tick(&mut io_regs, 64);
+    }
+    assert_eq!(io_regs.read(0xFF04), 5);
+}
+
+#[test]
+fn div_write_resets_system_counter() {
+    let mut timers = Timers::default();
+    let mut io_regs = IORegs::default();
+
+    timers.tick(&mut io_regs, 100);
+    timers.write(&mut io_regs, 0xFF04, 0x55);
+    assert_eq!(io_regs.read(0xFF04), 0);
+    assert_eq!(timers.system_counter(), 0);
+
+    // The next DIV increment is a full 64 M-cycles away
+    timers.tick(&mut io_regs, 63);
+    assert_eq!(io_regs.read(0xFF04), 0);
+    timers.tick(&mut io_regs, 1);
+    assert_eq!(io_regs.read(0xFF04), 1);
+}
+
+#[test]
+fn tima_increments_at_selected_rate() {
+    let (mut timers, mut io_regs) = fast_timer();
+
+    timers.tick(&mut io_regs, 3);
+    assert_eq!(io_regs.read(0xFF05), 0);
+    timers.tick(&mut io_regs, 1);
+    assert_eq!(io_regs.read(0xFF05), 1);
+    timers.tick(&mut io_regs, 40);
+    assert_eq!(io_regs.read(0xFF05), 11);
+
+    // 4096 Hz ticks every 256 M-cycles
+    timers.write(&mut io_regs, 0xFF04, 0);
+    timers.write(&mut io_regs, 0xFF05, 0);
+    timers.write(&mut io_regs, 0xFF07, 0b100);
+    timers.tick(&mut io_regs, 128);
+    timers.tick(&mut io_regs, 127);
+    assert_eq!(io_regs.read(0xFF05), 0);
+    timers.tick(&mut io_regs, 1);
+    assert_eq!(io_regs.read(0xFF05), 1);
+}
+
+#[test]
+fn disabled_timer_does_not_tick() {
+    let mut timers = Timers::default();
+    let mut io_regs = IORegs::default();
+    timers.write(&mut io_regs, 0xFF07, 0b001);
+
+    timers.tick(&mut io_regs, 200);
+    assert_eq!(io_regs.read(0xFF05), 0);
+}
+
+#[test]
+fn div_reset_with_selected_bit_high_increments_tima() {
+    let (mut timers, mut io_regs) = fast_timer();
+
+    // Bit 3 of the counter is high after 2 M-cycles
+    timers.tick(&mut io_regs, 2);
+    timers.write(&mut io_regs, 0xFF04, 0);
+    assert_eq!(io_regs.read(0xFF05), 1);
+
+    // Bit 3 low, no extra increment
+    timers.tick(&mut io_regs, 1);
+    timers.write(&mut io_regs, 0xFF04, 0);
+    assert_eq!(io_regs.read(0xFF05), 1);
+}
+
+#[test]
+fn tac_change_glitch_increments_tima() {
+    let (mut timers, mut io_regs) = fast_timer();
+
+    // Disabling the timer while the selected bit is high
+    timers.tick(&mut io_regs, 2);
+    timers.write(&mut io_regs, 0xFF07, 0b001);
+    assert_eq!(io_regs.read(0xFF05), 1);
+
+    // Switching from a high bit (3) to a low bit (9)
+    timers.write(&mut io_regs, 0xFF07, 0b101);
+    timers.write(&mut io_regs, 0xFF07, 0b100);
+    assert_eq!(io_regs.read(0xFF05), 2);
+}
+
+#[test]
+fn tima_overflow_reloads_after_one_m_cycle() {
+    let (mut timers, mut io_regs) = fast_timer();
+    timers.write(&mut io_regs, 0xFF06, 0xAB);
+    timers.write(&mut io_regs, 0xFF05, 0xFF);
+
+    timers.tick(&mut io_regs, 4);
+    assert_eq!(io_regs.read(0xFF05), 0);
+    assert_eq!(io_regs.read(0xFF0F) & 0b100, 0);
+
+    timers.tick(&mut io_regs, 1);
+    assert_eq!(io_regs.read(0xFF05), 0xAB);
+    assert_eq!(io_regs.read(0xFF0F) & 0b100, 0b100);
+}
+
+#[test]
+fn tima_write_during_overflow_delay_cancels_reload() {
+    let (mut timers, mut io_regs) = fast_timer();
+    timers.write(&mut io_regs, 0xFF06, 0xAB);
+    timers.write(&mut io_regs, 0xFF05, 0xFF);
+
+    timers.tick(&mut io_regs, 4);
+    timers.write(&mut io_regs, 0xFF05, 0x12);
+    timers.tick(&mut io_regs, 1);
+    assert_eq!(io_regs.read(0xFF05), 0x12);
+    assert_eq!(io_regs.read(0xFF0F) & 0b100, 0);
+}
+
+#[test]
+fn writes_on_reload_cycle() {
+    let (mut timers, mut io_regs) = fast_timer();
+    timers.write(&mut io_regs, 0xFF06, 0xAB);
+    timers.write(&mut io_regs, 0xFF05, 0xFF);
+    timers.tick(&mut io_regs, 5);
+
+    // TIMA writes are ignored, TMA writes are copied into TIMA
+    timers.write(&mut io_regs, 0xFF05, 0x12);
+    assert_eq!(io_regs.read(0xFF05), 0xAB);
+    timers.write(&mut io_regs, 0xFF06, 0x34);
+    assert_eq!(io_regs.read(0xFF05), 0x34);
+
+    // Back to normal on the next M-cycle
+    timers.tick(&mut io_regs, 1);
+    timers.write(&mut io_regs, 0xFF06, 0x56);
+    assert_eq!(io_regs.read(0xFF05), 0x34);
+}
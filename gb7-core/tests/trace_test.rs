@@ -0,0 +1,138 @@
+mod common;
+
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{self, BufReader, Cursor, Write},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use common::{blargg, test_cart};
+use gb7_core::{
+    gameboy::Gameboy,
+    trace::{self, TraceBuffer, TraceFormat, TraceLogger},
+};
+use test_case::test_case;
+
+// Trace the first `count` instructions
+fn trace(gb: &mut Gameboy, format: TraceFormat, count: usize) -> Vec<String> {
+    gb.tracer = Some(Box::new(TraceBuffer::new(format)));
+    let mut lines = Vec::new();
+    while lines.len() < count {
+        gb.execute();
+        let tracer = gb.tracer.as_mut().unwrap().as_mut() as &mut dyn std::any::Any;
+        lines.append(&mut tracer.downcast_mut::<TraceBuffer>().unwrap().lines);
+    }
+    gb.tracer = None;
+    lines
+}
+
+#[test]
+fn doctor_format_matches_post_boot_state() {
+    let lines = trace(&mut blargg("06-ld r,r"), TraceFormat::Doctor, 2);
+    assert_eq!(lines[0], "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02");
+    assert_eq!(lines[1], "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0101 PCMEM:C3,13,02,CE");
+}
+
+#[test]
+fn rich_format_shows_disassembly_and_cycles() {
+    // LD A,0x42; JR -2
+    let mut gb = Gameboy::new_dmg(test_cart(&[0x3E, 0x42, 0x18, 0xFE]));
+    let lines = trace(&mut gb, TraceFormat::Rich, 3);
+    assert_eq!(
+        lines[1],
+        "00:0102  JR $0102           A:42 F:Z-HC BC:0013 DE:00D8 HL:014D SP:FFFE LY:00 IME:0 CYC:2"
+    );
+    assert!(lines[2].ends_with("CYC:5"));
+}
+
+#[test]
+fn stubbed_ly_reads_0x90() {
+    let mut gb = common::idle_gameboy();
+    gb.stub_ly = true;
+    assert_eq!(gb.read(0xFF44), 0x90);
+    gb.stub_ly = false;
+    assert_eq!(gb.read(0xFF44), 0x00);
+}
+
+#[test]
+fn compare_log_finds_first_mismatch() {
+    let reference = trace(&mut blargg("06-ld r,r"), TraceFormat::Doctor, 1000);
+
+    let log = reference.join("\n");
+    let result = trace::compare_log(&mut blargg("06-ld r,r"), Cursor::new(log), TraceFormat::Doctor).unwrap();
+    assert_eq!(result, Ok(1000));
+
+    let mut altered = reference.clone();
+    altered[600] = altered[600].replace("A:", "A:F");
+    let mismatch = trace::compare_log(&mut blargg("06-ld r,r"), Cursor::new(altered.join("\n")), TraceFormat::Doctor)
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(mismatch.line, 601);
+    assert_eq!(mismatch.actual, reference[600]);
+    assert_eq!(mismatch.context.last(), Some(&reference[599]));
+}
+
+// Fails every write after the first `limit`, counting the attempts
+struct FailingWriter {
+    limit: usize,
+    writes: Rc<Cell<usize>>,
+}
+
+impl Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes.set(self.writes.get() + 1);
+        match self.writes.get() > self.limit {
+            true => Err(io::Error::other("disk full")),
+            false => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn logger_keeps_first_error_and_stops_writing() {
+    let writes = Rc::new(Cell::new(0));
+    let mut gb = blargg("06-ld r,r");
+    gb.tracer = Some(Box::new(TraceLogger::new(FailingWriter { limit: 10, writes: writes.clone() }, TraceFormat::Doctor)));
+    for _ in 0..100 {
+        gb.execute();
+    }
+    assert_eq!(writes.get(), 11);
+
+    let tracer = gb.tracer.as_mut().unwrap().as_mut() as &mut dyn std::any::Any;
+    let logger = tracer.downcast_mut::<TraceLogger>().unwrap();
+    assert_eq!(logger.error().unwrap().to_string(), "disk full");
+    assert_eq!(logger.finish().unwrap_err().to_string(), "disk full");
+}
+
+// Diff against gameboy-doctor's reference logs, which are too large to bundle. Put them
+// in resources/gameboy-doctor/ named after the ROM, e.g. "01-special.log", then run these
+// with `cargo test -- --ignored`.
+#[test_case("01-special" ; "special")]
+#[test_case("02-interrupts" ; "interrupts")]
+#[test_case("03-op sp,hl" ; "sp,hl")]
+#[test_case("04-op r,imm" ; "r,imm")]
+#[test_case("05-op rp" ; "rp")]
+#[test_case("06-ld r,r" ; "ld r,r")]
+#[test_case("07-jr,jp,call,ret,rst" ; "jr,jp,call,ret,rst")]
+#[test_case("08-misc instrs" ; "misc")]
+#[test_case("09-op r,r" ; "op r,r")]
+#[test_case("10-bit ops" ; "bit ops")]
+#[test_case("11-op a,(hl)" ; "op a,hl")]
+#[ignore = "needs gameboy-doctor logs in resources/gameboy-doctor"]
+fn gameboy_doctor(test_name: &str) {
+    let mut log_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    log_path.push(format!("resources/gameboy-doctor/{}.log", test_name));
+    let log = File::open(&log_path).unwrap_or_else(|e| panic!("no reference log at {}: {}", log_path.display(), e));
+
+    let mut gb = blargg(test_name);
+    gb.stub_ly = true;
+    if let Err(mismatch) = trace::compare_log(&mut gb, BufReader::new(log), TraceFormat::Doctor).unwrap() {
+        panic!("{}", mismatch);
+    }
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use std::{cell::RefCell, rc::Rc};
+
+use common::{test_cart, test_cart_sections};
+use gb7_core::{
+    cartridge::MBC1,
+    gameboy::Gameboy,
+    watch::{Access, DebugEvent, WatchCondition, Watches, Watchpoint},
+};
+
+// Install watches that collect every event
+fn watch(gb: &mut Gameboy) -> Rc<RefCell<Vec<DebugEvent>>> {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let sink = events.clone();
+    gb.watches = Some(Watches::new(move |event| sink.borrow_mut().push(*event)));
+    events
+}
+
+fn run(gb: &mut Gameboy, instructions: usize) {
+    (0..instructions).for_each(|_| {
+        gb.execute();
+    });
+}
+
+#[test]
+fn watchpoints_report_cpu_accesses() {
+    // LD HL,0xC000; LD (HL),1; LD A,(HL); LD (HL),1; JR -2
+    let mut gb = Gameboy::new_dmg(test_cart(&[0x21, 0x00, 0xC0, 0x36, 0x01, 0x7E, 0x36, 0x01, 0x18, 0xFE]));
+    let events = watch(&mut gb);
+    let watches = gb.watches.as_mut().unwrap();
+    let write = watches.add(Watchpoint::new(0xC000..=0xC0FF, &[Access::Write]));
+    let read = watches.add(Watchpoint::new(0xC000..=0xC000, &[Access::Read]));
+    let jump = watches.add(Watchpoint::new(0x0108..=0x0108, &[Access::Execute]));
+
+    run(&mut gb, 5);
+    assert_eq!(
+        *events.borrow(),
+        [
+            DebugEvent::Watchpoint { id: write, access: Access::Write, addr: 0xC000, value: 1 },
+            DebugEvent::Watchpoint { id: read, access: Access::Read, addr: 0xC000, value: 1 },
+            DebugEvent::Watchpoint { id: write, access: Access::Write, addr: 0xC000, value: 1 },
+            DebugEvent::Watchpoint { id: jump, access: Access::Execute, addr: 0x0108, value: 0x18 },
+        ]
+    );
+
+    // Accesses through the Gameboy are watched too, but not peeks and pokes
+    gb.peek(0xC000);
+    gb.poke(0xC000, 2);
+    assert_eq!(events.borrow().len(), 4);
+    gb.read(0xC000);
+    gb.write(0xC000, 3);
+    assert_eq!(
+        events.borrow()[4..],
+        [
+            DebugEvent::Watchpoint { id: read, access: Access::Read, addr: 0xC000, value: 2 },
+            DebugEvent::Watchpoint { id: write, access: Access::Write, addr: 0xC000, value: 3 },
+        ]
+    );
+}
+
+#[test]
+fn conditions_filter_hits() {
+    // LD HL,0xC000; LD (HL),1; LD (HL),1; LD (HL),2; JR -2
+    let mut gb = Gameboy::new_dmg(test_cart(&[0x21, 0x00, 0xC0, 0x36, 0x01, 0x36, 0x01, 0x36, 0x02, 0x18, 0xFE]));
+    let events = watch(&mut gb);
+    let watches = gb.watches.as_mut().unwrap();
+    let changed = watches.add(Watchpoint::new(0xC000..=0xC000, &[Access::Write]).with_condition(WatchCondition::Changed));
+    let equals = watches.add(Watchpoint::new(0xC000..=0xC000, &[Access::Write]).with_condition(WatchCondition::Equals(2)));
+
+    run(&mut gb, 4);
+    let ids: Vec<usize> = events
+        .borrow()
+        .iter()
+        .map(|event| match event {
+            DebugEvent::Watchpoint { id, .. } => *id,
+            _ => unreachable!(),
+        })
+        .collect();
+    assert_eq!(ids, [changed, changed, equals]);
+}
+
+#[test]
+fn writes_that_dont_land_are_not_reported() {
+    let mut gb = common::idle_gameboy();
+    // LD A,0xC0; LDH (0x46),A; NOP; NOP; LD (HL),A; LD A,5; LDH (0x44),A; JR -2, run from
+    // high RAM so OAM DMA doesn't cut off the program
+    for (i, &byte) in [0x3E, 0xC0, 0xE0, 0x46, 0x00, 0x00, 0x77, 0x3E, 0x05, 0xE0, 0x44, 0x18, 0xFE].iter().enumerate() {
+        gb.poke(0xFF80 + i as u16, byte);
+    }
+    gb.cpu.pc = 0xFF80;
+    gb.cpu.registers.set_hl(0xC100);
+    let events = watch(&mut gb);
+    let watches = gb.watches.as_mut().unwrap();
+    watches.add(Watchpoint::new(0xC100..=0xC100, &[Access::Write]));
+    let ly = watches.add(Watchpoint::new(0xFF44..=0xFF44, &[Access::Write]));
+    watches.add(Watchpoint::new(0xFF44..=0xFF44, &[Access::Write]).with_condition(WatchCondition::Changed));
+
+    // The write during OAM DMA is lost, and LY is read-only so the write doesn't change it
+    run(&mut gb, 7);
+    assert_eq!(gb.peek(0xC100), 0);
+    assert_eq!(
+        *events.borrow(),
+        [DebugEvent::Watchpoint { id: ly, access: Access::Write, addr: 0xFF44, value: 5 }]
+    );
+}
+
+#[test]
+fn bank_condition_matches_mapped_bank() {
+    // LD A,2; LD (0x2000),A; LD A,(0x4000); LD A,3; LD (0x2000),A; LD A,(0x4000); JR -2
+    let mut rom = vec![0; 0x10000];
+    rom[0x0147] = 0x01;
+    rom[0x0100..0x0112].copy_from_slice(&[
+        0x3E, 0x02, 0xEA, 0x00, 0x20, 0xFA, 0x00, 0x40, 0x3E, 0x03, 0xEA, 0x00, 0x20, 0xFA, 0x00, 0x40, 0x18, 0xFE,
+    ]);
+    let mut gb = Gameboy::new_dmg(MBC1::new(&rom, 0).into());
+    let events = watch(&mut gb);
+    let watches = gb.watches.as_mut().unwrap();
+    watches.add(Watchpoint::new(0x4000..=0x7FFF, &[Access::Read]).with_condition(WatchCondition::Bank(3)));
+    watches.break_on_bank_switch = true;
+
+    run(&mut gb, 6);
+    assert_eq!(
+        *events.borrow(),
+        [
+            DebugEvent::BankSwitch { from: 1, to: 2 },
+            DebugEvent::BankSwitch { from: 2, to: 3 },
+            DebugEvent::Watchpoint { id: 1, access: Access::Read, addr: 0x4000, value: 0 },
+        ]
+    );
+}
+
+#[test]
+fn reports_interrupt_line_and_dma_events() {
+    // Enable VBlank interrupts, then start OAM DMA from 0xC000 and spin. The CPU sees
+    // NOPs on the bus during the DMA, so it slides past the spin, which doesn't matter.
+    let mut gb = Gameboy::new_dmg(test_cart_sections(&[
+        (0x0040, &[0xD9]), // RETI
+        (0x0100, &[0x3E, 0x01, 0xE0, 0xFF, 0xFB, 0x3E, 0xC0, 0xE0, 0x46, 0x18, 0xFE]),
+    ]));
+    let events = watch(&mut gb);
+    let watches = gb.watches.as_mut().unwrap();
+    watches.break_on_interrupt = true;
+    watches.break_on_line = Some(0x90);
+    watches.break_on_dma = true;
+
+    gb.execute_frame();
+    gb.execute();
+    assert_eq!(
+        *events.borrow(),
+        [DebugEvent::DmaStart(0xC000), DebugEvent::Line(0x90), DebugEvent::Interrupt(0)]
+    );
+}
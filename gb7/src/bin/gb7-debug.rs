@@ -0,0 +1,58 @@
+// Command-line debugger: runs a ROM without a window under a gdb-style prompt
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use gb7_core::{
+    cartridge,
+    debugger::{Command, Debugger},
+    disasm::Syntax,
+    gameboy::Gameboy,
+};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let cart_path = args.iter().skip(1).find(|arg| !arg.starts_with("--")).expect("no cartridge path given");
+    let cartridge = cartridge::load_from_path(Path::new(cart_path));
+    let mut gameboy = match args.iter().any(|arg| arg == "--sgb") {
+        true => Gameboy::new_sgb(cartridge),
+        false => Gameboy::new_dmg(cartridge),
+    };
+    gameboy.cycle_accurate = args.iter().any(|arg| arg == "--accurate");
+
+    let mut debugger = Debugger::new();
+    if args.iter().any(|arg| arg == "--rgbds") {
+        debugger.syntax = Syntax::Rgbds;
+    }
+    println!("{}", debugger.execute(&mut gameboy, Command::Disassemble(None, 1)).unwrap());
+
+    // An empty line repeats the last command, handy for stepping
+    let mut last_line = String::new();
+    let stdin = io::stdin();
+    loop {
+        print!("(gb7) ");
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            line = last_line.clone();
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match Command::parse(&line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => match debugger.execute(&mut gameboy, command) {
+                Ok(output) => println!("{}", output),
+                Err(e) => println!("error: {}", e),
+            },
+            Err(e) => println!("error: {}", e),
+        }
+        last_line = line;
+    }
+}
@@ -0,0 +1,112 @@
+use gb7_core::{debug, debug::DebugImage, gameboy::Gameboy};
+use pixels::{Pixels, SurfaceTexture};
+use winit::{
+    dpi::LogicalSize,
+    event::WindowEvent,
+    event_loop::EventLoopWindowTarget,
+    window::{Window, WindowBuilder, WindowId},
+};
+
+#[derive(Clone, Copy)]
+enum DebugViewKind {
+    Tiles,
+    Map9800,
+    Map9C00,
+    WindowMap,
+    Oam,
+}
+
+impl DebugViewKind {
+    fn title(&self) -> &'static str {
+        match self {
+            DebugViewKind::Tiles => "gb7 - Tiles",
+            DebugViewKind::Map9800 => "gb7 - BG Map 9800",
+            DebugViewKind::Map9C00 => "gb7 - BG Map 9C00",
+            DebugViewKind::WindowMap => "gb7 - Window Map",
+            DebugViewKind::Oam => "gb7 - OAM",
+        }
+    }
+
+    fn render(&self, gameboy: &Gameboy) -> DebugImage {
+        let palette = gameboy.lcd.palette();
+        match self {
+            DebugViewKind::Tiles => debug::tile_sheet(&gameboy.vram, &palette),
+            DebugViewKind::Map9800 => debug::tile_map(&gameboy.vram, &gameboy.io_regs, 0x9800, &palette),
+            DebugViewKind::Map9C00 => debug::tile_map(&gameboy.vram, &gameboy.io_regs, 0x9C00, &palette),
+            DebugViewKind::WindowMap => debug::window_map(&gameboy.vram, &gameboy.io_regs, &palette),
+            DebugViewKind::Oam => debug::oam_table(&gameboy.vram, &gameboy.oam, &gameboy.io_regs, &palette),
+        }
+    }
+}
+
+struct DebugView {
+    kind: DebugViewKind,
+    window: Window,
+    pixels: Pixels,
+}
+
+// Extra windows showing VRAM tiles, tile maps and OAM, refreshed every frame
+pub struct DebugWindows {
+    views: Vec<DebugView>,
+}
+
+impl DebugWindows {
+    pub fn open(target: &EventLoopWindowTarget<()>, gameboy: &Gameboy) -> Self {
+        let kinds = [
+            DebugViewKind::Tiles,
+            DebugViewKind::Map9800,
+            DebugViewKind::Map9C00,
+            DebugViewKind::WindowMap,
+            DebugViewKind::Oam,
+        ];
+
+        let views = kinds
+            .iter()
+            .map(|&kind| {
+                let image = kind.render(gameboy);
+                let size = LogicalSize::new(image.width as f64 * 2f64, image.height as f64 * 2f64);
+                let window = WindowBuilder::new()
+                    .with_title(kind.title())
+                    .with_inner_size(size)
+                    .build(target)
+                    .unwrap();
+
+                let window_size = window.inner_size();
+                let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
+                let pixels = Pixels::new(image.width as u32, image.height as u32, surface_texture).unwrap();
+
+                DebugView { kind, window, pixels }
+            })
+            .collect();
+
+        DebugWindows { views }
+    }
+
+    pub fn owns(&self, window_id: WindowId) -> bool {
+        self.views.iter().any(|view| view.window.id() == window_id)
+    }
+
+    pub fn handle_event(&mut self, window_id: WindowId, event: &WindowEvent) {
+        match event {
+            WindowEvent::Resized(size) => {
+                if let Some(view) = self.views.iter_mut().find(|view| view.window.id() == window_id) {
+                    view.pixels.resize_surface(size.width, size.height);
+                }
+            }
+            WindowEvent::CloseRequested => {
+                self.views.retain(|view| view.window.id() != window_id);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn redraw(&mut self, gameboy: &Gameboy) {
+        for view in self.views.iter_mut() {
+            let image = view.kind.render(gameboy);
+            view.pixels.get_frame_mut().copy_from_slice(&image.data);
+            if let Err(e) = view.pixels.render() {
+                panic!("pixels.render() failed: {}", e);
+            }
+        }
+    }
+}
@@ -1,7 +1,15 @@
-use std::{env, path::Path, time::{Instant, Duration}};
+mod debug_windows;
+
+use std::{any::Any, env, fs, path::{Path, PathBuf}, time::{Instant, Duration}};
 use std::cmp::min;
 
-use gb7_core::{cartridge, gameboy::Gameboy, lcd::Lcd, joypad::JoypadButton};
+use debug_windows::DebugWindows;
+
+use gb7_core::{cartridge, gameboy::Gameboy, lcd::{self, Palette}, joypad::JoypadButton, link::LinkCable, netlink::TcpLink, printer::Printer, serial::Disconnected, sgb};
+use gb7_core::movie::{Movie, MoviePlayer, MovieRecorder, MovieStart};
+use gb7_core::rewind::Rewind;
+use gb7_core::trace::{TraceFormat, TraceLogger};
+use gb7_core::postprocess::{ColorCorrection, PostProcess};
 use pixels::{Pixels, SurfaceTexture};
 use winit::{
     dpi::LogicalSize,
@@ -9,15 +17,34 @@ use winit::{
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
 };
-use winit::event::{ElementState, Event, WindowEvent};
+use winit::event::{ElementState, Event, ModifiersState, WindowEvent};
 
 const WIDTH: u32 = 160;
 const HEIGHT: u32 = 144;
+const SGB_WIDTH: u32 = sgb::SGB_WIDTH as u32;
+const SGB_HEIGHT: u32 = sgb::SGB_HEIGHT as u32;
 const TARGET_FPS: u32 = 60;
 
+// Hold to rewind
+const REWIND_KEY: VirtualKeyCode = VirtualKeyCode::Back;
+
 static CONTROLS: [VirtualKeyCode; 8] = [VirtualKeyCode::Z, VirtualKeyCode::X, VirtualKeyCode::Return, VirtualKeyCode::RShift,
                     VirtualKeyCode::Left, VirtualKeyCode::Right, VirtualKeyCode::Up, VirtualKeyCode::Down];
 
+// Second player's controls in linked mode
+static PLAYER2_CONTROLS: [VirtualKeyCode; 8] = [VirtualKeyCode::F, VirtualKeyCode::R, VirtualKeyCode::Tab, VirtualKeyCode::LShift,
+                    VirtualKeyCode::A, VirtualKeyCode::D, VirtualKeyCode::W, VirtualKeyCode::S];
+
+// F1-F10 load save state slots 1-10, holding shift saves to them
+static SAVE_SLOTS: [VirtualKeyCode; 10] = [VirtualKeyCode::F1, VirtualKeyCode::F2, VirtualKeyCode::F3, VirtualKeyCode::F4, VirtualKeyCode::F5,
+                    VirtualKeyCode::F6, VirtualKeyCode::F7, VirtualKeyCode::F8, VirtualKeyCode::F9, VirtualKeyCode::F10];
+
+static LAYER_TOGGLES: [VirtualKeyCode; 4] = [VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3, VirtualKeyCode::Key4];
+
+// [ and ] select an OAM entry, 5 hides or shows it and 6 shows every entry again
+static SPRITE_TOGGLES: [VirtualKeyCode; 4] = [VirtualKeyCode::LBracket, VirtualKeyCode::RBracket, VirtualKeyCode::Key5, VirtualKeyCode::Key6];
+const OAM_ENTRIES: usize = 40;
+
 fn control(key: VirtualKeyCode) -> JoypadButton {
     match key {
         VirtualKeyCode::Z => JoypadButton::A,
@@ -32,16 +59,238 @@ fn control(key: VirtualKeyCode) -> JoypadButton {
     }
 }
 
+fn player2_control(key: VirtualKeyCode) -> JoypadButton {
+    match key {
+        VirtualKeyCode::F => JoypadButton::A,
+        VirtualKeyCode::R => JoypadButton::B,
+        VirtualKeyCode::Tab => JoypadButton::Start,
+        VirtualKeyCode::LShift => JoypadButton::Select,
+        VirtualKeyCode::A => JoypadButton::Left,
+        VirtualKeyCode::D => JoypadButton::Right,
+        VirtualKeyCode::W => JoypadButton::Up,
+        VirtualKeyCode::S => JoypadButton::Down,
+        _ => unreachable!("invalid player 2 control keycode")
+    }
+}
+
+// Slots are kept next to the ROM, e.g. game.ss1 for slot 1
+fn save_slot_path(cart_path: &Path, slot: usize) -> PathBuf {
+    cart_path.with_extension(format!("ss{}", slot))
+}
+
+fn save_to_slot(gameboy: &Gameboy, cart_path: &Path, slot: usize) {
+    let path = save_slot_path(cart_path, slot);
+    match fs::write(&path, gameboy.save_state()) {
+        Ok(()) => println!("Saved state to slot {}", slot),
+        Err(e) => eprintln!("Failed to save state to {}: {}", path.display(), e),
+    }
+}
+
+fn load_from_slot(gameboy: &mut Gameboy, cart_path: &Path, slot: usize) {
+    let path = save_slot_path(cart_path, slot);
+    match fs::read(&path).and_then(|data| gameboy.load_state(&data)) {
+        Ok(()) => println!("Loaded state from slot {}", slot),
+        Err(e) => eprintln!("Failed to load state from {}: {}", path.display(), e),
+    }
+}
+
+// Input for the (single) Gameboy is being recorded to, or played back from, a movie
+enum MovieMode {
+    Recording(MovieRecorder, PathBuf),
+    Playing(MoviePlayer),
+}
+
+// A single Gameboy, two connected by a link cable and shown side by side, or one
+// linked to another gb7 process over the network. The network link is dropped if it
+// fails, leaving the Gameboy running with nothing plugged in.
+enum Emulator {
+    Single(Gameboy),
+    Linked(Box<LinkCable>),
+    Networked(Gameboy, Option<TcpLink>),
+}
+
+impl Emulator {
+    fn gameboys(&self) -> &[Gameboy] {
+        match self {
+            Emulator::Single(gameboy) | Emulator::Networked(gameboy, _) => std::slice::from_ref(gameboy),
+            Emulator::Linked(link) => &link.gameboys,
+        }
+    }
+
+    fn gameboys_mut(&mut self) -> &mut [Gameboy] {
+        match self {
+            Emulator::Single(gameboy) | Emulator::Networked(gameboy, _) => std::slice::from_mut(gameboy),
+            Emulator::Linked(link) => &mut link.gameboys,
+        }
+    }
+
+    fn execute_frame(&mut self) {
+        match self {
+            Emulator::Single(gameboy) => gameboy.execute_frame(),
+            Emulator::Linked(link) => link.execute_frame(),
+            Emulator::Networked(gameboy, tcp_link) => match tcp_link {
+                Some(link) => {
+                    if let Err(e) = link.execute_frame(gameboy) {
+                        eprintln!("Link cable failed, continuing unplugged: {}", e);
+                        gameboy.serial.connect(Box::new(Disconnected));
+                        *tcp_link = None;
+                    }
+                }
+                None => gameboy.execute_frame(),
+            },
+        }
+    }
+
+    fn draw(&self, frame: &mut [u8]) {
+        match self {
+            Emulator::Single(gameboy) => match &gameboy.sgb {
+                Some(sgb) => frame.copy_from_slice(sgb.frame()),
+                None => frame.copy_from_slice(gameboy.lcd.frame()),
+            },
+            Emulator::Networked(gameboy, _) => frame.copy_from_slice(gameboy.lcd.frame()),
+            Emulator::Linked(link) => {
+                // Interleave the rows of both screens
+                let row_len = lcd::LCD_WIDTH * 4;
+                for (row, out) in frame.chunks_exact_mut(row_len * 2).enumerate() {
+                    for (half, gameboy) in out.chunks_exact_mut(row_len).zip(link.gameboys.iter()) {
+                        half.copy_from_slice(&gameboy.lcd.frame()[row * row_len..(row + 1) * row_len]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Filter the LCD and, in SGB mode, the composed SGB frame around it
+fn set_post_process(gameboy: &mut Gameboy, color_correction: Option<ColorCorrection>, ghosting: Option<f32>) {
+    gameboy.lcd.set_post_process(PostProcess::new(color_correction, ghosting));
+    if let Some(sgb) = &mut gameboy.sgb {
+        sgb.set_post_process(PostProcess::new(color_correction, ghosting));
+    }
+}
+
+fn trace_logger(gameboy: &mut Gameboy) -> Option<&mut TraceLogger> {
+    let tracer = gameboy.tracer.as_mut()?.as_mut() as &mut dyn Any;
+    tracer.downcast_mut()
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let cart_path = Path::new(&args[1]);
+    let sgb_mode = args.iter().any(|arg| arg == "--sgb");
+    // A second cartridge starts two-player mode, with both Gameboys linked
+    let cart_args: Vec<&String> = args.iter().skip(1).filter(|arg| !arg.starts_with("--")).collect();
+    let cart_path = PathBuf::from(cart_args.first().expect("no cartridge path given"));
     let cartridge = cartridge::load_from_path(&cart_path);
 
-    let mut gameboy = Gameboy::new_dmg(cartridge);
+    // Or link to another gb7 process, one side listening and the other connecting
+    let link_host = args.iter().find_map(|arg| arg.strip_prefix("--link-host="));
+    let link_connect = args.iter().find_map(|arg| arg.strip_prefix("--link-connect="));
+    let tcp_link = match (link_host, link_connect) {
+        (Some(addr), _) => {
+            println!("Waiting for link connection on {}", addr);
+            Some(TcpLink::host(addr).expect("failed to host link cable"))
+        }
+        (None, Some(addr)) => Some(TcpLink::connect(addr).expect("failed to connect link cable")),
+        (None, None) => None,
+    };
+
+    let mut emulator = match (cart_args.get(1), sgb_mode) {
+        (Some(second_arg), _) => {
+            let second_cartridge = cartridge::load_from_path(Path::new(second_arg));
+            Emulator::Linked(Box::new(LinkCable::new(Gameboy::new_dmg(cartridge), Gameboy::new_dmg(second_cartridge))))
+        }
+        (None, _) if tcp_link.is_some() => {
+            let link = tcp_link.unwrap();
+            let mut gameboy = Gameboy::new_dmg(cartridge);
+            link.attach(&mut gameboy);
+            Emulator::Networked(gameboy, Some(link))
+        }
+        (None, true) => Emulator::Single(Gameboy::new_sgb(cartridge)),
+        (None, false) => Emulator::Single(Gameboy::new_dmg(cartridge)),
+    };
+    // Time each memory access within instructions, slower but needed by some games and tests
+    if args.iter().any(|arg| arg == "--accurate") {
+        for gameboy in emulator.gameboys_mut() {
+            gameboy.cycle_accurate = true;
+        }
+    }
+    // Log every instruction the (first) Gameboy executes, for gameboy-doctor by default
+    if let Some(trace_path) = args.iter().find_map(|arg| arg.strip_prefix("--trace=")) {
+        let format = match args.iter().find_map(|arg| arg.strip_prefix("--trace-format=")) {
+            Some("rich") => TraceFormat::Rich,
+            Some("doctor") | None => TraceFormat::Doctor,
+            Some(format) => panic!("unknown trace format {}, expected doctor or rich", format),
+        };
+        let logger = TraceLogger::create(Path::new(trace_path), format).expect("failed to create trace log");
+        emulator.gameboys_mut()[0].tracer = Some(Box::new(logger));
+    }
+    if args.iter().any(|arg| arg == "--stub-ly") {
+        for gameboy in emulator.gameboys_mut() {
+            gameboy.stub_ly = true;
+        }
+    }
+    // Plug a Game Boy Printer into the link port, saving prints to the given directory
+    if let Some(printer_dir) = args.iter().find_map(|arg| arg.strip_prefix("--printer=")) {
+        match &mut emulator {
+            Emulator::Single(gameboy) => gameboy.serial.connect(Box::new(Printer::new(printer_dir))),
+            _ => panic!("--printer can't be used while the link port is connected to another Gameboy"),
+        }
+    }
+
+    // Start from a save state slot
+    if let Some(slot) = args.iter().find_map(|arg| arg.strip_prefix("--state=")) {
+        let slot = slot.parse().expect("--state expects a slot number");
+        match &mut emulator {
+            Emulator::Single(gameboy) => load_from_slot(gameboy, &cart_path, slot),
+            _ => panic!("--state can't be used while linked to another Gameboy"),
+        }
+    }
+    // Record input to a movie from power on, or from the --state slot, or play one back
+    let record_path = args.iter().find_map(|arg| arg.strip_prefix("--record="));
+    let play_path = args.iter().find_map(|arg| arg.strip_prefix("--play="));
+    let mut movie = match (&mut emulator, record_path, play_path) {
+        (_, None, None) => None,
+        (Emulator::Single(gameboy), Some(path), None) => {
+            let start = match args.iter().any(|arg| arg.starts_with("--state=")) {
+                true => MovieStart::SaveState,
+                false => MovieStart::PowerOn,
+            };
+            Some(MovieMode::Recording(MovieRecorder::new(gameboy, start), PathBuf::from(path)))
+        }
+        (Emulator::Single(gameboy), None, Some(path)) => {
+            let player = Movie::load(Path::new(path)).and_then(|movie| MoviePlayer::new(movie, gameboy));
+            Some(MovieMode::Playing(player.unwrap_or_else(|e| panic!("failed to play movie {}: {}", path, e))))
+        }
+        (Emulator::Single(_), Some(_), Some(_)) => panic!("--record and --play can't be used together"),
+        _ => panic!("movies can't be used while linked to another Gameboy"),
+    };
+
+    // Rewind history, captured every N frames and stepped back M captures per frame shown
+    // while rewinding, within a budget in MiB
+    let numeric_arg = |name: &str, default: u32| {
+        args.iter()
+            .find_map(|arg| arg.strip_prefix(name))
+            .map_or(default, |val| val.parse().unwrap_or_else(|_| panic!("{} expects a number", name)))
+    };
+    let rewind_interval = numeric_arg("--rewind-interval=", 4);
+    let rewind_speed = numeric_arg("--rewind-speed=", 1);
+    let rewind_budget = numeric_arg("--rewind-budget=", 64) as usize * 1024 * 1024;
+    let mut rewind = match &emulator {
+        Emulator::Single(_) => Some(Rewind::new(rewind_interval, rewind_budget)),
+        _ => None,
+    };
+    let mut rewinding = false;
+
+    let (width, height) = match &emulator {
+        Emulator::Linked(_) => (WIDTH * 2, HEIGHT),
+        Emulator::Networked(..) => (WIDTH, HEIGHT),
+        Emulator::Single(_) if sgb_mode => (SGB_WIDTH, SGB_HEIGHT),
+        Emulator::Single(_) => (WIDTH, HEIGHT),
+    };
 
     let event_loop = EventLoop::new();
     let window = {
-        let size = LogicalSize::new(WIDTH as f64 * 3f64, HEIGHT as f64 * 3f64);
+        let size = LogicalSize::new(width as f64 * 3f64, height as f64 * 3f64);
         WindowBuilder::new()
             .with_title("gb7")
             .with_inner_size(size)
@@ -53,25 +302,48 @@ fn main() {
     let mut pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        Pixels::new(WIDTH, HEIGHT, surface_texture).unwrap()
+        Pixels::new(width, height, surface_texture).unwrap()
     };
 
     let active_target_fps: u32 = TARGET_FPS;
     let target_frame_duration: Duration = Duration::from_secs(1) / active_target_fps;
     let mut turbo_enabled: bool = false;
+    let mut palette_index: usize = 0;
+    let color_corrections = [None, Some(ColorCorrection::CGB), Some(ColorCorrection::GBA)];
+    let mut color_correction_index: usize = 0;
+    let mut ghosting_enabled: bool = false;
+    let mut debug_windows: Option<DebugWindows> = None;
+    let mut modifiers = ModifiersState::empty();
+    // Shown in the title once any of the sprite toggles are used
+    let mut selected_sprite: Option<usize> = None;
 
-    event_loop.run(move |main_event, _, control_flow| {
+    event_loop.run(move |main_event, window_target, control_flow| {
         // Handle input events
         match main_event {
+            Event::WindowEvent { ref event, window_id } if debug_windows.as_ref().is_some_and(|d| d.owns(window_id)) => {
+                if let Some(debug_windows) = &mut debug_windows {
+                    debug_windows.handle_event(window_id, event);
+                }
+            },
             Event::WindowEvent { ref event, .. } => {
                 match event {
                     WindowEvent::KeyboardInput { input, .. } => {
                         if let Some(keycode) = input.virtual_keycode {
-                            if CONTROLS.contains(&keycode) {
+                            if CONTROLS.contains(&keycode) && matches!(movie, Some(MovieMode::Playing(_))) {
+                                // The movie provides the input
+                            } else if CONTROLS.contains(&keycode) {
+                                let gameboy = &mut emulator.gameboys_mut()[0];
                                 match input.state {
                                     ElementState::Pressed => gameboy.joypad.press(control(keycode)),
                                     ElementState::Released => gameboy.joypad.release(control(keycode)),
                                 }
+                            } else if let (true, Some(gameboy)) = (PLAYER2_CONTROLS.contains(&keycode), emulator.gameboys_mut().get_mut(1)) {
+                                match input.state {
+                                    ElementState::Pressed => gameboy.joypad.press(player2_control(keycode)),
+                                    ElementState::Released => gameboy.joypad.release(player2_control(keycode)),
+                                }
+                            } else if keycode == REWIND_KEY {
+                                rewinding = input.state == ElementState::Pressed;
                             } else if keycode == VirtualKeyCode::Grave {
                                 match input.state {
                                     ElementState::Pressed => {
@@ -81,9 +353,68 @@ fn main() {
                                         turbo_enabled = false;
                                     }
                                 }
+                            } else if keycode == VirtualKeyCode::P && input.state == ElementState::Pressed {
+                                // Cycle through the built-in palettes
+                                palette_index = (palette_index + 1) % Palette::BUILTIN.len();
+                                for gameboy in emulator.gameboys_mut() {
+                                    gameboy.lcd.set_palette(Palette::BUILTIN[palette_index]);
+                                }
+                            } else if keycode == VirtualKeyCode::C && input.state == ElementState::Pressed {
+                                // Cycle through colour correction modes
+                                color_correction_index = (color_correction_index + 1) % color_corrections.len();
+                                for gameboy in emulator.gameboys_mut() {
+                                    set_post_process(gameboy, color_corrections[color_correction_index], ghosting_enabled.then_some(0.5));
+                                }
+                            } else if keycode == VirtualKeyCode::G && input.state == ElementState::Pressed {
+                                // Toggle LCD ghosting
+                                ghosting_enabled = !ghosting_enabled;
+                                for gameboy in emulator.gameboys_mut() {
+                                    set_post_process(gameboy, color_corrections[color_correction_index], ghosting_enabled.then_some(0.5));
+                                }
+                            } else if LAYER_TOGGLES.contains(&keycode) && input.state == ElementState::Pressed {
+                                // Toggle rendering of individual PPU layers
+                                let sgb_screen = emulator.gameboys()[0].sgb.is_some();
+                                let debug = &mut emulator.gameboys_mut()[0].ppu.debug;
+                                match keycode {
+                                    VirtualKeyCode::Key1 => debug.hide_background = !debug.hide_background,
+                                    VirtualKeyCode::Key2 => debug.hide_window = !debug.hide_window,
+                                    VirtualKeyCode::Key3 => debug.hide_sprites = !debug.hide_sprites,
+                                    // The SGB colours the screen from the PPU's shades, which don't carry the tint
+                                    VirtualKeyCode::Key4 if sgb_screen => (),
+                                    VirtualKeyCode::Key4 => debug.tint_layers = !debug.tint_layers,
+                                    _ => unreachable!("invalid layer toggle keycode"),
+                                }
+                            } else if SPRITE_TOGGLES.contains(&keycode) && input.state == ElementState::Pressed {
+                                // Hide individual OAM entries
+                                let debug = &mut emulator.gameboys_mut()[0].ppu.debug;
+                                let selected = selected_sprite.get_or_insert(0);
+                                match keycode {
+                                    VirtualKeyCode::LBracket => *selected = (*selected + OAM_ENTRIES - 1) % OAM_ENTRIES,
+                                    VirtualKeyCode::RBracket => *selected = (*selected + 1) % OAM_ENTRIES,
+                                    VirtualKeyCode::Key5 => debug.hidden_sprites ^= 1 << *selected,
+                                    VirtualKeyCode::Key6 => debug.hidden_sprites = 0,
+                                    _ => unreachable!("invalid sprite toggle keycode"),
+                                }
+                            } else if SAVE_SLOTS.contains(&keycode) && input.state == ElementState::Pressed {
+                                let slot = SAVE_SLOTS.iter().position(|&key| key == keycode).unwrap() + 1;
+                                match &mut emulator {
+                                    Emulator::Single(gameboy) if modifiers.shift() => save_to_slot(gameboy, &cart_path, slot),
+                                    Emulator::Single(_) if movie.is_some() => eprintln!("Save states can't be loaded during a movie"),
+                                    Emulator::Single(gameboy) => load_from_slot(gameboy, &cart_path, slot),
+                                    _ => eprintln!("Save states aren't supported while linked to another Gameboy"),
+                                }
+                            } else if keycode == VirtualKeyCode::F12 && input.state == ElementState::Pressed {
+                                // Toggle the VRAM/OAM debug windows
+                                debug_windows = match debug_windows {
+                                    Some(_) => None,
+                                    None => Some(DebugWindows::open(window_target, &emulator.gameboys()[0])),
+                                };
                             }
                         }
                     },
+                    WindowEvent::ModifiersChanged(state) => {
+                        modifiers = *state;
+                    }
                     WindowEvent::Resized(size) => {
                         pixels.resize_surface(size.width, size.height)
                     }
@@ -97,10 +428,43 @@ fn main() {
             Event::MainEventsCleared => {
                 let frame_start = Instant::now();
 
-                // Execute one gameboy frame
-                match turbo_enabled {
-                    true => (0..10).for_each(|_| gameboy.execute_frame()),
-                    false => gameboy.execute_frame()
+                match (&mut rewind, &mut emulator) {
+                    (Some(rewind), Emulator::Single(gameboy)) if rewinding && movie.is_none() => {
+                        for _ in 0..rewind_speed {
+                            if let Err(e) = rewind.rewind(gameboy) {
+                                eprintln!("Failed to rewind: {}", e);
+                                rewind.clear();
+                            }
+                        }
+                    }
+                    _ => {
+                        // Execute one gameboy frame
+                        let frames = if turbo_enabled { 10 } else { 1 };
+                        for _ in 0..frames {
+                            match (&mut movie, &mut emulator) {
+                                (Some(MovieMode::Recording(recorder, _)), Emulator::Single(gameboy)) => recorder.execute_frame(gameboy),
+                                (Some(MovieMode::Playing(player)), Emulator::Single(gameboy)) => {
+                                    if !player.execute_frame(gameboy) {
+                                        // Hand control back to the keyboard, with nothing held
+                                        println!("Movie ended after {} frames", player.frame());
+                                        gameboy.joypad.set_pressed(0);
+                                        movie = None;
+                                        gameboy.execute_frame();
+                                    }
+                                }
+                                _ => emulator.execute_frame(),
+                            }
+                            if let (Some(rewind), Emulator::Single(gameboy)) = (&mut rewind, &emulator) {
+                                rewind.frame(gameboy);
+                            }
+                        }
+
+                        for gameboy in emulator.gameboys_mut() {
+                            if let Some(e) = gameboy.serial.device_mut::<Printer>().and_then(Printer::take_error) {
+                                eprintln!("Printer: {}", e);
+                            }
+                        }
+                    }
                 }
 
                 // Wait to conserve framerate
@@ -110,17 +474,58 @@ fn main() {
                 let fps = 1e9f64 / (elapsed_time.as_nanos() as f64);
 
 
-                window.set_title(format!("gb7 - FPS: {:.2}", min(active_target_fps, fps as u32)).as_str());
+                let movie_status = match &movie {
+                    Some(MovieMode::Recording(recorder, _)) => format!(" - Recording frame {}", recorder.frame()),
+                    Some(MovieMode::Playing(player)) => format!(" - Movie frame {}/{}", player.frame(), player.movie().len()),
+                    None => String::new(),
+                };
+                let sprite_status = match selected_sprite {
+                    Some(entry) => {
+                        let hidden = emulator.gameboys()[0].ppu.debug.hidden_sprites & (1 << entry) != 0;
+                        format!(" - OAM entry {}{}", entry, if hidden { " (hidden)" } else { "" })
+                    }
+                    None => String::new(),
+                };
+                window.set_title(format!("gb7 - FPS: {:.2}{}{}", min(active_target_fps, fps as u32), movie_status, sprite_status).as_str());
 
                 if target_frame_duration > elapsed_time {
                     *control_flow = ControlFlow::WaitUntil(frame_start + target_frame_duration);
                 }
 
+                // Stop once the trace log can't be written, the error is reported on exit
+                if emulator.gameboys_mut().iter_mut().any(|gameboy| trace_logger(gameboy).is_some_and(|logger| logger.error().is_some())) {
+                    *control_flow = ControlFlow::ExitWithCode(1);
+                }
+
+                if let Some(debug_windows) = &mut debug_windows {
+                    debug_windows.redraw(&emulator.gameboys()[0]);
+                }
+
                 window.request_redraw()
             },
+            Event::LoopDestroyed => {
+                // The process exits without dropping the emulator, so flush trace logs now
+                let mut trace_failed = false;
+                for gameboy in emulator.gameboys_mut() {
+                    if let Some(Err(e)) = trace_logger(gameboy).map(TraceLogger::finish) {
+                        eprintln!("Failed to write trace log: {}", e);
+                        trace_failed = true;
+                    }
+                    gameboy.tracer = None;
+                }
+                if let Some(MovieMode::Recording(recorder, path)) = &movie {
+                    match recorder.movie().save(path) {
+                        Ok(()) => println!("Saved {} frame movie to {}", recorder.frame(), path.display()),
+                        Err(e) => eprintln!("Failed to save movie to {}: {}", path.display(), e),
+                    }
+                }
+                if trace_failed {
+                    std::process::exit(1);
+                }
+            }
             Event::RedrawRequested(_) => {
                 // Draw the current frame to screen
-                draw_lcd(&gameboy.lcd, pixels.get_frame_mut());
+                emulator.draw(pixels.get_frame_mut());
                 if pixels
                     .render()
                     .map_err(|e| panic!("pixels.render() failed: {}", e))
@@ -134,17 +539,3 @@ fn main() {
         }
     });
 }
-
-fn draw_lcd(lcd: &Lcd, frame: &mut [u8]) {
-    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-        let c = match lcd.pixels[i] {
-            3 => [0, 0, 0, 255],
-            2 => [100, 100, 100, 255],
-            1 => [175, 175, 175, 255],
-            0 => [255, 255, 255, 255],
-            _ => panic!("invalid color code"),
-        };
-
-        pixel.copy_from_slice(&c);
-    }
-}